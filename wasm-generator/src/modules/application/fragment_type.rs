@@ -8,6 +8,8 @@ pub enum RustItemType {
     Function,
     #[serde(rename = "struct")]
     Struct,
+    #[serde(rename = "enum")]
+    Enum,
     #[serde(rename = "impl")]
     Impl,
     #[serde(rename = "type")]
@@ -18,6 +20,8 @@ pub enum RustItemType {
     Const,
     #[serde(rename = "use")]
     Use,
+    #[serde(rename = "module")]
+    Module,
 }
 
 impl fmt::Display for RustItemType {
@@ -25,11 +29,13 @@ impl fmt::Display for RustItemType {
         match *self {
             RustItemType::Function => write!(f, "function"),
             RustItemType::Struct => write!(f, "struct"),
+            RustItemType::Enum => write!(f, "enum"),
             RustItemType::Impl => write!(f, "impl"),
             RustItemType::Type => write!(f, "type"),
             RustItemType::Static => write!(f, "static"),
             RustItemType::Const => write!(f, "const"),
             RustItemType::Use => write!(f, "use"),
+            RustItemType::Module => write!(f, "module"),
         }
     }
 }
@@ -1,5 +1,6 @@
 use crate::modules::application::fragment_type::RustItemType;
 use crate::modules::application::traits::fragment::Fragment;
+use crate::modules::application::FragmentTest;
 use crate::modules::cfd_analyzer::CodeFragmentDescription;
 use crate::modules::source_code_analyzer::attribute_parser::AttributeParser;
 use crate::modules::source_code_analyzer::cargo_toml::ProjectCargoToml;
@@ -20,6 +21,12 @@ pub struct FunctionFragment {
     pub fragment_type: RustItemType,
     pub rust_function: RustFunction,
     pub module_hierarchy: Vec<String>,
+    /// Descriptions of the mutable global state in the resolved code, whose behaviour depends on where the fragment is executed.
+    pub mutable_global_state: Vec<String>,
+    /// The bundle the fragment is built into along with other fragments, `None` if it is built into its own module
+    pub bundle: Option<String>,
+    /// The tests of the project carried into the crate of the fragment
+    pub tests: Vec<FragmentTest>,
 }
 
 impl FunctionFragment {
@@ -41,8 +48,11 @@ impl FunctionFragment {
                     crates: attribute_parser.crates.unwrap_or_default(),
                     dependencies: attribute_parser.dependencies.clone().unwrap_or_default(),
                     module_hierarchy,
+                    mutable_global_state: Vec::new(),
+                    bundle: None,
+                    tests: Vec::new(),
                     fragment_type: RustItemType::Function,
-                    rust_function: rust_function,
+                    rust_function,
                 };
                 Some(_self)
             }
@@ -70,8 +80,11 @@ impl FunctionFragment {
                 .unwrap_or_default()
                 .into_iter()
                 .collect(),
-            rust_function: rust_function,
+            rust_function,
             module_hierarchy,
+            mutable_global_state: Vec::new(),
+            bundle: cfd.bundle.clone(),
+            tests: Vec::new(),
             fragment_type: RustItemType::Function,
         }
     }
@@ -113,6 +126,14 @@ impl Fragment for FunctionFragment {
         self.fragment_type.clone()
     }
 
+    fn get_execution_location(&self) -> ExecutionLocation {
+        self.initial_execution_location.clone()
+    }
+
+    fn set_mutable_global_state(&mut self, mutable_global_state: Vec<String>) {
+        self.mutable_global_state = mutable_global_state;
+    }
+
     fn get_module_hierarchy(&self) -> Vec<String> {
         self.module_hierarchy.clone()
     }
@@ -127,6 +148,14 @@ impl Fragment for FunctionFragment {
         self.dependencies = dependency_ids;
     }
 
+    fn get_bundle(&self) -> Option<String> {
+        self.bundle.clone()
+    }
+
+    fn set_bundle(&mut self, bundle: Option<String>) {
+        self.bundle = bundle;
+    }
+
     fn get_crates(&self) -> Vec<String> {
         self.crates.clone()
     }
@@ -135,6 +164,18 @@ impl Fragment for FunctionFragment {
         self.crates = crates;
     }
 
+    fn get_tests(&self) -> Vec<FragmentTest> {
+        self.tests.clone()
+    }
+
+    fn set_tests(&mut self, tests: Vec<FragmentTest>) {
+        self.tests = tests;
+    }
+
+    fn get_functions(&self) -> Vec<RustFunction> {
+        vec![self.rust_function.clone()]
+    }
+
     fn get_package_name(&self) -> String {
         self.id.clone()
     }
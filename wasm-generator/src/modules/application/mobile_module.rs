@@ -0,0 +1,189 @@
+use crate::modules::application::fragment_type::RustItemType;
+use crate::modules::application::function_fragment::{ExecutionLocation, FunctionFragment};
+use crate::modules::application::object_fragment::ObjectFragment;
+use crate::modules::application::traits::fragment::Fragment;
+use crate::modules::application::MobileFragments;
+use crate::modules::cfd_analyzer::CodeFragmentDescription;
+use crate::modules::source_code_analyzer::attribute_parser::AttributeParser;
+use crate::modules::source_code_analyzer::rust_file::RustFile;
+use crate::modules::source_code_analyzer::types::RustItemCommonProperties;
+use std::collections::HashSet;
+
+/// A module marked as mobile, either with the `@mobile` annotation or in the CFD.
+#[derive(Debug, Clone)]
+pub struct MobileModule {
+    /// The path of the module relative to the crate root
+    pub module_path: Vec<String>,
+    pub description: CodeFragmentDescription,
+}
+
+impl MobileModule {
+    /// Returns the mobile module declared by a `mod` item, if it is annotated with `@mobile`.
+    pub fn try_create_from_attributes(
+        attrs: Vec<syn::Attribute>,
+        module_path: Vec<String>,
+    ) -> Option<Self> {
+        let attribute_parser = AttributeParser::new(attrs);
+        if !attribute_parser.mobile_annotation_exists() {
+            return None;
+        }
+        let initial_execution_location = match attribute_parser.initial_execution_location {
+            Some(ExecutionLocation::Server) => "server",
+            _ => "client",
+        };
+        let description = CodeFragmentDescription {
+            id: attribute_parser.id,
+            name: module_path.last().cloned().unwrap_or_default(),
+            initial_execution_location: initial_execution_location.to_string(),
+            crates: attribute_parser.crates,
+            dependencies: attribute_parser.dependencies,
+            item_type: Some(RustItemType::Module),
+            ..Default::default()
+        };
+        Some(Self {
+            module_path,
+            description,
+        })
+    }
+
+    /// Adds a fragment for every public function and every inherent impl declared directly in the module.
+    /// The fragments inherit the settings of the module and are built into one bundle, which is named after the module
+    /// unless the module is assigned to a bundle itself. Items that are already mobile on their own are left as they are.
+    /// Returns the number of added fragments.
+    pub fn expand(&self, rust_files: &[RustFile], mobile_fragments: &mut MobileFragments) -> usize {
+        let existing_items: Vec<RustItemCommonProperties> = mobile_fragments
+            .functions
+            .iter()
+            .map(|fragment| fragment.get_common_properties())
+            .chain(
+                mobile_fragments
+                    .impls
+                    .iter()
+                    .map(|fragment| fragment.get_common_properties()),
+            )
+            .collect();
+        let mut ids = HashSet::new();
+        let mut added_fragments = 0;
+
+        for rust_file in rust_files {
+            for rust_function in rust_file.functions.iter().filter(|function| {
+                function.is_public && self.contains(&function.properties, &existing_items)
+            }) {
+                let description = self.describe_item(&rust_function.properties.name, &mut ids);
+                mobile_fragments
+                    .functions
+                    .push(FunctionFragment::create_from_cfd(
+                        rust_function.clone(),
+                        &description,
+                        rust_function.properties.module_hierarchy.clone(),
+                    ));
+                added_fragments += 1;
+            }
+            for rust_impl in rust_file.impls.iter().filter(|rust_impl| {
+                self.contains(&rust_impl.properties, &existing_items)
+                    && is_inherent_impl(&rust_impl.properties.code)
+            }) {
+                let description = self.describe_item(&rust_impl.properties.name, &mut ids);
+                mobile_fragments.impls.push(ObjectFragment::create_from_cfd(
+                    rust_impl.clone(),
+                    &description,
+                    rust_impl.properties.module_hierarchy.clone(),
+                ));
+                added_fragments += 1;
+            }
+        }
+        added_fragments
+    }
+
+    fn contains(
+        &self,
+        properties: &RustItemCommonProperties,
+        existing_items: &[RustItemCommonProperties],
+    ) -> bool {
+        properties.module_hierarchy == self.module_path && !existing_items.contains(properties)
+    }
+
+    // The ids of the items are derived from the id of the module, items without it are assigned ids like other fragments
+    fn describe_item(&self, item_name: &str, ids: &mut HashSet<String>) -> CodeFragmentDescription {
+        let id = self.description.id.as_ref().map(|module_id| {
+            let mut id = format!("{}_{}", module_id, item_name);
+            let mut counter = 1;
+            while ids.contains(&id) {
+                id = format!("{}_{}{}", module_id, item_name, counter);
+                counter += 1;
+            }
+            ids.insert(id.clone());
+            id
+        });
+        let bundle = self
+            .description
+            .bundle
+            .clone()
+            .or_else(|| self.module_path.last().map(|name| name.to_lowercase()));
+        CodeFragmentDescription {
+            id,
+            name: item_name.to_string(),
+            bundle,
+            item_type: None,
+            ..self.description.clone()
+        }
+    }
+}
+
+fn is_inherent_impl(code: &str) -> bool {
+    syn::parse_str::<syn::ItemImpl>(code).is_ok_and(|item_impl| item_impl.trait_.is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::source_code_analyzer::ast_visitor::AstVisitor;
+    use crate::modules::source_code_analyzer::FilePath;
+    use syn::visit::Visit;
+
+    #[test]
+    fn test_expand_mobile_module() {
+        let source_code = r#"
+/// @mobile id = "shop", initial_execution_location = "server"
+pub mod shop {
+    pub struct Cart { pub items: Vec<u32> }
+    impl Cart { pub fn total(&self) -> u32 { self.items.iter().sum() } }
+    impl Default for Cart { fn default() -> Self { Self { items: Vec::new() } } }
+    pub fn checkout(cart: Cart) -> u32 { cart.total() }
+    fn discount(total: u32) -> u32 { total / 10 }
+    /// @mobile id = "refund"
+    pub fn refund(total: u32) -> u32 { total }
+}
+pub fn main_page() {}
+"#;
+        let mut mobile_fragments = MobileFragments::default();
+        let mut ast_visitor = AstVisitor::new(
+            FilePath::from("src/lib.rs".to_string()),
+            source_code.to_string(),
+            &mut mobile_fragments,
+        );
+        ast_visitor.visit_file(&syn::parse_file(source_code).unwrap());
+        let mobile_modules = ast_visitor.mobile_modules.clone();
+        let rust_files = vec![ast_visitor.rust_file];
+
+        assert_eq!(mobile_modules.len(), 1);
+        assert_eq!(mobile_modules[0].module_path, vec!["shop".to_string()]);
+        assert_eq!(
+            mobile_modules[0].expand(&rust_files, &mut mobile_fragments),
+            2
+        );
+
+        let ids: Vec<_> = mobile_fragments
+            .functions
+            .iter()
+            .map(|f| f.get_id())
+            .collect();
+        assert_eq!(ids, vec!["refund", "shop_checkout"]);
+        assert_eq!(mobile_fragments.impls.len(), 1);
+        let cart = &mobile_fragments.impls[0];
+        assert_eq!(cart.get_id(), "shop_Cart");
+        assert_eq!(cart.get_bundle(), Some("shop".to_string()));
+        assert_eq!(cart.initial_execution_location, ExecutionLocation::Server);
+        assert_eq!(mobile_fragments.functions[0].get_bundle(), None);
+    }
+}
@@ -1,23 +1,31 @@
+use std::collections::BTreeMap;
 use std::io::Write;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
-use derive_new::new;
+use log::error;
 use serde_derive::{Deserialize, Serialize};
 
 use crate::modules::application::function_fragment::{ExecutionLocation, FunctionFragment};
 use crate::modules::application::object_fragment::ObjectFragment;
-use crate::modules::configuration::Configuration;
+use crate::modules::application::traits::fragment::Fragment;
+use crate::modules::configuration::{Command, Configuration};
 use crate::modules::constants::TEMP_PATH;
+use crate::modules::dependency_resolver::dependency_graph;
+use crate::modules::fragment_generator::bundle_generator::get_bundle_package_name;
+use crate::modules::fragment_generator::wasm_wrapper_generator::get_bundle_export_prefix;
+use crate::modules::source_code_analyzer::rust_file::RustFile;
+use crate::modules::source_code_analyzer::types::rust_function::{ReceiverKind, RustFunction};
 use crate::modules::util::file_handler::DirectoryContext;
 use crate::modules::{
-    cfd_analyzer, dependency_resolver, fragment_compiler, fragment_generator,
+    cfd_analyzer, dependency_resolver, fragment_compiler, fragment_generator, portability_analyzer,
     post_compilation_processor, source_code_analyzer, util,
 };
 
 pub(crate) mod fragment_type;
 pub mod function_fragment;
+pub mod mobile_module;
 pub mod object_fragment;
 pub mod traits;
 
@@ -27,6 +35,11 @@ pub async fn run() {
     let config = Arc::new(Configuration::default());
     init_logger();
 
+    if let Some(Command::Why { fragment_id, item }) = &config.command {
+        explain_inclusion(fragment_id, item, &config);
+        return;
+    }
+
     // delete the temp directory if it already exists from previous run
     delete_temporary_directory(&config);
 
@@ -44,24 +57,67 @@ pub async fn run() {
     cfd_analyzer::run(config.clone(), syntax_tree.clone(), &mut mobile_fragments);
 
     fragment_generator::check_duplicate_and_assign_missing_ids(&mut mobile_fragments);
+    fragment_generator::assign_bundles(&mut mobile_fragments, &config);
 
     // run the dependency resolver to resolve all the dependencies of the mobile fragments
     dependency_resolver::run(
         &mut mobile_fragments,
-        syntax_tree,
+        syntax_tree.clone(),
         project_metadata.as_ref().clone(),
         config.clone(),
     )
     .await;
 
+    // check that the resolved code of the mobile fragments can run on their target
+    portability_analyzer::run(&mut mobile_fragments, config.clone());
+
+    // the components are generated from the resolved code, before the wrappers of the pointer protocol are added to it
+    let components = match config.component_model.unwrap_or(false) {
+        true => {
+            fragment_generator::generate_components(&mobile_fragments, &syntax_tree, config.clone())
+        }
+        false => BTreeMap::new(),
+    };
+    let bindgen_crates = match config.wasm_bindgen.unwrap_or(false) {
+        true => fragment_generator::generate_bindgen_crates(&mobile_fragments, config.clone()),
+        false => BTreeMap::new(),
+    };
+
     // modify and write the extracted mobile fragments to disk
     fragment_generator::generate_wasm_wrapper(&mut mobile_fragments);
-    let mut generated_fragments = fragment_generator::generate(&mobile_fragments, config.clone());
-    fragment_generator::generate_js_wrappers(&mobile_fragments, config.clone());
-    fragment_generator::export_fragments_data(&mobile_fragments, config.clone());
+    let mut generated_fragments = fragment_generator::generate(
+        &mobile_fragments,
+        components,
+        bindgen_crates,
+        config.clone(),
+    );
+    fragment_generator::generate_js_wrappers(&mobile_fragments, &syntax_tree, config.clone());
 
     // run the fragment compiler to compile the generated fragments to wasm
     fragment_compiler::run(&mut generated_fragments, config.clone());
+    if config.debug_info.unwrap_or(false) {
+        if let Err(e) =
+            fragment_compiler::source_map::run(&generated_fragments, &syntax_tree, &config)
+        {
+            error!("Error writing source maps: {:?}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // the manifest records the hashes of the compiled modules, so it is written once they are final
+    fragment_generator::export_fragments_data(&mobile_fragments, config.clone());
+
+    if let Some(Command::Verify { cases, seed }) = &config.command {
+        verify_fragments(
+            &mobile_fragments,
+            &generated_fragments,
+            &syntax_tree,
+            &config,
+            *cases,
+            *seed,
+        );
+        return;
+    }
 
     // delete the deployed fragments if they exist from previous run
     delete_deployed_fragments(&config);
@@ -73,6 +129,71 @@ pub async fn run() {
     }
 }
 
+fn explain_inclusion(fragment_id: &str, item: &str, config: &Configuration) {
+    let dependency_graphs_dir = config
+        .dependency_graphs_dir
+        .as_ref()
+        .expect("dependency_graphs_dir must be configured to explain dependencies");
+    if let Err(e) = dependency_graph::explain_inclusion(
+        &PathBuf::from(dependency_graphs_dir),
+        fragment_id,
+        item,
+    ) {
+        error!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Runs the verify command on the compiled fragments and exits with an error if any of them behave differently as wasm.
+#[cfg(feature = "verify")]
+fn verify_fragments(
+    mobile_fragments: &MobileFragments,
+    generated_fragments: &[FinalFragmentContext],
+    rust_files: &[RustFile],
+    config: &Configuration,
+    case_count: usize,
+    seed: u64,
+) {
+    let result = crate::modules::verifier::run(
+        mobile_fragments,
+        generated_fragments,
+        rust_files,
+        config,
+        case_count,
+        seed,
+    );
+    if !config.keep_temp_dir.unwrap_or(false) {
+        delete_temporary_directory(config);
+    }
+    match result {
+        Ok(0) => log::info!("All verified fragments behave the same natively and as wasm"),
+        Ok(mismatch_count) => {
+            error!(
+                "Found {} calls of fragments behaving differently as wasm",
+                mismatch_count
+            );
+            std::process::exit(1);
+        }
+        Err(e) => {
+            error!("Error verifying fragments: {:?}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(feature = "verify"))]
+fn verify_fragments(
+    _mobile_fragments: &MobileFragments,
+    _generated_fragments: &[FinalFragmentContext],
+    _rust_files: &[RustFile],
+    _config: &Configuration,
+    _case_count: usize,
+    _seed: u64,
+) {
+    error!("The verify command requires the wasm-generator to be built with the verify feature");
+    std::process::exit(1);
+}
+
 pub fn delete_temporary_directory(config: &Configuration) {
     // Delete the temporary directory if already exists
     util::file_handler::delete_directory(&PathBuf::from(&config.project.clone()).join(TEMP_PATH))
@@ -102,6 +223,16 @@ pub struct FinalFragmentContext {
     pub directory: DirectoryContext,
     pub fragment_identifier: String,
     pub compilation_data: FragmentCompilationMetric,
+    /// The hash of the generated sources, only computed in reproducible mode
+    pub source_hash: Option<String>,
+    /// The component built next to the core module, only generated in component model mode
+    pub component: Option<FragmentComponentContext>,
+    /// The crate exporting the fragment through wasm-bindgen, only generated for client fragments in wasm-bindgen mode
+    pub bindgen: Option<FragmentBindgenContext>,
+    /// The fragments built into the module, a single one unless the module is a bundle
+    pub sources: Vec<FragmentSource>,
+    /// The tests of the project carried into the crate, only collected when `test_fragments` is enabled
+    pub tests: Vec<FragmentTest>,
 }
 
 impl FinalFragmentContext {
@@ -114,19 +245,190 @@ impl FinalFragmentContext {
     }
 }
 
+/// The location of a fragment in the project, recorded in the metadata of the module it is built into.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Serialize)]
+pub struct FragmentSource {
+    pub id: String,
+    /// The file declaring the fragment, relative to the project
+    pub file: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl FragmentSource {
+    pub fn new(fragment: &dyn Fragment, config: &Configuration) -> Self {
+        let properties = fragment.get_common_properties();
+        let file_path = properties.file_path.get_absolute_path();
+        Self {
+            id: fragment.get_id(),
+            file: file_path
+                .strip_prefix(&config.project)
+                .unwrap_or(&file_path)
+                .to_string_lossy()
+                .to_string(),
+            start_line: properties.position.start_line,
+            end_line: properties.position.end_line,
+        }
+    }
+}
+
+/// A unit test of the project carried into the crate of a fragment, which exports it to be run against the wasm module.
+/// See "Fragment Tests" in the readme.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FragmentTest {
+    /// The path of the test in the project, e.g. `shared::playground4::tests::test_fibonacci`
+    pub name: String,
+    pub export_name: String,
+    /// Whether the test is annotated with `#[should_panic]`, the expected message is not compared
+    pub should_panic: bool,
+}
+
+/// The crate exporting a fragment through a WIT world, which is built to a core module and then wrapped into a component.
+#[derive(Clone, Default, Serialize)]
+pub struct FragmentComponentContext {
+    pub directory: DirectoryContext,
+    /// The core module built from the component crate
+    pub core_module_identifier: String,
+    pub component_identifier: String,
+}
+
+/// The crate exporting a fragment through wasm-bindgen, whose core module is processed by the wasm-bindgen CLI.
+#[derive(Clone, Default, Serialize)]
+pub struct FragmentBindgenContext {
+    pub directory: DirectoryContext,
+    /// The core module built from the bindgen crate
+    pub core_module_identifier: String,
+    /// The name of the JS module and the wasm module generated by wasm-bindgen, without extension
+    pub out_name: String,
+}
+
+/// The version of the fragment manifest, the plain list of fragments written by earlier versions is version 1.
+pub const MANIFEST_VERSION: u32 = 2;
+
+/// The manifest written to `executable_fragments.json`, describing every fragment to the code distributors.
+/// See "Fragment Manifest" in the readme.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Serialize)]
+pub struct FragmentManifest {
+    pub version: u32,
+    pub generator_version: String,
+    pub abi_version: u32,
+    pub fragments: Vec<ExecutableFragmentDataForCodeDistributor>,
+}
+
 /// This struct is used to represent final fragments with minimal information.
 /// It will be later read by the code distributor to get final list of fragments.
-#[derive(Debug, new, Clone, Default, Deserialize, PartialEq, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Serialize)]
 pub struct ExecutableFragmentDataForCodeDistributor {
     pub id: String,
     pub execution_location: ExecutionLocation,
+    /// The locations the fragment may be moved to, which always include its initial execution location
+    #[serde(default)]
+    pub allowed_locations: Vec<ExecutionLocation>,
+    /// Fragments keeping mutable global state behave differently on the client and the server, so they must not be moved.
+    #[serde(default)]
+    pub has_mutable_global_state: bool,
+    /// The wasm module of the bundle the fragment is built into, see "Bundles" in the readme.
+    /// Fragments without a bundle are built into `<id>.wasm`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle: Option<String>,
+    #[serde(default)]
+    pub module: FragmentModule,
+    #[serde(default)]
+    pub functions: Vec<FragmentFunction>,
+    #[serde(default)]
+    pub crates: Vec<String>,
+    #[serde(default)]
+    pub source: FragmentSource,
+}
+
+/// The wasm module a fragment is built into.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Serialize)]
+pub struct FragmentModule {
+    pub file: String,
+    /// The hex encoded SHA-256 hash of the deployed file
+    pub hash: String,
+    pub size: u64,
+    pub target: String,
+    /// The prefix of the exports of the fragment, empty unless the fragment is bundled
+    pub export_prefix: String,
+}
+
+impl FragmentModule {
+    fn new(fragment_id: &str, bundle: Option<&str>) -> Self {
+        Self {
+            file: match bundle {
+                Some(bundle) => format!("{}.wasm", get_bundle_package_name(bundle)),
+                None => format!("{}.wasm", fragment_id),
+            },
+            target: "wasm32-unknown-unknown".to_string(),
+            export_prefix: bundle
+                .map(|_| get_bundle_export_prefix(fragment_id))
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A function exported by a fragment, with the types of its signature as written in the project.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Serialize)]
+pub struct FragmentFunction {
+    pub name: String,
+    /// The export executing the function, without the export prefix of the fragment
+    pub export_name: String,
+    /// `&self`, `&mut self` or `self` for methods, whose instance is passed after the parameters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receiver: Option<String>,
+    pub parameters: Vec<FragmentParameter>,
+    pub return_type: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Serialize)]
+pub struct FragmentParameter {
+    pub name: String,
+    pub rust_type: String,
+}
+
+impl From<&RustFunction> for FragmentFunction {
+    fn from(rust_function: &RustFunction) -> Self {
+        Self {
+            name: rust_function.properties.name.clone(),
+            export_name: format!("execute__{}", rust_function.properties.name),
+            receiver: rust_function
+                .receiver
+                .as_ref()
+                .map(|receiver| match receiver {
+                    ReceiverKind::Shared => "&self".to_string(),
+                    ReceiverKind::Mutable => "&mut self".to_string(),
+                    ReceiverKind::Owned => "self".to_string(),
+                    ReceiverKind::Pointer(pointer) => format!("self: {}<Self>", pointer),
+                    ReceiverKind::Unsupported(receiver_type) => format!("self: {}", receiver_type),
+                }),
+            parameters: rust_function
+                .parameters
+                .iter()
+                .map(|parameter| FragmentParameter {
+                    name: parameter.name.clone(),
+                    rust_type: parameter.rust_type.clone(),
+                })
+                .collect(),
+            return_type: rust_function.return_type.rust_type.clone(),
+        }
+    }
 }
 
 impl From<FunctionFragment> for ExecutableFragmentDataForCodeDistributor {
     fn from(final_fragment: FunctionFragment) -> Self {
         Self {
+            functions: vec![FragmentFunction::from(&final_fragment.rust_function)],
+            crates: final_fragment.crates,
+            module: FragmentModule::new(&final_fragment.id, final_fragment.bundle.as_deref()),
             id: final_fragment.id,
             execution_location: final_fragment.initial_execution_location,
+            has_mutable_global_state: !final_fragment.mutable_global_state.is_empty(),
+            bundle: final_fragment
+                .bundle
+                .map(|bundle| format!("{}.wasm", get_bundle_package_name(&bundle))),
+            ..Default::default()
         }
     }
 }
@@ -134,19 +436,50 @@ impl From<FunctionFragment> for ExecutableFragmentDataForCodeDistributor {
 impl From<ObjectFragment> for ExecutableFragmentDataForCodeDistributor {
     fn from(final_fragment: ObjectFragment) -> Self {
         Self {
+            functions: final_fragment
+                .rust_impl
+                .functions
+                .iter()
+                .map(FragmentFunction::from)
+                .collect(),
+            crates: final_fragment.crates,
+            module: FragmentModule::new(&final_fragment.id, final_fragment.bundle.as_deref()),
             id: final_fragment.id,
             execution_location: final_fragment.initial_execution_location,
+            has_mutable_global_state: !final_fragment.mutable_global_state.is_empty(),
+            bundle: final_fragment
+                .bundle
+                .map(|bundle| format!("{}.wasm", get_bundle_package_name(&bundle))),
+            ..Default::default()
         }
     }
 }
 
+/// The benchmark results written to the benchmarks directory. The fragments are compiled by a single workspace build,
+/// so its duration is recorded once instead of per fragment.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct CompilationMetrics {
+    pub(crate) workspace: WorkspaceCompilationMetric,
+    pub(crate) fragments: Vec<FragmentCompilationMetric>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct WorkspaceCompilationMetric {
+    pub(crate) release_mode: bool,
+    pub(crate) fragment_count: usize,
+    pub(crate) compilation_time: Duration,
+}
+
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct FragmentCompilationMetric {
     pub(crate) fragment_identifier: String,
     pub(crate) release_mode: bool,
     pub(crate) optimization_mode: bool,
     pub(crate) wasm_size: Size,
-    pub(crate) compilation_time: Duration,
+    /// The time spent optimizing the module of the fragment with `wasm-opt`.
+    pub(crate) optimization_time: Duration,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) source_hash: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
@@ -1,12 +1,15 @@
 use crate::modules::application::fragment_type::RustItemType;
 use crate::modules::application::function_fragment::ExecutionLocation;
 use crate::modules::application::traits::fragment::Fragment;
+use crate::modules::application::FragmentTest;
 use crate::modules::cfd_analyzer::CodeFragmentDescription;
 use crate::modules::source_code_analyzer::attribute_parser::AttributeParser;
 use crate::modules::source_code_analyzer::cargo_toml::ProjectCargoToml;
+use crate::modules::source_code_analyzer::types::rust_function::{ReceiverKind, RustFunction};
 use crate::modules::source_code_analyzer::types::rust_impl::RustImpl;
 use crate::modules::source_code_analyzer::types::rust_struct::RustStruct;
 use crate::modules::source_code_analyzer::types::RustItemCommonProperties;
+use log::warn;
 use serde_derive::Serialize;
 use syn::Attribute;
 
@@ -21,6 +24,12 @@ pub struct ObjectFragment {
     pub module_hierarchy: Vec<String>,
     pub rust_struct: RustStruct,
     pub rust_impl: RustImpl,
+    /// Descriptions of the mutable global state in the resolved code, whose behaviour depends on where the fragment is executed.
+    pub mutable_global_state: Vec<String>,
+    /// The bundle the fragment is built into along with other fragments, `None` if it is built into its own module
+    pub bundle: Option<String>,
+    /// The tests of the project carried into the crate of the fragment
+    pub tests: Vec<FragmentTest>,
 }
 
 impl ObjectFragment {
@@ -42,9 +51,12 @@ impl ObjectFragment {
                     crates: attribute_parser.crates.unwrap_or_default(),
                     dependencies: attribute_parser.dependencies.unwrap_or_default(),
                     module_hierarchy,
+                    mutable_global_state: Vec::new(),
+                    bundle: None,
+                    tests: Vec::new(),
                     fragment_type: RustItemType::Impl,
                     rust_struct: RustStruct::default(),
-                    rust_impl,
+                    rust_impl: remove_unsupported_methods(rust_impl),
                 };
                 Some(_self)
             }
@@ -73,13 +85,33 @@ impl ObjectFragment {
                 .into_iter()
                 .collect(),
             module_hierarchy,
+            mutable_global_state: Vec::new(),
+            bundle: cfd.bundle.clone(),
+            tests: Vec::new(),
             fragment_type: RustItemType::Impl,
             rust_struct: RustStruct::default(),
-            rust_impl,
+            rust_impl: remove_unsupported_methods(rust_impl),
         }
     }
 }
 
+/// Removes the methods the wrappers cannot pass the instance to, e.g. `self: Pin<&mut Self>`, they are not exported.
+fn remove_unsupported_methods(mut rust_impl: RustImpl) -> RustImpl {
+    rust_impl
+        .functions
+        .retain(|function| match &function.receiver {
+            Some(ReceiverKind::Unsupported(receiver_type)) => {
+                warn!(
+                    "{}::{} is not exported, its receiver `self: {}` is not supported",
+                    rust_impl.properties.name, function.properties.name, receiver_type
+                );
+                false
+            }
+            _ => true,
+        });
+    rust_impl
+}
+
 impl Fragment for ObjectFragment {
     fn get_common_properties(&self) -> RustItemCommonProperties {
         self.rust_impl.properties.clone()
@@ -109,6 +141,14 @@ impl Fragment for ObjectFragment {
         self.fragment_type.clone()
     }
 
+    fn get_execution_location(&self) -> ExecutionLocation {
+        self.initial_execution_location.clone()
+    }
+
+    fn set_mutable_global_state(&mut self, mutable_global_state: Vec<String>) {
+        self.mutable_global_state = mutable_global_state;
+    }
+
     fn get_module_hierarchy(&self) -> Vec<String> {
         self.module_hierarchy.clone()
     }
@@ -124,6 +164,14 @@ impl Fragment for ObjectFragment {
         self.dependencies = dependency_ids;
     }
 
+    fn get_bundle(&self) -> Option<String> {
+        self.bundle.clone()
+    }
+
+    fn set_bundle(&mut self, bundle: Option<String>) {
+        self.bundle = bundle;
+    }
+
     fn get_crates(&self) -> Vec<String> {
         self.crates.clone()
     }
@@ -132,6 +180,18 @@ impl Fragment for ObjectFragment {
         self.crates = crates;
     }
 
+    fn get_tests(&self) -> Vec<FragmentTest> {
+        self.tests.clone()
+    }
+
+    fn set_tests(&mut self, tests: Vec<FragmentTest>) {
+        self.tests = tests;
+    }
+
+    fn get_functions(&self) -> Vec<RustFunction> {
+        self.rust_impl.functions.clone()
+    }
+
     fn get_package_name(&self) -> String {
         self.id.clone()
     }
@@ -1,5 +1,8 @@
 use crate::modules::application::fragment_type::RustItemType;
+use crate::modules::application::function_fragment::ExecutionLocation;
+use crate::modules::application::FragmentTest;
 use crate::modules::source_code_analyzer::cargo_toml::ProjectCargoToml;
+use crate::modules::source_code_analyzer::types::rust_function::RustFunction;
 use crate::modules::source_code_analyzer::types::rust_struct::RustStruct;
 use crate::modules::source_code_analyzer::types::RustItemCommonProperties;
 
@@ -16,6 +19,10 @@ pub trait Fragment {
 
     fn get_type(&self) -> RustItemType;
 
+    fn get_execution_location(&self) -> ExecutionLocation;
+
+    fn set_mutable_global_state(&mut self, mutable_global_state: Vec<String>);
+
     fn get_module_hierarchy(&self) -> Vec<String>;
 
     fn set_struct_for_impl(&mut self, rust_struct: RustStruct);
@@ -23,10 +30,21 @@ pub trait Fragment {
     fn get_dependency_ids(&self) -> Vec<String>;
 
     fn set_dependency_ids(&mut self, dependency_ids: Vec<String>);
+    fn get_bundle(&self) -> Option<String>;
+
+    fn set_bundle(&mut self, bundle: Option<String>);
+
     fn get_crates(&self) -> Vec<String>;
 
     fn set_crates(&mut self, crates: Vec<String>);
 
+    fn get_tests(&self) -> Vec<FragmentTest>;
+
+    fn set_tests(&mut self, tests: Vec<FragmentTest>);
+
+    /// Returns the functions a wrapper is generated for.
+    fn get_functions(&self) -> Vec<RustFunction>;
+
     fn get_package_name(&self) -> String;
 
     fn get_wasm_identifier(&self) -> String;
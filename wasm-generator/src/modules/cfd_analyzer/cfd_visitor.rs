@@ -1,5 +1,6 @@
 use crate::modules::application::fragment_type::RustItemType;
 use crate::modules::application::function_fragment::FunctionFragment;
+use crate::modules::application::mobile_module::MobileModule;
 use crate::modules::application::object_fragment::ObjectFragment;
 use crate::modules::application::MobileFragments;
 use crate::modules::cfd_analyzer::traits::visit::Visit;
@@ -76,6 +77,27 @@ impl<'a> Visit for CfdVisitor<'a> {
             }
         };
     }
+
+    /// The module is declared in the file of the description, like modules annotated with `@mobile`.
+    fn visit_module(&mut self, cfd_item: &CodeFragmentDescription) {
+        let Some(rf) =
+            find_containing_file_in_syntax_tree(&self.rust_files, cfd_item, &mut self.cfd_errors)
+        else {
+            return;
+        };
+        let mut module_path = rf.module_path;
+        module_path.push(cfd_item.name.clone());
+        let mobile_module = MobileModule {
+            module_path,
+            description: cfd_item.clone(),
+        };
+        if mobile_module.expand(&self.rust_files, self.mobile_fragments) == 0 {
+            self.cfd_errors.push(format!(
+                "Module `{}` not found or has no public functions and impls.",
+                &cfd_item.name
+            ));
+        }
+    }
 }
 
 pub fn visit_cfd(visitor: &mut dyn Visit, items: &[CodeFragmentDescription]) {
@@ -83,6 +105,7 @@ pub fn visit_cfd(visitor: &mut dyn Visit, items: &[CodeFragmentDescription]) {
         match item.item_type.clone().unwrap_or_default() {
             RustItemType::Function => visitor.visit_function(item),
             RustItemType::Impl => visitor.visit_impl(item),
+            RustItemType::Module => visitor.visit_module(item),
             _ => {}
         }
     }
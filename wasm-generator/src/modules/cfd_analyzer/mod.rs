@@ -70,6 +70,8 @@ pub struct CodeFragmentDescription {
     pub crates: Option<Vec<String>>,
     pub dependencies: Option<Vec<String>>,
     pub item_type: Option<RustItemType>,
+    /// The bundle the fragment is built into along with other fragments
+    pub bundle: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone, Default)]
@@ -1,10 +1,11 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use figment::providers::Format;
 use figment::{
     providers::{Env, Toml},
     Figment,
 };
 use serde_derive::Deserialize;
+use std::collections::BTreeMap;
 
 /// This struct represents the configuration of the application
 #[derive(Debug, Deserialize, Clone)]
@@ -17,6 +18,32 @@ pub struct Configuration {
     pub max_thread_pool: Option<usize>,
     pub benchmarks_dir: Option<String>,
     pub keep_temp_dir: Option<bool>,
+    pub offline: Option<bool>,
+    pub reproducible: Option<bool>,
+    /// Keeps the debug information of the modules and writes source maps next to them
+    pub debug_info: Option<bool>,
+    /// Carries the unit tests using the fragments into their crates and runs them against the wasm modules
+    pub test_fragments: Option<bool>,
+    pub js_bigint: Option<bool>,
+    pub component_model: Option<bool>,
+    pub wasm_bindgen: Option<bool>,
+    /// Bundles built from several fragments, mapping the name of every bundle to the ids of its fragments
+    pub bundles: Option<BTreeMap<String, Vec<String>>>,
+    pub dependency_graphs_dir: Option<String>,
+    pub client_portability_check: Option<PortabilityCheckLevel>,
+    pub server_portability_check: Option<PortabilityCheckLevel>,
+    #[serde(skip)]
+    pub command: Option<Command>,
+}
+
+/// How portability issues found in the fragments of a target are handled
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PortabilityCheckLevel {
+    Off,
+    #[default]
+    Warn,
+    Deny,
 }
 
 #[derive(Parser, Debug)]
@@ -24,16 +51,36 @@ pub struct Configuration {
 pub struct Args {
     #[arg(short, long, default_value = "Config.toml")]
     config: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Print the shortest usage chain explaining why a fragment includes an item.
+    /// Requires the dependency graphs exported to `dependency_graphs_dir` by a previous run.
+    Why { fragment_id: String, item: String },
+    /// Build the fragments, then call every function fragment with generated parameters natively and as wasm,
+    /// and report the calls whose results differ. The fragments are not deployed.
+    Verify {
+        /// The number of calls per fragment
+        #[arg(long, default_value_t = 64)]
+        cases: usize,
+        /// The seed of the generated parameters, the same seed repeats the same calls
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+    },
 }
 
 impl Default for Configuration {
     fn default() -> Self {
         let args = Args::parse();
-        let config: Configuration = Figment::new()
+        let mut config: Configuration = Figment::new()
             .merge(Toml::file(args.config))
             .merge(Env::raw())
             .extract()
             .unwrap();
+        config.command = args.command;
         config
     }
 }
@@ -1,10 +1,11 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Default)]
 /// This struct is used to append code by keeping track of module hierarchy.
+/// Modules are generated in alphabetical order, so that the generated code does not change between runs.
 pub struct CodeAppender {
     code: String,
-    children: HashMap<String, CodeAppender>,
+    children: BTreeMap<String, CodeAppender>,
 }
 
 impl CodeAppender {
@@ -66,4 +67,21 @@ mod tests {
         }
         println!("combined_code: {}", code_appender.generate_code());
     }
+
+    #[test]
+    fn test_modules_are_generated_in_order() {
+        let mut first_appender = CodeAppender::default();
+        let mut second_appender = CodeAppender::default();
+        let modules = ["zeta", "alpha", "mid"];
+        for module in modules {
+            first_appender.insert(&[module.to_string()], "fn f() {}");
+        }
+        for module in modules.iter().rev() {
+            second_appender.insert(&[module.to_string()], "fn f() {}");
+        }
+        let code = first_appender.generate_code();
+        assert_eq!(code, second_appender.generate_code());
+        assert!(code.find("pub mod alpha").unwrap() < code.find("pub mod mid").unwrap());
+        assert!(code.find("pub mod mid").unwrap() < code.find("pub mod zeta").unwrap());
+    }
 }
@@ -1,13 +1,38 @@
 use crate::modules::application::traits::fragment::Fragment;
+use crate::modules::dependency_resolver::test_collector::TESTS_FEATURE;
 use crate::modules::source_code_analyzer::cargo_toml::{CargoPackageInformation, ProjectCargoToml};
+use log::warn;
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+use syn::visit::{visit_path, visit_use_tree, Visit};
+use syn::{Path, UseTree};
 
-const DEFAULT_CRATES: [(&str, &str); 4] = [
+pub(crate) const DEFAULT_CRATES: [(&str, &str); 5] = [
     ("serde_json", "1.0.104"),
     ("rmp-serde", "1.1.2"),
+    ("rmp", "0.8.15"),
     ("serde_derive", "1.0.163"),
     ("serde", "1.0.163"),
 ];
 
+/// Crates that are known to depend on OS threads, sockets or native libraries and therefore do not build for wasm targets.
+const NON_WASM_CRATES: [&str; 13] = [
+    "tokio",
+    "mio",
+    "socket2",
+    "nix",
+    "rayon",
+    "native-tls",
+    "openssl",
+    "hyper",
+    "actix-rt",
+    "actix-web",
+    "rusqlite",
+    "winapi",
+    "windows-sys",
+];
+
 pub fn set_cargo_toml<T: Fragment>(fragment: &mut T, project_cargo_toml: &ProjectCargoToml) {
     let mut cargo_dependencies: toml::Table =
         project_cargo_toml
@@ -16,11 +41,11 @@ pub fn set_cargo_toml<T: Fragment>(fragment: &mut T, project_cargo_toml: &Projec
             .map_or(toml::Table::new(), |deps| {
                 deps.iter()
                     .filter(|(k, v)| {
-                        // Keep if the crate exactly matches the string in the list from fragment and does not have a local path.
+                        // Keep if the crate matches a name in the list from fragment and does not have a local path.
                         fragment
                             .get_crates()
                             .iter()
-                            .any(|crate_name| crate_name == *k)
+                            .any(|crate_name| normalize(crate_name) == normalize(k))
                             && match v {
                                 toml::Value::Table(table) => !table.contains_key("path"),
                                 _ => true,
@@ -30,6 +55,17 @@ pub fn set_cargo_toml<T: Fragment>(fragment: &mut T, project_cargo_toml: &Projec
                     .collect()
             });
 
+    for (name, value) in &cargo_dependencies {
+        let package_name = get_package_name(name, value);
+        if NON_WASM_CRATES.contains(&package_name.as_str()) || package_name.ends_with("-sys") {
+            warn!(
+                "Fragment {} depends on crate {} which is unlikely to compile for wasm targets",
+                fragment.get_package_name(),
+                package_name
+            );
+        }
+    }
+
     // add default dependencies
     for (k, v) in DEFAULT_CRATES {
         if !cargo_dependencies.contains_key(k) {
@@ -44,19 +80,191 @@ pub fn set_cargo_toml<T: Fragment>(fragment: &mut T, project_cargo_toml: &Projec
         project_cargo_toml.package.edition.clone(),
     );
 
+    // The carried tests are only compiled into the modules the tests are run against
+    let features = match fragment.get_tests().is_empty() {
+        true => None,
+        false => Some(toml::Table::from_iter([(
+            TESTS_FEATURE.to_string(),
+            toml::Value::Array(Vec::new()),
+        )])),
+    };
     let mut cargo_toml = ProjectCargoToml {
         package: cargo_package,
         dependencies: cargo_dependencies.into(),
         lib: toml::Table::new().into(),
+        features,
     };
 
-    // Add crate-type to the Cargo.toml
+    // Add crate-type to the Cargo.toml, the rlib is linked into the native harnesses of the verify command
     cargo_toml.lib.as_mut().map(|lib| {
         lib.insert(
             "crate-type".to_string(),
-            toml::Value::Array(vec![toml::Value::String("cdylib".to_string())]),
+            toml::Value::Array(vec![
+                toml::Value::String("cdylib".to_string()),
+                toml::Value::String("rlib".to_string()),
+            ]),
         )
     });
 
     fragment.set_cargo_toml(cargo_toml);
 }
+
+/// Infers the project dependencies a fragment uses, returning their keys as written in the project's Cargo.toml.
+/// A dependency is considered used if a path or `use` statement in the fragment code starts with its name,
+/// or if the LSP located a definition inside its package.
+pub fn infer_crates(
+    code: &str,
+    external_packages: &[String],
+    project_cargo_toml: &ProjectCargoToml,
+) -> Vec<String> {
+    let Some(dependencies) = project_cargo_toml.dependencies.as_ref() else {
+        return Vec::new();
+    };
+    let path_roots = match syn::parse_file(code) {
+        Ok(syntax_tree) => {
+            let mut visitor = PathRootVisitor::default();
+            visitor.visit_file(&syntax_tree);
+            visitor.roots
+        }
+        Err(_) => BTreeSet::new(),
+    };
+
+    dependencies
+        .iter()
+        .filter(|(name, value)| {
+            path_roots.contains(&normalize(name))
+                || external_packages
+                    .iter()
+                    .any(|package| normalize(package) == normalize(&get_package_name(name, value)))
+        })
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Extracts the package name from the path of a source file inside the cargo registry or a git checkout.
+/// Returns `None` for any other location, e.g. the standard library sources.
+pub fn get_package_name_from_path(path: &str) -> Option<String> {
+    static REGISTRY_REGEX: OnceLock<Regex> = OnceLock::new();
+    static GIT_REGEX: OnceLock<Regex> = OnceLock::new();
+    let registry_regex = REGISTRY_REGEX
+        .get_or_init(|| Regex::new(r"/registry/src/[^/]+/([^/]+?)-\d+\.\d+\.\d+[^/]*/").unwrap());
+    let git_regex =
+        GIT_REGEX.get_or_init(|| Regex::new(r"/git/checkouts/([^/]+)-[0-9a-f]+/").unwrap());
+    registry_regex
+        .captures(path)
+        .or_else(|| git_regex.captures(path))
+        .map(|captures| captures[1].to_string())
+}
+
+/// Returns the package name of a dependency, taking a renamed `package` key into account.
+fn get_package_name(name: &str, value: &toml::Value) -> String {
+    value
+        .get("package")
+        .and_then(|package| package.as_str())
+        .unwrap_or(name)
+        .to_string()
+}
+
+/// Crate names are written with dashes in Cargo.toml but with underscores in the code.
+fn normalize(crate_name: &str) -> String {
+    crate_name.replace('-', "_")
+}
+
+/// Collects the first segment of every multi-segment path and `use` tree in the code.
+#[derive(Default)]
+struct PathRootVisitor {
+    roots: BTreeSet<String>,
+}
+
+impl<'ast> Visit<'ast> for PathRootVisitor {
+    fn visit_path(&mut self, node: &'ast Path) {
+        if node.segments.len() > 1 || node.leading_colon.is_some() {
+            if let Some(first_segment) = node.segments.first() {
+                self.roots.insert(first_segment.ident.to_string());
+            }
+        }
+        visit_path(self, node);
+    }
+
+    fn visit_item_use(&mut self, node: &'ast syn::ItemUse) {
+        match &node.tree {
+            UseTree::Path(use_path) => {
+                self.roots.insert(use_path.ident.to_string());
+            }
+            UseTree::Name(use_name) => {
+                self.roots.insert(use_name.ident.to_string());
+            }
+            UseTree::Rename(use_rename) => {
+                self.roots.insert(use_rename.ident.to_string());
+            }
+            _ => visit_use_tree(self, &node.tree),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project_cargo_toml(dependencies: &str) -> ProjectCargoToml {
+        ProjectCargoToml {
+            package: CargoPackageInformation::new(
+                "demo".to_string(),
+                "0.1.0".to_string(),
+                None,
+                Some("2021".to_string()),
+            ),
+            dependencies: Some(toml::from_str(dependencies).unwrap()),
+            lib: None,
+            features: None,
+        }
+    }
+
+    #[test]
+    fn test_get_package_name_from_path() {
+        assert_eq!(
+            get_package_name_from_path(
+                "/home/user/.cargo/registry/src/index.crates.io-6f17d22bba15001f/itertools-0.12.0/src/lib.rs"
+            ),
+            Some("itertools".to_string())
+        );
+        assert_eq!(
+            get_package_name_from_path(
+                "/home/user/.cargo/registry/src/index.crates.io-6f17d22bba15001f/sha-1-0.10.0-rc.1/src/lib.rs"
+            ),
+            Some("sha-1".to_string())
+        );
+        assert_eq!(
+            get_package_name_from_path(
+                "/home/user/.cargo/git/checkouts/my-crate-3fa2b1c4d5e6f708/abc1234/src/lib.rs"
+            ),
+            Some("my-crate".to_string())
+        );
+        assert_eq!(
+            get_package_name_from_path(
+                "/home/user/.rustup/toolchains/stable/lib/rustlib/src/rust/library/std/src/lib.rs"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_infer_crates() {
+        let cargo_toml = project_cargo_toml(
+            r#"
+            itertools = "0.12.0"
+            rmp-serde = "1.1.2"
+            rand_renamed = { version = "0.8.5", package = "rand", default-features = false }
+            chrono = "0.4.26"
+            "#,
+        );
+        let code = r#"
+            use itertools::Itertools;
+            pub fn f() -> Vec<u8> {
+                rmp_serde::to_vec(&1).unwrap()
+            }
+        "#;
+        let crates = infer_crates(code, &["rand".to_string()], &cargo_toml);
+        assert_eq!(crates, vec!["itertools", "rand_renamed", "rmp-serde"]);
+    }
+}
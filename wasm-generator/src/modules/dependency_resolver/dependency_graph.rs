@@ -0,0 +1,238 @@
+use crate::modules::application::fragment_type::RustItemType;
+use crate::modules::dependency_resolver::DependencyUsageDetail;
+use crate::modules::error::ApplicationError;
+use crate::modules::source_code_analyzer::types::RustItemCommonProperties;
+use crate::modules::util::file_handler;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+/// This struct represents the items included in a fragment, with an edge from every item to each dependency it uses.
+/// The first node is always the item marked as mobile.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    pub fragment_id: String,
+    pub nodes: Vec<DependencyGraphNode>,
+    pub edges: Vec<DependencyGraphEdge>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DependencyGraphNode {
+    pub name: String,
+    pub item_type: RustItemType,
+    pub file_path: String,
+    pub line: usize,
+}
+
+/// An edge from the item using a dependency to the item defining it, along with the location of the usage.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DependencyGraphEdge {
+    pub from: usize,
+    pub to: usize,
+    pub usage_file_path: String,
+    pub usage_line: u32,
+    pub usage_column: u32,
+}
+
+impl From<&RustItemCommonProperties> for DependencyGraphNode {
+    fn from(item: &RustItemCommonProperties) -> Self {
+        Self {
+            name: item.name.clone(),
+            item_type: item.item_type.clone(),
+            file_path: item.file_path.get_absolute_path_as_string(),
+            line: item.position.start_line,
+        }
+    }
+}
+
+impl DependencyGraph {
+    pub fn new(fragment_id: String, root: &RustItemCommonProperties) -> Self {
+        Self {
+            fragment_id,
+            nodes: vec![root.into()],
+            edges: Vec::new(),
+        }
+    }
+
+    pub fn add_edge(
+        &mut self,
+        used_by: &RustItemCommonProperties,
+        definition: &RustItemCommonProperties,
+        usage: &DependencyUsageDetail,
+    ) {
+        let from = self.get_or_insert_node(used_by.into());
+        let to = self.get_or_insert_node(definition.into());
+        self.edges.push(DependencyGraphEdge {
+            from,
+            to,
+            usage_file_path: usage.file_path.get_absolute_path_as_string(),
+            usage_line: usage.line,
+            usage_column: usage.column,
+        });
+    }
+
+    fn get_or_insert_node(&mut self, node: DependencyGraphNode) -> usize {
+        match self.nodes.iter().position(|existing| existing == &node) {
+            Some(index) => index,
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec![format!("digraph \"{}\" {{", self.fragment_id)];
+        for (index, node) in self.nodes.iter().enumerate() {
+            lines.push(format!(
+                "    {} [label=\"{} {}\\n{}:{}\"];",
+                index,
+                node.item_type,
+                node.name,
+                get_file_name(&node.file_path),
+                node.line
+            ));
+        }
+        for edge in &self.edges {
+            lines.push(format!(
+                "    {} -> {} [label=\"{}:{}\"];",
+                edge.from,
+                edge.to,
+                get_file_name(&edge.usage_file_path),
+                edge.usage_line
+            ));
+        }
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+
+    /// Returns the shortest chain of edges leading from the mobile item to the item with the given name.
+    pub fn find_shortest_chain(&self, item_name: &str) -> Option<Vec<&DependencyGraphEdge>> {
+        let target = self.nodes.iter().position(|node| node.name == item_name)?;
+        let mut incoming_edges: Vec<Option<&DependencyGraphEdge>> = vec![None; self.nodes.len()];
+        let mut visited = vec![false; self.nodes.len()];
+        let mut queue = VecDeque::from([0]);
+        visited[0] = true;
+
+        while let Some(current) = queue.pop_front() {
+            if current == target {
+                break;
+            }
+            for edge in self.edges.iter().filter(|edge| edge.from == current) {
+                if !visited[edge.to] {
+                    visited[edge.to] = true;
+                    incoming_edges[edge.to] = Some(edge);
+                    queue.push_back(edge.to);
+                }
+            }
+        }
+
+        if !visited[target] {
+            return None;
+        }
+        let mut chain = Vec::new();
+        let mut current = target;
+        while let Some(edge) = incoming_edges[current] {
+            chain.push(edge);
+            current = edge.from;
+        }
+        chain.reverse();
+        Some(chain)
+    }
+
+    /// Writes the graph as `<fragment_id>.json` and `<fragment_id>.dot` to the given directory.
+    pub fn export(&self, directory: &Path) -> Result<(), ApplicationError> {
+        file_handler::create_file_or_directory_recursive(&directory.to_path_buf())?;
+        file_handler::writeln(
+            &directory.join(format!("{}.json", self.fragment_id)),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        file_handler::writeln(
+            &directory.join(format!("{}.dot", self.fragment_id)),
+            self.to_dot(),
+        )
+    }
+
+    pub fn load(directory: &Path, fragment_id: &str) -> Result<Self, ApplicationError> {
+        let content = file_handler::read(&directory.join(format!("{}.json", fragment_id)))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// Prints the shortest usage chain explaining why the given item is included in the fragment.
+pub fn explain_inclusion(
+    graphs_directory: &Path,
+    fragment_id: &str,
+    item_name: &str,
+) -> Result<(), ApplicationError> {
+    let graph = DependencyGraph::load(graphs_directory, fragment_id)?;
+    let chain = graph.find_shortest_chain(item_name).ok_or_else(|| {
+        ApplicationError::StrError(format!(
+            "Fragment {} does not include item {}",
+            fragment_id, item_name
+        ))
+    })?;
+
+    println!("{} {}", graph.nodes[0].item_type, graph.nodes[0].name);
+    for edge in chain {
+        let node = &graph.nodes[edge.to];
+        println!(
+            "  -> {} {} (used at {}:{}:{})",
+            node.item_type, node.name, edge.usage_file_path, edge.usage_line, edge.usage_column
+        );
+    }
+    Ok(())
+}
+
+fn get_file_name(file_path: &str) -> String {
+    PathBuf::from(file_path)
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str, line: usize) -> RustItemCommonProperties {
+        let mut item = RustItemCommonProperties {
+            name: name.to_string(),
+            file_path: "/project/src/lib.rs".to_string().into(),
+            ..Default::default()
+        };
+        item.position.start_line = line;
+        item
+    }
+
+    fn usage(line: u32) -> DependencyUsageDetail {
+        DependencyUsageDetail {
+            file_path: "/project/src/lib.rs".to_string().into(),
+            line,
+            column: 4,
+        }
+    }
+
+    #[test]
+    fn test_find_shortest_chain() {
+        let (root, a, b, c) = (item("root", 1), item("a", 10), item("b", 20), item("c", 30));
+        let mut graph = DependencyGraph::new("fragment".to_string(), &root);
+        graph.add_edge(&root, &a, &usage(2));
+        graph.add_edge(&a, &b, &usage(11));
+        graph.add_edge(&b, &c, &usage(21));
+        graph.add_edge(&root, &c, &usage(3));
+
+        let chain = graph.find_shortest_chain("c").unwrap();
+        assert_eq!(chain.len(), 1);
+        assert_eq!(chain[0].usage_line, 3);
+
+        let chain = graph.find_shortest_chain("b").unwrap();
+        let names = chain
+            .iter()
+            .map(|edge| graph.nodes[edge.to].name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(names, vec!["a", "b"]);
+
+        assert!(graph.find_shortest_chain("unknown").is_none());
+    }
+}
@@ -1,4 +1,6 @@
+use crate::modules::application::fragment_type::RustItemType;
 use crate::modules::configuration::Configuration;
+use crate::modules::dependency_resolver::crates_resolver::get_package_name_from_path;
 use crate::modules::dependency_resolver::{DependencyDefinitionDetail, DependencyUsageDetail};
 use crate::modules::language_server_protocol::traits::lsp_client::{
     LspClient, LspFilePath, RustItemLocation,
@@ -7,12 +9,14 @@ use crate::modules::source_code_analyzer::rust_file::RustFile;
 use crate::modules::source_code_analyzer::traits::rust_item::RustItem;
 use crate::modules::source_code_analyzer::types::{RustItemCommonProperties, RustItemPosition};
 use crate::modules::source_code_analyzer::FilePath;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 pub struct DependencyLocator<'a> {
     lsp_client: &'a mut Box<dyn LspClient>,
     resolved_item_definitions: Vec<RustItemCommonProperties>,
+    /// Packages outside the project in which the LSP located definitions used by the fragment.
+    external_packages: Vec<String>,
     config: Arc<Configuration>,
     rust_files: Arc<Vec<RustFile>>,
 }
@@ -26,6 +30,7 @@ impl<'a> DependencyLocator<'a> {
         Self {
             lsp_client,
             resolved_item_definitions: Vec::new(),
+            external_packages: Vec::new(),
             config,
             rust_files,
         }
@@ -54,15 +59,32 @@ impl<'a> DependencyLocator<'a> {
                 .get_absolute_path()
                 .starts_with(self.config.project.as_str())
             {
-                return self.process_crate_dependency(dependency, &item_definition_location);
+                return self.process_crate_dependency(&item_definition_location);
             }
+            self.record_external_package(&item_definition_location);
         }
         None
     }
 
+    pub fn get_external_packages(&self) -> Vec<String> {
+        self.external_packages.clone()
+    }
+
+    fn record_external_package(&mut self, item_definition_location: &RustItemLocation) {
+        if let Some(package_name) = get_package_name_from_path(
+            &item_definition_location
+                .file_path
+                .get_absolute_path()
+                .to_string_lossy(),
+        ) {
+            if !self.external_packages.contains(&package_name) {
+                self.external_packages.push(package_name);
+            }
+        }
+    }
+
     fn process_crate_dependency(
         &mut self,
-        dependency_usage_detail: &DependencyUsageDetail,
         item_definition_location: &RustItemLocation,
     ) -> Option<DependencyDefinitionDetail> {
         let mut dependency: Option<DependencyDefinitionDetail> = None;
@@ -92,7 +114,7 @@ impl<'a> DependencyLocator<'a> {
             {
                 dependency = Some(DependencyDefinitionDetail::new(
                     item.get_common_properties().clone(),
-                    dependency_usage_detail.module_hierarchy.clone(),
+                    item.get_common_properties().module_hierarchy.clone(),
                 ));
             }
 
@@ -102,11 +124,9 @@ impl<'a> DependencyLocator<'a> {
                     .iter()
                     .any(|function| is_within_lines_condition(&function.get_common_properties()))
             }) {
-                let mut module_hierarchy = dependency_usage_detail.module_hierarchy.clone();
-                module_hierarchy.pop();
                 dependency = Some(DependencyDefinitionDetail::new(
                     item.get_common_properties().clone(),
-                    module_hierarchy,
+                    item.get_common_properties().module_hierarchy.clone(),
                 ));
             }
 
@@ -117,7 +137,18 @@ impl<'a> DependencyLocator<'a> {
             {
                 dependency = Some(DependencyDefinitionDetail::new(
                     item.get_common_properties().clone(),
-                    dependency_usage_detail.module_hierarchy.clone(),
+                    item.get_common_properties().module_hierarchy.clone(),
+                ));
+            }
+
+            if let Some(item) = rust_file
+                .enums
+                .iter()
+                .find(|item| is_within_lines_condition(&item.get_common_properties()))
+            {
+                dependency = Some(DependencyDefinitionDetail::new(
+                    item.get_common_properties().clone(),
+                    item.get_common_properties().module_hierarchy.clone(),
                 ));
             }
 
@@ -128,7 +159,7 @@ impl<'a> DependencyLocator<'a> {
             {
                 dependency = Some(DependencyDefinitionDetail::new(
                     item.get_common_properties().clone(),
-                    dependency_usage_detail.module_hierarchy.clone(),
+                    item.get_common_properties().module_hierarchy.clone(),
                 ));
             }
 
@@ -139,7 +170,7 @@ impl<'a> DependencyLocator<'a> {
             {
                 dependency = Some(DependencyDefinitionDetail::new(
                     item.get_common_properties().clone(),
-                    dependency_usage_detail.module_hierarchy.clone(),
+                    item.get_common_properties().module_hierarchy.clone(),
                 ));
             }
 
@@ -150,7 +181,7 @@ impl<'a> DependencyLocator<'a> {
             {
                 dependency = Some(DependencyDefinitionDetail::new(
                     item.get_common_properties().clone(),
-                    dependency_usage_detail.module_hierarchy.clone(),
+                    item.get_common_properties().module_hierarchy.clone(),
                 ));
             }
         }
@@ -176,10 +207,11 @@ impl<'a> DependencyLocator<'a> {
         visited_rust_items: Vec<RustItemCommonProperties>,
     ) -> Vec<DependencyDefinitionDetail> {
         let mut use_statements: Vec<DependencyDefinitionDetail> = Vec::new();
-        let mut grouped_visited_items: HashMap<FilePath, Vec<RustItemCommonProperties>> =
-            HashMap::new();
+        let mut grouped_visited_items: BTreeMap<FilePath, Vec<RustItemCommonProperties>> =
+            BTreeMap::new();
 
-        for item in visited_rust_items.clone() {
+        let all_visited_rust_items = visited_rust_items.clone();
+        for item in visited_rust_items {
             grouped_visited_items
                 .entry(item.file_path.clone())
                 .or_default()
@@ -208,11 +240,17 @@ impl<'a> DependencyLocator<'a> {
                         )
                         .await
                     {
-                        if item_definition_location
+                        let is_project_item = item_definition_location
                             .file_path
                             .clone()
                             .get_absolute_path()
-                            .starts_with(self.config.project.as_str())
+                            .starts_with(self.config.project.as_str());
+                        // Imports of project items are only kept if the imported item is part of the fragment
+                        if is_project_item
+                            && !lies_within_visited_code(
+                                &item_definition_location,
+                                &all_visited_rust_items,
+                            )
                         {
                             continue;
                         }
@@ -225,16 +263,33 @@ impl<'a> DependencyLocator<'a> {
                             )
                             .await
                         {
-                            if is_any_highlighted_position_inside_used_code(
+                            if !is_any_highlighted_position_inside_used_code(
                                 &document_highlight_positions,
                                 &visited_rust_items,
-                            ) && !use_statements
+                            ) {
+                                continue;
+                            }
+                            // Imports are placed in the module they were declared in.
+                            // For project items only the used path is imported, since the rest of a grouped import may not be part of the fragment.
+                            let use_properties = match is_project_item {
+                                true => RustItemCommonProperties {
+                                    name: current_use.use_string.clone(),
+                                    code: format!("use {};", current_use.use_string),
+                                    item_type: RustItemType::Use,
+                                    ..use_item.properties.clone()
+                                },
+                                false => {
+                                    self.record_external_package(&item_definition_location);
+                                    use_item.properties.clone()
+                                }
+                            };
+                            if !use_statements
                                 .iter()
-                                .any(|dep| dep.item_properties == use_item.properties)
+                                .any(|dep| dep.item_properties == use_properties)
                             {
                                 use_statements.push(DependencyDefinitionDetail::new(
-                                    use_item.properties.clone(),
-                                    vec![],
+                                    use_properties.clone(),
+                                    use_properties.module_hierarchy,
                                 ));
                             }
                         }
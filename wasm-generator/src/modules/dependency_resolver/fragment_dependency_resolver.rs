@@ -1,6 +1,7 @@
 use crate::modules::application::fragment_type::RustItemType;
 use crate::modules::application::traits::fragment::Fragment;
 use crate::modules::configuration::Configuration;
+use crate::modules::dependency_resolver::dependency_graph::DependencyGraph;
 use crate::modules::dependency_resolver::dependency_locator::DependencyLocator;
 use crate::modules::dependency_resolver::rust_item_ast_visitor::RustItemAstVisitor;
 use crate::modules::dependency_resolver::{DependencyDefinitionDetail, DependencyUsageDetail};
@@ -17,6 +18,7 @@ where
 {
     rust_files: Arc<Vec<RustFile>>,
     dependency_locator: DependencyLocator<'a>,
+    dependency_graph: DependencyGraph,
     fragment: &'a mut T,
 }
 
@@ -31,9 +33,12 @@ where
         fragment: &'a mut T,
     ) -> Self {
         let dependency_locator = DependencyLocator::new(client, config.clone(), rust_files.clone());
+        let dependency_graph =
+            DependencyGraph::new(fragment.get_id(), &fragment.get_common_properties());
         Self {
             rust_files,
             dependency_locator,
+            dependency_graph,
             fragment,
         }
     }
@@ -47,6 +52,8 @@ where
         let mut visited_items: Vec<RustItemCommonProperties> = Vec::new();
         // A list of all the dependencies that need to be located in the source code.
         let mut dependency_usages: Vec<DependencyUsageDetail> = Vec::new();
+        // The rust items in which the dependencies were used, in the same order as the usages.
+        let mut dependency_users: Vec<RustItemCommonProperties> = Vec::new();
         // A list of all the dependencies that have been located in the source code.
         let mut located_dependency_definitions: Vec<DependencyDefinitionDetail> = Vec::new();
         // A flag to check if the impl's struct has been found.
@@ -54,12 +61,17 @@ where
 
         loop {
             for rust_item in &items {
+                let usage_count = dependency_usages.len();
                 let mut rust_item_visitor =
                     RustItemAstVisitor::new(rust_item, &mut dependency_usages);
                 let source_code = file_handler::read(&rust_item.file_path.get_absolute_path())
                     .expect("Failed to read file");
                 let syntax_tree = syn::parse_file(&source_code).expect("Failed to parse file");
                 rust_item_visitor.visit_file(&syntax_tree);
+                dependency_users.resize(
+                    dependency_users.len() + dependency_usages.len() - usage_count,
+                    rust_item.clone(),
+                );
             }
             visited_items.append(&mut items);
 
@@ -67,7 +79,9 @@ where
                 break;
             }
 
-            for dependency_usage_detail in dependency_usages.drain(..) {
+            for (dependency_usage_detail, dependency_user) in
+                dependency_usages.drain(..).zip(dependency_users.drain(..))
+            {
                 let located_dependency = self
                     .dependency_locator
                     .locate_dependency(&dependency_usage_detail, &visited_items)
@@ -94,6 +108,11 @@ where
                         self.fragment.set_struct_for_impl(impl_struct);
                         struct_found = true;
                     }
+                    self.dependency_graph.add_edge(
+                        &dependency_user,
+                        &dependency.item_properties,
+                        &dependency_usage_detail,
+                    );
                     items.push(dependency.item_properties.clone());
                     located_dependency_definitions.push(dependency);
                 }
@@ -107,4 +126,13 @@ where
         located_dependency_definitions.append(&mut use_statements);
         located_dependency_definitions
     }
+
+    pub(crate) fn get_dependency_graph(&self) -> &DependencyGraph {
+        &self.dependency_graph
+    }
+
+    /// Returns the packages outside the project whose items are used by the fragment.
+    pub(crate) fn get_external_packages(&self) -> Vec<String> {
+        self.dependency_locator.get_external_packages()
+    }
 }
@@ -3,8 +3,12 @@ use crate::modules::application::traits::fragment::Fragment;
 use crate::modules::application::MobileFragments;
 use crate::modules::configuration::Configuration;
 use crate::modules::dependency_resolver::code_appender::CodeAppender;
-use crate::modules::dependency_resolver::crates_resolver::set_cargo_toml;
+use crate::modules::dependency_resolver::crates_resolver::{infer_crates, set_cargo_toml};
 use crate::modules::dependency_resolver::fragment_dependency_resolver::FragmentDependencyResolver;
+use crate::modules::dependency_resolver::serde_derive_injector::{
+    get_boundary_types, get_item_path, inject_serde_derives,
+};
+use crate::modules::fragment_generator::wasm_wrapper_generator::get_bundle_export_prefix;
 use crate::modules::language_server_protocol::rust_analyzer::RustAnalyzerClient;
 use crate::modules::language_server_protocol::traits::lsp_client::{LspClient, LspFilePath};
 use crate::modules::source_code_analyzer::cargo_toml::ProjectCargoToml;
@@ -13,13 +17,18 @@ use crate::modules::source_code_analyzer::types::RustItemCommonProperties;
 use crate::modules::source_code_analyzer::FilePath;
 use derive_new::new;
 use log::info;
+use quote::ToTokens;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 pub mod code_appender;
 pub mod crates_resolver;
+pub mod dependency_graph;
 pub mod dependency_locator;
 pub mod fragment_dependency_resolver;
 pub mod rust_item_ast_visitor;
+pub mod serde_derive_injector;
+pub mod test_collector;
 
 pub async fn run(
     mobile_fragments: &mut MobileFragments,
@@ -82,19 +91,81 @@ impl FragmentsDependencyResolver {
             );
             let mut code_appender = CodeAppender::default();
             let mut final_dependencies = resolver.resolve().await;
+            // Dependencies are appended in the order of their definitions, independently of the order in which they were located
+            final_dependencies
+                .sort_by(|a, b| get_definition_order_key(a).cmp(&get_definition_order_key(b)));
+            final_dependencies
+                .dedup_by(|a, b| get_definition_order_key(a) == get_definition_order_key(b));
+            let external_packages = resolver.get_external_packages();
+            if let Some(dependency_graphs_dir) = &self.config.dependency_graphs_dir {
+                resolver
+                    .get_dependency_graph()
+                    .export(&PathBuf::from(dependency_graphs_dir))
+                    .expect("Failed to export dependency graph");
+            }
+            // The fragment and its dependencies are placed in the modules they are defined in, mirroring the original crate
+            let module_hierarchy = fragment.get_common_properties().module_hierarchy;
+            // only the types crossing the wasm boundary need to be serializable
+            let boundary_types =
+                get_boundary_types(&fragment.get_code(), &module_hierarchy, &final_dependencies);
+            code_appender.insert(&module_hierarchy, &expose_item(&fragment.get_code()));
             // append dependencies to the code
             for dependency in &mut final_dependencies {
                 let mut code = dependency.item_properties.code.clone();
-                if dependency.item_properties.item_type == RustItemType::Struct {
-                    code = format!("#[derive(Serialize, Deserialize)]\n{}", code);
+                if matches!(
+                    dependency.item_properties.item_type,
+                    RustItemType::Struct | RustItemType::Enum
+                ) && boundary_types.contains(&get_item_path(dependency))
+                {
+                    code = inject_serde_derives(&code);
+                }
+                // The struct of a mobile impl is named by the wrappers at the crate root
+                if fragment.get_type() == RustItemType::Impl
+                    && dependency.item_properties.item_type == RustItemType::Struct
+                    && dependency.item_properties.name == fragment.get_name()
+                {
+                    code = expose_item(&code);
                 }
                 code_appender.insert(&dependency.module_hierarchy, &code);
             }
-            fragment.set_code(format!(
-                "{}\n\n{}",
-                fragment.get_code(),
-                code_appender.generate_code()
+            if self.config.test_fragments.unwrap_or(false) {
+                let export_prefix = fragment
+                    .get_bundle()
+                    .map(|_| get_bundle_export_prefix(&fragment.get_id()))
+                    .unwrap_or_default();
+                let tests = test_collector::append_tests(
+                    &mut code_appender,
+                    &self.rust_files,
+                    &export_prefix,
+                );
+                info!(
+                    "Carried {} tests into fragment {}",
+                    tests.len(),
+                    fragment.get_package_name()
+                );
+                fragment.set_tests(tests);
+            }
+            let mut code = code_appender.generate_code();
+            if !module_hierarchy.is_empty() {
+                // Re-export the mobile item, so that the wrappers at the crate root can call it
+                code = format!(
+                    "pub use {}::{};\n\n{}",
+                    module_hierarchy.join("::"),
+                    fragment.get_name(),
+                    code
+                );
+            }
+            fragment.set_code(code);
+            // crates listed by the user are kept alongside the inferred ones
+            let mut crates = fragment.get_crates();
+            crates.extend(infer_crates(
+                &fragment.get_code(),
+                &external_packages,
+                &self.project_cargo_toml,
             ));
+            crates.sort();
+            crates.dedup();
+            fragment.set_crates(crates);
             set_cargo_toml(fragment, &self.project_cargo_toml);
         }
     }
@@ -104,10 +175,53 @@ impl FragmentsDependencyResolver {
     }
 }
 
+fn get_definition_order_key(
+    dependency: &DependencyDefinitionDetail,
+) -> (&FilePath, usize, usize, &String) {
+    let properties = &dependency.item_properties;
+    (
+        &properties.file_path,
+        properties.position.start_line,
+        properties.position.start_column,
+        &properties.code,
+    )
+}
+
+/// Makes the given function, struct or the methods of an inherent impl public, so that they can be used from the crate root.
+/// Code that does not need to be changed is returned as is.
+fn expose_item(code: &str) -> String {
+    let Ok(mut item) = syn::parse_str::<syn::Item>(code) else {
+        return code.to_string();
+    };
+    let public: syn::Visibility = syn::parse_quote!(pub);
+    let mut changed = false;
+    let mut expose = |vis: &mut syn::Visibility| {
+        if !matches!(vis, syn::Visibility::Public(_)) {
+            *vis = public.clone();
+            changed = true;
+        }
+    };
+    match &mut item {
+        syn::Item::Fn(item_fn) => expose(&mut item_fn.vis),
+        syn::Item::Struct(item_struct) => expose(&mut item_struct.vis),
+        syn::Item::Impl(item_impl) if item_impl.trait_.is_none() => {
+            for impl_item in item_impl.items.iter_mut() {
+                if let syn::ImplItem::Fn(impl_item_fn) = impl_item {
+                    expose(&mut impl_item_fn.vis);
+                }
+            }
+        }
+        _ => {}
+    }
+    match changed {
+        true => item.to_token_stream().to_string(),
+        false => code.to_string(),
+    }
+}
+
 /// This struct is used to store the information about a dependency usage.
 #[derive(Debug, Clone)]
 pub struct DependencyUsageDetail {
-    module_hierarchy: Vec<String>,
     file_path: FilePath,
     line: u32,
     column: u32,
@@ -8,7 +8,7 @@ use syn::visit::{
 };
 use syn::{
     ExprCall, ExprMethodCall, ExprPath, ExprStruct, GenericArgument, ItemFn, ItemImpl, ItemStruct,
-    Path, PathArguments, Type,
+    PathArguments, Type,
 };
 
 pub struct RustItemAstVisitor<'a> {
@@ -36,7 +36,6 @@ impl<'a> RustItemAstVisitor<'a> {
 impl<'a, 'ast> Visit<'ast> for RustItemAstVisitor<'a> {
     fn visit_expr_call(&mut self, node: &'ast ExprCall) {
         if let syn::Expr::Path(ref expr_path) = *node.func {
-            let module_hierarchy = extract_module_hierarchy(&expr_path.path);
             if let Some(last_segment) = expr_path.path.segments.last() {
                 let function_name = last_segment.ident.to_string();
                 let func_span = last_segment.ident.span();
@@ -47,7 +46,6 @@ impl<'a, 'ast> Visit<'ast> for RustItemAstVisitor<'a> {
                 if function_name != self.rust_item.name {
                     self.dependencies.push(DependencyUsageDetail {
                         file_path: self.rust_item.file_path.clone(),
-                        module_hierarchy,
                         line,
                         column,
                     });
@@ -63,13 +61,11 @@ impl<'a, 'ast> Visit<'ast> for RustItemAstVisitor<'a> {
         let method_span = node.method.span();
         let line = method_span.start().line as u32;
         let column = method_span.start().column as u32;
-        let module_hierarchy = Vec::new();
 
         // Condition for skipping recursive call
         if method_name != self.rust_item.name {
             self.dependencies.push(DependencyUsageDetail {
                 file_path: self.rust_item.file_path.clone(),
-                module_hierarchy,
                 line,
                 column,
             });
@@ -79,7 +75,6 @@ impl<'a, 'ast> Visit<'ast> for RustItemAstVisitor<'a> {
     }
 
     fn visit_expr_path(&mut self, node: &'ast ExprPath) {
-        let module_hierarchy = extract_module_hierarchy(&node.path);
         if let Some(last_segment) = node.path.segments.last() {
             let item_span = last_segment.ident.span();
             let line = item_span.start().line as u32;
@@ -87,7 +82,6 @@ impl<'a, 'ast> Visit<'ast> for RustItemAstVisitor<'a> {
 
             self.dependencies.push(DependencyUsageDetail {
                 file_path: self.rust_item.file_path.clone(),
-                module_hierarchy,
                 line,
                 column,
             });
@@ -97,7 +91,6 @@ impl<'a, 'ast> Visit<'ast> for RustItemAstVisitor<'a> {
     }
 
     fn visit_expr_struct(&mut self, node: &'ast ExprStruct) {
-        let module_hierarchy = extract_module_hierarchy(&node.path);
         if let Some(last_segment) = node.path.segments.last() {
             let span = last_segment.ident.span();
             let line = span.start().line as u32;
@@ -105,7 +98,6 @@ impl<'a, 'ast> Visit<'ast> for RustItemAstVisitor<'a> {
 
             self.dependencies.push(DependencyUsageDetail {
                 file_path: self.rust_item.file_path.clone(),
-                module_hierarchy,
                 line,
                 column,
             });
@@ -127,7 +119,6 @@ impl<'a, 'ast> Visit<'ast> for RustItemAstVisitor<'a> {
                     if !util::is_primitive(&type_name) {
                         self.dependencies.push(DependencyUsageDetail {
                             file_path: self.rust_item.file_path.clone(),
-                            module_hierarchy: Vec::new(),
                             line,
                             column,
                         });
@@ -159,14 +150,12 @@ impl<'a, 'ast> Visit<'ast> for RustItemAstVisitor<'a> {
     fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
         if self.is_within_line_range(node.span()) {
             if let Type::Path(type_path) = &*node.self_ty {
-                let module_hierarchy = extract_module_hierarchy(&type_path.path);
                 if let Some(last_segment) = type_path.path.segments.last() {
                     let line = last_segment.ident.span().start().line as u32;
                     let column = last_segment.ident.span().start().column as u32;
 
                     self.dependencies.push(DependencyUsageDetail {
                         file_path: self.rust_item.file_path.clone(),
-                        module_hierarchy,
                         line,
                         column,
                     });
@@ -186,7 +175,6 @@ impl<'a, 'ast> Visit<'ast> for RustItemAstVisitor<'a> {
     fn visit_return_type(&mut self, node: &'ast syn::ReturnType) {
         if let syn::ReturnType::Type(_, type_box) = node {
             if let Type::Path(type_path) = &**type_box {
-                let module_hierarchy = extract_module_hierarchy(&type_path.path);
                 if let Some(last_segment) = type_path.path.segments.last() {
                     let type_name = last_segment.ident.to_string();
                     let type_span = last_segment.ident.span();
@@ -197,7 +185,6 @@ impl<'a, 'ast> Visit<'ast> for RustItemAstVisitor<'a> {
                     if !util::is_primitive(&type_name) {
                         self.dependencies.push(DependencyUsageDetail {
                             file_path: self.rust_item.file_path.clone(),
-                            module_hierarchy,
                             line,
                             column,
                         });
@@ -222,7 +209,6 @@ impl<'a, 'ast> Visit<'ast> for RustItemAstVisitor<'a> {
 
     fn visit_type(&mut self, node: &'ast Type) {
         if let Type::Path(type_path) = node {
-            let module_hierarchy = extract_module_hierarchy(&type_path.path);
             if let Some(last_segment) = type_path.path.segments.last() {
                 let type_name = last_segment.ident.to_string();
                 let type_span = last_segment.ident.span();
@@ -233,7 +219,6 @@ impl<'a, 'ast> Visit<'ast> for RustItemAstVisitor<'a> {
                 if !util::is_primitive(&type_name) {
                     self.dependencies.push(DependencyUsageDetail {
                         file_path: self.rust_item.file_path.clone(),
-                        module_hierarchy,
                         line,
                         column,
                     });
@@ -256,29 +241,6 @@ impl<'a, 'ast> Visit<'ast> for RustItemAstVisitor<'a> {
     }
 }
 
-fn extract_module_hierarchy(path: &Path) -> Vec<String> {
-    let mut segments: Vec<String> = path
-        .segments
-        .iter()
-        .map(|segment| segment.ident.to_string())
-        .collect();
-
-    if !segments.is_empty() {
-        match segments[0].as_str() {
-            // If the path starts with `crate`, `self`, or `super`, remove this segment
-            "crate" | "self" | "super" => {
-                segments.remove(0);
-            }
-            _ => {}
-        }
-    }
-
-    // Omit the last segment which is the item name itself, not part of the module hierarchy
-    segments.pop();
-
-    segments
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
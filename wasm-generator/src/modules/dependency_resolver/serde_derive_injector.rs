@@ -0,0 +1,514 @@
+use crate::modules::application::fragment_type::RustItemType;
+use crate::modules::dependency_resolver::DependencyDefinitionDetail;
+use quote::ToTokens;
+use std::collections::HashSet;
+use syn::punctuated::Punctuated;
+use syn::visit::{visit_type_path, Visit};
+use syn::{
+    parse_quote, Attribute, GenericParam, Generics, Item, ItemEnum, ItemStruct, ItemUse, Path,
+    Token, TypePath, UseTree,
+};
+
+const SERDE_DERIVES: [&str; 2] = ["Serialize", "Deserialize"];
+/// Bounds the number of imports followed when resolving a path, since re-exports may form cycles.
+const MAX_IMPORT_DEPTH: usize = 8;
+
+/// Returns the paths of all the types that cross the wasm boundary of a fragment, each being the module hierarchy of the type
+/// followed by its name. These are the types in the signatures of the fragment's functions, the struct of a mobile impl whose
+/// state is shipped back, and transitively the types of the fields of resolved structs and enums and the targets of resolved
+/// type aliases. Type paths are resolved against the module they are used in and its imports, so that types with the same
+/// name in different modules are told apart.
+pub fn get_boundary_types(
+    fragment_code: &str,
+    fragment_module: &[String],
+    dependencies: &[DependencyDefinitionDetail],
+) -> HashSet<Vec<String>> {
+    let mut collector = TypePathCollector::default();
+    if let Ok(syntax_tree) = syn::parse_file(fragment_code) {
+        for item in &syntax_tree.items {
+            match item {
+                syn::Item::Fn(item_fn) => collector.visit_signature(&item_fn.sig),
+                syn::Item::Impl(item_impl) => {
+                    collector.visit_type(&item_impl.self_ty);
+                    for impl_item in &item_impl.items {
+                        if let syn::ImplItem::Fn(impl_item_fn) = impl_item {
+                            collector.visit_signature(&impl_item_fn.sig);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut boundary_types = collector
+        .type_paths
+        .iter()
+        .filter_map(|path| resolve_type_path(path, fragment_module, dependencies))
+        .collect::<HashSet<Vec<String>>>();
+    loop {
+        let mut resolved_types = HashSet::new();
+        for dependency in dependencies
+            .iter()
+            .filter(|dependency| boundary_types.contains(&get_item_path(dependency)))
+        {
+            let mut collector = TypePathCollector::default();
+            let code = &dependency.item_properties.code;
+            match dependency.item_properties.item_type {
+                RustItemType::Struct => {
+                    if let Ok(item_struct) = syn::parse_str::<ItemStruct>(code) {
+                        collector.visit_fields(&item_struct.fields);
+                    }
+                }
+                RustItemType::Enum => {
+                    if let Ok(item_enum) = syn::parse_str::<ItemEnum>(code) {
+                        for variant in &item_enum.variants {
+                            collector.visit_fields(&variant.fields);
+                        }
+                    }
+                }
+                RustItemType::Type => {
+                    if let Ok(item_type) = syn::parse_str::<syn::ItemType>(code) {
+                        collector.visit_type(&item_type.ty);
+                    }
+                }
+                _ => {}
+            }
+            resolved_types.extend(collector.type_paths.iter().filter_map(|path| {
+                resolve_type_path(path, &dependency.module_hierarchy, dependencies)
+            }));
+        }
+        let previous_count = boundary_types.len();
+        boundary_types.extend(resolved_types);
+        if boundary_types.len() == previous_count {
+            break;
+        }
+    }
+    boundary_types
+}
+
+/// Returns the module hierarchy of the dependency followed by its name, as returned by `get_boundary_types`.
+pub fn get_item_path(dependency: &DependencyDefinitionDetail) -> Vec<String> {
+    let mut item_path = dependency.module_hierarchy.clone();
+    item_path.push(dependency.item_properties.name.clone());
+    item_path
+}
+
+/// Resolves a type path used in the given module to the path of the struct, enum or type alias among the dependencies it names.
+/// Single segment paths that do not resolve, e.g. types brought into scope by glob imports, fall back to the only type
+/// dependency with that name. Paths naming types of other crates resolve to `None`.
+fn resolve_type_path(
+    path: &Path,
+    module: &[String],
+    dependencies: &[DependencyDefinitionDetail],
+) -> Option<Vec<String>> {
+    let segments = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<String>>();
+    let type_dependencies = dependencies
+        .iter()
+        .filter(|dependency| {
+            matches!(
+                dependency.item_properties.item_type,
+                RustItemType::Struct | RustItemType::Enum | RustItemType::Type
+            )
+        })
+        .collect::<Vec<&DependencyDefinitionDetail>>();
+    let item_path = get_absolute_path(segments.clone(), module, dependencies, 0);
+    if type_dependencies
+        .iter()
+        .any(|dependency| get_item_path(dependency) == item_path)
+    {
+        return Some(item_path);
+    }
+    match segments.as_slice() {
+        [name] => {
+            let mut candidates = type_dependencies
+                .iter()
+                .filter(|dependency| &dependency.item_properties.name == name);
+            match (candidates.next(), candidates.next()) {
+                (Some(dependency), None) => Some(get_item_path(dependency)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Turns a path used in the given module into a path from the crate root, following `crate`, `self` and `super` segments
+/// and the imports of the module.
+fn get_absolute_path(
+    segments: Vec<String>,
+    module: &[String],
+    dependencies: &[DependencyDefinitionDetail],
+    depth: usize,
+) -> Vec<String> {
+    let Some(first_segment) = segments.first() else {
+        return segments;
+    };
+    match first_segment.as_str() {
+        "crate" => segments[1..].to_vec(),
+        "self" => [module, &segments[1..]].concat(),
+        "super" => {
+            let super_count = segments
+                .iter()
+                .take_while(|segment| *segment == "super")
+                .count();
+            let parent = &module[..module.len().saturating_sub(super_count)];
+            [parent, &segments[super_count..]].concat()
+        }
+        _ => match get_imported_path(first_segment, module, dependencies) {
+            Some(imported_path) if depth < MAX_IMPORT_DEPTH => get_absolute_path(
+                [imported_path.as_slice(), &segments[1..]].concat(),
+                module,
+                dependencies,
+                depth + 1,
+            ),
+            _ => [module, &segments].concat(),
+        },
+    }
+}
+
+/// Returns the path imported under the given name by a `use` declaration of the module.
+fn get_imported_path(
+    name: &str,
+    module: &[String],
+    dependencies: &[DependencyDefinitionDetail],
+) -> Option<Vec<String>> {
+    dependencies
+        .iter()
+        .filter(|dependency| {
+            dependency.item_properties.item_type == RustItemType::Use
+                && dependency.module_hierarchy == module
+        })
+        .filter_map(|dependency| syn::parse_str::<ItemUse>(&dependency.item_properties.code).ok())
+        .find_map(|item_use| find_import(&item_use.tree, name, Vec::new()))
+}
+
+fn find_import(use_tree: &UseTree, name: &str, mut prefix: Vec<String>) -> Option<Vec<String>> {
+    match use_tree {
+        UseTree::Path(use_path) => {
+            prefix.push(use_path.ident.to_string());
+            find_import(&use_path.tree, name, prefix)
+        }
+        UseTree::Name(use_name) if use_name.ident == name => {
+            prefix.push(use_name.ident.to_string());
+            Some(prefix)
+        }
+        UseTree::Rename(use_rename) if use_rename.rename == name => {
+            prefix.push(use_rename.ident.to_string());
+            Some(prefix)
+        }
+        UseTree::Group(use_group) => use_group
+            .items
+            .iter()
+            .find_map(|item| find_import(item, name, prefix.clone())),
+        _ => None,
+    }
+}
+
+/// Adds the serde derives to the given struct or enum code, merging them with any derives already present.
+/// For generic types, explicit serde bounds are added for every type parameter unless the type already specifies them.
+/// Code that cannot be parsed as a struct or an enum is returned unchanged.
+pub fn inject_serde_derives(code: &str) -> String {
+    let Ok(mut item) = syn::parse_str::<Item>(code) else {
+        return code.to_string();
+    };
+    let (attrs, generics) = match &mut item {
+        Item::Struct(item_struct) => (&mut item_struct.attrs, &item_struct.generics),
+        Item::Enum(item_enum) => (&mut item_enum.attrs, &item_enum.generics),
+        _ => return code.to_string(),
+    };
+
+    let derive_attributes = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)
+                .ok()
+        })
+        .collect::<Vec<Punctuated<Path, Token![,]>>>();
+    let missing_derives = SERDE_DERIVES
+        .iter()
+        .filter(|derive| {
+            !derive_attributes.iter().flatten().any(|path| {
+                path.segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == derive)
+            })
+        })
+        .map(|derive| syn::Ident::new(derive, proc_macro2::Span::call_site()))
+        .collect::<Vec<syn::Ident>>();
+
+    if !missing_derives.is_empty() {
+        match attrs.iter_mut().find(|attr| attr.path().is_ident("derive")) {
+            Some(derive_attribute) => {
+                let mut derives = derive_attribute
+                    .parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)
+                    .unwrap_or_default();
+                derives.extend(missing_derives.into_iter().map(Path::from));
+                *derive_attribute = parse_quote!(#[derive(#derives)]);
+            }
+            None => {
+                let derive_attribute: Attribute = parse_quote!(#[derive(#(#missing_derives),*)]);
+                attrs.insert(0, derive_attribute);
+            }
+        }
+    }
+
+    if let Some(bound_attribute) = get_serde_bound_attribute(attrs, generics) {
+        attrs.push(bound_attribute);
+    }
+
+    item.to_token_stream().to_string()
+}
+
+fn get_serde_bound_attribute(attrs: &[Attribute], generics: &Generics) -> Option<Attribute> {
+    let has_bound = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+        .any(|attr| attr.meta.to_token_stream().to_string().contains("bound"));
+    let type_params = generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(type_param) => Some(type_param.ident.to_string()),
+            _ => None,
+        })
+        .collect::<Vec<String>>();
+    if has_bound || type_params.is_empty() {
+        return None;
+    }
+
+    let serialize_bound = type_params
+        .iter()
+        .map(|param| format!("{}: serde::Serialize", param))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let deserialize_bound = type_params
+        .iter()
+        .map(|param| format!("{}: serde::Deserialize<'de>", param))
+        .collect::<Vec<String>>()
+        .join(", ");
+    Some(parse_quote!(
+        #[serde(bound(serialize = #serialize_bound, deserialize = #deserialize_bound))]
+    ))
+}
+
+/// Collects every type path, including the ones nested in generic arguments.
+#[derive(Default)]
+struct TypePathCollector {
+    type_paths: Vec<Path>,
+}
+
+impl<'ast> Visit<'ast> for TypePathCollector {
+    fn visit_type_path(&mut self, node: &'ast TypePath) {
+        if node.qself.is_none() {
+            self.type_paths.push(node.path.clone());
+        }
+        visit_type_path(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::dependency_resolver::code_appender::CodeAppender;
+    use crate::modules::source_code_analyzer::types::RustItemCommonProperties;
+    use crate::modules::util::compile_check::test_generated_crate;
+
+    fn dependency(
+        module: &[&str],
+        name: &str,
+        item_type: RustItemType,
+        code: &str,
+    ) -> DependencyDefinitionDetail {
+        DependencyDefinitionDetail::new(
+            RustItemCommonProperties {
+                name: name.to_string(),
+                code: code.to_string(),
+                item_type,
+                ..Default::default()
+            },
+            module.iter().map(|module| module.to_string()).collect(),
+        )
+    }
+
+    fn item_path(path: &[&str]) -> Vec<String> {
+        path.iter().map(|segment| segment.to_string()).collect()
+    }
+
+    #[test]
+    fn test_get_boundary_types() {
+        let fragment_code = "pub fn place(order: Vec<Order>) -> bool { true }";
+        let dependencies = vec![
+            dependency(
+                &[],
+                "Order",
+                RustItemType::Struct,
+                "struct Order { products: Vec<Product> }",
+            ),
+            dependency(
+                &[],
+                "Product",
+                RustItemType::Struct,
+                "struct Product { id: String }",
+            ),
+            dependency(
+                &[],
+                "Cache",
+                RustItemType::Struct,
+                "struct Cache { entries: Vec<u32> }",
+            ),
+        ];
+        let boundary_types = get_boundary_types(fragment_code, &[], &dependencies);
+        assert!(boundary_types.contains(&item_path(&["Order"])));
+        assert!(boundary_types.contains(&item_path(&["Product"])));
+        assert!(!boundary_types.contains(&item_path(&["Cache"])));
+    }
+
+    #[test]
+    fn test_get_boundary_types_resolves_modules_and_enums() {
+        let fragment_code = "pub fn place(order: Order) -> billing::Invoice { todo!() }";
+        let dependencies = vec![
+            dependency(
+                &["orders"],
+                "Order",
+                RustItemType::Struct,
+                "struct Order { status: Status }",
+            ),
+            dependency(
+                &["orders"],
+                "Status",
+                RustItemType::Enum,
+                "enum Status { Open, Cancelled(Reason) }",
+            ),
+            dependency(
+                &["orders"],
+                "Reason",
+                RustItemType::Struct,
+                "struct Reason { text: String }",
+            ),
+            dependency(
+                &["orders"],
+                "billing",
+                RustItemType::Use,
+                "use crate::billing;",
+            ),
+            dependency(
+                &["billing"],
+                "Invoice",
+                RustItemType::Struct,
+                "struct Invoice { total: u64 }",
+            ),
+            dependency(
+                &["billing"],
+                "Status",
+                RustItemType::Enum,
+                "enum Status { Paid }",
+            ),
+            dependency(
+                &["billing"],
+                "Order",
+                RustItemType::Struct,
+                "struct Order { status: Status }",
+            ),
+        ];
+        let boundary_types =
+            get_boundary_types(fragment_code, &item_path(&["orders"]), &dependencies);
+        assert_eq!(
+            boundary_types,
+            HashSet::from([
+                item_path(&["orders", "Order"]),
+                item_path(&["orders", "Status"]),
+                item_path(&["orders", "Reason"]),
+                item_path(&["billing", "Invoice"]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_boundary_types_compile_with_injected_derives() {
+        let fragment_code = "pub fn place(order: Order) -> Status { order.status }";
+        let dependencies = vec![
+            dependency(
+                &["orders"],
+                "Order",
+                RustItemType::Struct,
+                "#[derive(Debug)]\npub struct Order { pub status: Status }",
+            ),
+            dependency(
+                &["orders"],
+                "Status",
+                RustItemType::Enum,
+                "#[derive(Debug, PartialEq)]\npub enum Status { Open, Cancelled(Reason) }",
+            ),
+            dependency(
+                &["orders"],
+                "Reason",
+                RustItemType::Struct,
+                "#[derive(Debug, PartialEq)]\npub struct Reason { pub text: String }",
+            ),
+            // Not serializable, so deriving serde for it would not compile
+            dependency(
+                &["billing"],
+                "Status",
+                RustItemType::Enum,
+                "pub enum Status { Paid(*const u8) }",
+            ),
+        ];
+        let boundary_types =
+            get_boundary_types(fragment_code, &item_path(&["orders"]), &dependencies);
+        let mut code_appender = CodeAppender::default();
+        code_appender.insert(&item_path(&["orders"]), fragment_code);
+        for dependency in &dependencies {
+            let code = match boundary_types.contains(&get_item_path(dependency)) {
+                true => inject_serde_derives(&dependency.item_properties.code),
+                false => dependency.item_properties.code.clone(),
+            };
+            code_appender.insert(&dependency.module_hierarchy, &code);
+        }
+        let code = format!(
+            "use serde_derive::{{Deserialize, Serialize}};\n{}\n{}",
+            code_appender.generate_code(),
+            r#"
+            #[test]
+            fn test_round_trip() {
+                let order = orders::Order {
+                    status: orders::Status::Cancelled(orders::Reason { text: "late".to_string() }),
+                };
+                let json = serde_json::to_string(&order).unwrap();
+                let order: orders::Order = serde_json::from_str(&json).unwrap();
+                assert_eq!(orders::place(order), orders::Status::Cancelled(orders::Reason { text: "late".to_string() }));
+            }
+            "#
+        );
+        test_generated_crate("boundary_enums", &code).unwrap();
+    }
+
+    #[test]
+    fn test_inject_serde_derives_merges_existing_derives() {
+        let code = inject_serde_derives(
+            "#[derive(Clone, Serialize)]\n#[serde(rename_all = \"camelCase\")]\npub struct Order { pub order_id: String }",
+        );
+        let item_struct = syn::parse_str::<ItemStruct>(&code).unwrap();
+        let derive_count = item_struct
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("derive"))
+            .count();
+        assert_eq!(derive_count, 1);
+        assert!(code.contains("derive (Clone , Serialize , Deserialize)"));
+        assert!(code.contains("rename_all"));
+    }
+
+    #[test]
+    fn test_inject_serde_derives_adds_generic_bounds() {
+        let code = inject_serde_derives("pub struct Page<T> { pub items: Vec<T> }");
+        assert!(code.contains("derive (Serialize , Deserialize)"));
+        assert!(code.contains("T: serde::Serialize"));
+        assert!(code.contains("T: serde::Deserialize<'de>"));
+    }
+}
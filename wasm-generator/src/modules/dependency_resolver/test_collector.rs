@@ -0,0 +1,382 @@
+use crate::modules::application::FragmentTest;
+use crate::modules::dependency_resolver::code_appender::CodeAppender;
+use crate::modules::source_code_analyzer::rust_file::RustFile;
+use crate::modules::source_code_analyzer::types::rust_function::RustFunction;
+use log::{info, warn};
+use quote::ToTokens;
+use std::collections::BTreeSet;
+use syn::punctuated::Punctuated;
+use syn::visit::{visit_expr_method_call, visit_macro, visit_path, Visit};
+use syn::{
+    Expr, ExprMethodCall, ImplItemFn, ItemConst, ItemEnum, ItemFn, ItemStatic, ItemStruct,
+    ItemTrait, ItemType, ItemUse, Macro, Path, ReturnType, Token, UseTree,
+};
+
+/// The feature of the fragment crates compiling the tests carried into them, see "Fragment Tests" in the readme.
+pub const TESTS_FEATURE: &str = "dcm-tests";
+
+/// Appends the tests of the project which use a fragment crate to the crate, placed in their modules and each followed by
+/// an export running it. A test is carried if it calls a function of the crate and every item of the project it names is
+/// part of the crate, since it would not compile in the crate otherwise. Ignored, async and parameterized tests are left out.
+/// The `use` declarations of the modules of the carried tests are carried along, unless they name items missing in the crate.
+pub fn append_tests(
+    code_appender: &mut CodeAppender,
+    rust_files: &[RustFile],
+    export_prefix: &str,
+) -> Vec<FragmentTest> {
+    let crate_items = match syn::parse_file(&code_appender.generate_code()) {
+        Ok(file) => {
+            let mut visitor = CrateItemVisitor::default();
+            visitor.visit_file(&file);
+            visitor
+        }
+        Err(e) => {
+            warn!(
+                "No tests are carried into the crate, its code cannot be parsed: {}",
+                e
+            );
+            return Vec::new();
+        }
+    };
+    let project_items = get_project_items(rust_files);
+    let get_missing_items = |used_names: &BTreeSet<String>| {
+        used_names
+            .intersection(&project_items)
+            .filter(|name| !crate_items.items.contains(*name))
+            .cloned()
+            .collect::<Vec<String>>()
+    };
+
+    let mut tests = Vec::new();
+    let mut test_modules: BTreeSet<Vec<String>> = BTreeSet::new();
+    for rust_file in rust_files {
+        for function in &rust_file.functions {
+            let Some(test_fn) = parse_test(function) else {
+                continue;
+            };
+            let used_names = get_used_names(|visitor| visitor.visit_block(&test_fn.block));
+            if used_names.is_disjoint(&crate_items.functions) {
+                continue;
+            }
+            let module_hierarchy = &function.properties.module_hierarchy;
+            let name = module_hierarchy
+                .iter()
+                .chain(std::iter::once(&function.properties.name))
+                .cloned()
+                .collect::<Vec<String>>()
+                .join("::");
+            let missing_items = get_missing_items(&used_names);
+            if !missing_items.is_empty() {
+                info!(
+                    "Test {} is not carried into the crate, it uses {} which are not part of it",
+                    name,
+                    missing_items.join(", ")
+                );
+                continue;
+            }
+
+            if test_modules.insert(module_hierarchy.clone()) {
+                for rust_use in rust_file
+                    .uses
+                    .iter()
+                    .filter(|rust_use| rust_use.properties.module_hierarchy == *module_hierarchy)
+                {
+                    let Ok(item_use) = syn::parse_str::<ItemUse>(&rust_use.properties.code) else {
+                        continue;
+                    };
+                    let used_names =
+                        get_used_names(|visitor| visitor.visit_use_tree(&item_use.tree));
+                    // The generated modules already glob import their parents
+                    if is_super_glob(&item_use.tree) || !get_missing_items(&used_names).is_empty() {
+                        continue;
+                    }
+                    code_appender.insert(
+                        module_hierarchy,
+                        &format!(
+                            "#[cfg(feature = \"{}\")]\n{}",
+                            TESTS_FEATURE, rust_use.properties.code
+                        ),
+                    );
+                }
+            }
+
+            let test = FragmentTest {
+                export_name: format!("{}dcm_test__{}", export_prefix, name),
+                should_panic: test_fn
+                    .attrs
+                    .iter()
+                    .any(|attr| attr.path().is_ident("should_panic")),
+                name,
+            };
+            code_appender.insert(module_hierarchy, &generate_test_code(test_fn, &test));
+            tests.push(test);
+        }
+    }
+    tests
+}
+
+/// Returns the test a function is, `None` for other functions and for tests that cannot be run through an export.
+fn parse_test(function: &RustFunction) -> Option<ItemFn> {
+    let item_fn = syn::parse_str::<ItemFn>(&function.properties.code).ok()?;
+    let has_attribute = |name: &str| item_fn.attrs.iter().any(|attr| attr.path().is_ident(name));
+    match has_attribute("test")
+        && !has_attribute("ignore")
+        && item_fn.sig.asyncness.is_none()
+        && item_fn.sig.inputs.is_empty()
+    {
+        true => Some(item_fn),
+        false => None,
+    }
+}
+
+/// Returns the test compiled with the tests feature only, followed by the export running it.
+/// The export reports a failure through the error envelope for tests returning an `Err`, panics trap as in every fragment.
+fn generate_test_code(mut test_fn: ItemFn, test: &FragmentTest) -> String {
+    test_fn
+        .attrs
+        .retain(|attr| !attr.path().is_ident("test") && !attr.path().is_ident("should_panic"));
+    test_fn
+        .attrs
+        .insert(0, syn::parse_quote!(#[cfg(feature = #TESTS_FEATURE)]));
+    let function_name = test_fn.sig.ident.to_string();
+    let call = match test_fn.sig.output {
+        ReturnType::Default => format!("{}();", function_name),
+        ReturnType::Type(..) => format!(
+            "if let Err(e) = {}() {{
+        return serialize_error(\"TestFailed\", format!(\"{{:?}}\", e));
+    }}",
+            function_name
+        ),
+    };
+    format!(
+        r#"{}
+
+#[cfg(feature = "{}")]
+#[export_name = "{}"]
+pub extern "C" fn dcm_run_{}(_params_ptr: *const u8, _parameter_count: usize) -> *const u8 {{
+    dcm_install_panic_hook();
+    {}
+    serialize_result(&(), None::<&()>, None::<&()>)
+}}
+"#,
+        test_fn.to_token_stream(),
+        TESTS_FEATURE,
+        test.export_name,
+        function_name,
+        call
+    )
+}
+
+/// Returns the names of the items declared in the project, leaving out the tests.
+fn get_project_items(rust_files: &[RustFile]) -> BTreeSet<String> {
+    let mut project_items = BTreeSet::new();
+    for rust_file in rust_files {
+        let functions = rust_file
+            .functions
+            .iter()
+            .filter(|function| parse_test(function).is_none())
+            .chain(
+                rust_file
+                    .impls
+                    .iter()
+                    .flat_map(|rust_impl| &rust_impl.functions),
+            )
+            .map(|function| &function.properties);
+        let types = rust_file
+            .structs
+            .iter()
+            .map(|item| &item.properties)
+            .chain(rust_file.enums.iter().map(|item| &item.properties))
+            .chain(
+                rust_file
+                    .type_definitions
+                    .iter()
+                    .map(|item| &item.properties),
+            );
+        let values = rust_file
+            .consts
+            .iter()
+            .map(|item| &item.properties)
+            .chain(rust_file.statics.iter().map(|item| &item.properties));
+        project_items.extend(
+            functions
+                .chain(types)
+                .chain(values)
+                .map(|properties| properties.name.clone()),
+        );
+    }
+    project_items
+}
+
+fn is_super_glob(use_tree: &UseTree) -> bool {
+    matches!(use_tree, UseTree::Path(use_path) if use_path.ident == "super" && matches!(*use_path.tree, UseTree::Glob(_)))
+}
+
+fn get_used_names(visit: impl FnOnce(&mut UsedNameVisitor)) -> BTreeSet<String> {
+    let mut visitor = UsedNameVisitor::default();
+    visit(&mut visitor);
+    visitor.names
+}
+
+/// Collects the identifiers of the paths and method calls, including those in the arguments of macros like `assert_eq!`.
+#[derive(Default)]
+struct UsedNameVisitor {
+    names: BTreeSet<String>,
+}
+
+impl<'ast> Visit<'ast> for UsedNameVisitor {
+    fn visit_path(&mut self, node: &'ast Path) {
+        self.names.extend(
+            node.segments
+                .iter()
+                .map(|segment| segment.ident.to_string()),
+        );
+        visit_path(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        self.names.insert(node.method.to_string());
+        visit_expr_method_call(self, node);
+    }
+
+    fn visit_use_tree(&mut self, node: &'ast UseTree) {
+        match node {
+            UseTree::Path(use_path) => self.names.insert(use_path.ident.to_string()),
+            UseTree::Name(use_name) => self.names.insert(use_name.ident.to_string()),
+            UseTree::Rename(use_rename) => self.names.insert(use_rename.ident.to_string()),
+            UseTree::Glob(_) | UseTree::Group(_) => false,
+        };
+        syn::visit::visit_use_tree(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast Macro) {
+        visit_macro(self, node);
+        if let Ok(arguments) = node.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+        {
+            for argument in &arguments {
+                self.visit_expr(argument);
+            }
+        }
+    }
+}
+
+/// Collects the names of the items of a crate, the functions including the methods of its impls.
+#[derive(Default)]
+struct CrateItemVisitor {
+    items: BTreeSet<String>,
+    functions: BTreeSet<String>,
+}
+
+impl<'ast> Visit<'ast> for CrateItemVisitor {
+    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+        self.items.insert(node.sig.ident.to_string());
+        self.functions.insert(node.sig.ident.to_string());
+        syn::visit::visit_item_fn(self, node);
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        self.items.insert(node.sig.ident.to_string());
+        self.functions.insert(node.sig.ident.to_string());
+        syn::visit::visit_impl_item_fn(self, node);
+    }
+
+    fn visit_item_struct(&mut self, node: &'ast ItemStruct) {
+        self.items.insert(node.ident.to_string());
+    }
+
+    fn visit_item_enum(&mut self, node: &'ast ItemEnum) {
+        self.items.insert(node.ident.to_string());
+    }
+
+    fn visit_item_type(&mut self, node: &'ast ItemType) {
+        self.items.insert(node.ident.to_string());
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast ItemTrait) {
+        self.items.insert(node.ident.to_string());
+        syn::visit::visit_item_trait(self, node);
+    }
+
+    fn visit_item_const(&mut self, node: &'ast ItemConst) {
+        self.items.insert(node.ident.to_string());
+    }
+
+    fn visit_item_static(&mut self, node: &'ast ItemStatic) {
+        self.items.insert(node.ident.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_function(code: &str, module_hierarchy: &[&str]) -> RustFunction {
+        let item_fn: ItemFn = syn::parse_str(code).unwrap();
+        let mut function: RustFunction = item_fn.into();
+        function.properties.code = code.to_string();
+        function.properties.module_hierarchy = module_hierarchy
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        function
+    }
+
+    #[test]
+    fn test_append_tests() {
+        let fibonacci = "pub fn fibonacci(n: u64) -> u64 { if n < 2 { n } else { fibonacci(n - 1) + fibonacci(n - 2) } }";
+        let rust_file = RustFile {
+            functions: vec![
+                get_function(fibonacci, &["shared"]),
+                get_function(
+                    "pub fn factorial(n: u64) -> u64 { (1..=n).product() }",
+                    &["shared"],
+                ),
+                get_function(
+                    "#[test]\nfn test_fibonacci() { assert_eq!(fibonacci(10), 55); }",
+                    &["shared", "tests"],
+                ),
+                get_function(
+                    "#[test]\n#[should_panic]\nfn test_panics() { assert_eq!(fibonacci(1), 0); }",
+                    &["shared", "tests"],
+                ),
+                get_function(
+                    "#[test]\nfn test_both() { assert!(fibonacci(5) < factorial(5)); }",
+                    &["shared", "tests"],
+                ),
+                get_function(
+                    "#[test]\n#[ignore]\nfn test_slow() { fibonacci(90); }",
+                    &["shared", "tests"],
+                ),
+                get_function(
+                    "#[test]\nfn test_unrelated() { assert_eq!(factorial(3), 6); }",
+                    &["shared", "tests"],
+                ),
+            ],
+            ..Default::default()
+        };
+        let mut code_appender = CodeAppender::default();
+        code_appender.insert(&["shared".to_string()], fibonacci);
+
+        let tests = append_tests(&mut code_appender, &[rust_file], "fib/");
+        assert_eq!(
+            tests,
+            vec![
+                FragmentTest {
+                    name: "shared::tests::test_fibonacci".to_string(),
+                    export_name: "fib/dcm_test__shared::tests::test_fibonacci".to_string(),
+                    should_panic: false,
+                },
+                FragmentTest {
+                    name: "shared::tests::test_panics".to_string(),
+                    export_name: "fib/dcm_test__shared::tests::test_panics".to_string(),
+                    should_panic: true,
+                },
+            ]
+        );
+        let code = code_appender.generate_code();
+        assert!(code.contains("#[export_name = \"fib/dcm_test__shared::tests::test_fibonacci\"]"));
+        assert!(!code.contains("# [test]"));
+        assert!(!code.contains("should_panic"));
+        assert!(syn::parse_file(&code).is_ok());
+    }
+}
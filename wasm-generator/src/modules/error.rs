@@ -31,6 +31,9 @@ pub enum ApplicationError {
     #[error("utf8 yaml error")]
     Utf8Error(#[from] std::string::FromUtf8Error),
 
+    #[error("wasm parse error: {0}")]
+    WasmParseError(#[from] wasmparser::BinaryReaderError),
+
     #[error("UnknownError")]
     UnknownError,
 }
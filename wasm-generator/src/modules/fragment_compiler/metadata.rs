@@ -0,0 +1,167 @@
+use crate::modules::application::{FinalFragmentContext, FragmentSource};
+use crate::modules::configuration::Configuration;
+use crate::modules::error::ApplicationError;
+use crate::modules::fragment_compiler::get_wasm_target_directory;
+use crate::modules::fragment_generator::helpers_generator::ABI_VERSION;
+use log::info;
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::process::Command;
+use wasm_encoder::{CustomSection, RawSection, Section};
+use wasmparser::{Parser, Payload};
+
+/// The name of the custom section holding the build metadata of a module.
+pub const METADATA_SECTION_NAME: &str = "dcm.meta";
+/// The version of the metadata format, increased whenever fields are removed or change their meaning.
+pub const METADATA_VERSION: u32 = 1;
+
+/// Describes how a module was built, so that deployed modules can be traced back to their sources.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Serialize)]
+pub struct ModuleMetadata {
+    pub version: u32,
+    pub fragments: Vec<FragmentSource>,
+    /// The hex encoded SHA-256 hash of the module without its `dcm.meta` section
+    pub content_hash: String,
+    /// The commit of the project, if it is a git repository
+    pub git_revision: Option<String>,
+    pub generator_version: String,
+    pub target: String,
+    pub release_mode: bool,
+    pub optimize_wasm: bool,
+    pub debug_info: bool,
+    pub abi_version: u32,
+}
+
+/// Appends the `dcm.meta` section to the module of every fragment.
+/// This runs after all other processing of the modules, which might otherwise drop or change the section.
+pub fn run(
+    fragments: &[FinalFragmentContext],
+    config: &Configuration,
+) -> Result<(), ApplicationError> {
+    let git_revision = get_git_revision(config);
+    for fragment in fragments {
+        let module_path = get_wasm_target_directory(config).join(&fragment.fragment_identifier);
+        let module = std::fs::read(&module_path)?;
+        let metadata = ModuleMetadata {
+            version: METADATA_VERSION,
+            fragments: fragment.sources.clone(),
+            content_hash: get_content_hash(&module),
+            git_revision: git_revision.clone(),
+            generator_version: env!("CARGO_PKG_VERSION").to_string(),
+            target: "wasm32-unknown-unknown".to_string(),
+            release_mode: config.release_mode.unwrap_or(false),
+            optimize_wasm: config.optimize_wasm.unwrap_or(false),
+            debug_info: config.debug_info.unwrap_or(false),
+            abi_version: ABI_VERSION,
+        };
+        info!(
+            "Embedding metadata into {:?}: {}",
+            &module_path, metadata.content_hash
+        );
+        std::fs::write(&module_path, embed(&module, &metadata)?)?;
+    }
+    Ok(())
+}
+
+/// Returns the module with the metadata appended as a custom section, replacing the metadata it already holds.
+pub fn embed(module: &[u8], metadata: &ModuleMetadata) -> Result<Vec<u8>, ApplicationError> {
+    let (mut module, _) = strip(module)?;
+    CustomSection {
+        name: Cow::Borrowed(METADATA_SECTION_NAME),
+        data: Cow::Owned(serde_json::to_vec(metadata)?),
+    }
+    .append_to(&mut module);
+    Ok(module)
+}
+
+/// Returns the metadata embedded in a module, or `None` for modules built before the metadata was introduced.
+pub fn read(module: &[u8]) -> Result<Option<ModuleMetadata>, ApplicationError> {
+    match strip(module)? {
+        (_, Some(metadata)) => Ok(Some(serde_json::from_slice(metadata)?)),
+        (_, None) => Ok(None),
+    }
+}
+
+/// Returns the hex encoded SHA-256 hash of a module, leaving out its `dcm.meta` section.
+pub fn get_content_hash(module: &[u8]) -> String {
+    let module = match strip(module) {
+        Ok((stripped, _)) => stripped,
+        Err(_) => module.to_vec(),
+    };
+    Sha256::digest(module)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Returns the module without its `dcm.meta` section, and the payload of the section.
+/// The other sections are copied with their content, the code-distributor strips modules the same way to check their hash.
+fn strip(module: &[u8]) -> Result<(Vec<u8>, Option<&[u8]>), ApplicationError> {
+    let mut stripped = Vec::new();
+    let mut metadata = None;
+    for payload in Parser::new(0).parse_all(module) {
+        match payload? {
+            Payload::Version { range, .. } => stripped.extend_from_slice(&module[range]),
+            Payload::CustomSection(reader) if reader.name() == METADATA_SECTION_NAME => {
+                metadata = Some(reader.data());
+            }
+            payload => {
+                if let Some((id, range)) = payload.as_section() {
+                    RawSection {
+                        id,
+                        data: &module[range],
+                    }
+                    .append_to(&mut stripped);
+                }
+            }
+        }
+    }
+    Ok((stripped, metadata))
+}
+
+fn get_git_revision(config: &Configuration) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(&config.project)
+        .output()
+        .ok()?;
+    match output.status.success() {
+        true => Some(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An empty module holding a single custom section named `a`
+    const MODULE: &[u8] = b"\0asm\x01\0\0\0\0\x02\x01a";
+
+    #[test]
+    fn test_embed_and_read_metadata() {
+        assert_eq!(read(MODULE).unwrap(), None);
+        let metadata = ModuleMetadata {
+            version: METADATA_VERSION,
+            fragments: vec![FragmentSource {
+                id: "checkout".to_string(),
+                file: "src/shop.rs".to_string(),
+                start_line: 12,
+                end_line: 20,
+            }],
+            content_hash: get_content_hash(MODULE),
+            abi_version: ABI_VERSION,
+            ..Default::default()
+        };
+        let module = embed(MODULE, &metadata).unwrap();
+        assert!(module.starts_with(MODULE));
+        assert_eq!(read(&module).unwrap(), Some(metadata.clone()));
+        assert_eq!(get_content_hash(&module), metadata.content_hash);
+
+        // Embedding again replaces the section instead of adding another one
+        let embedded_again = embed(&module, &metadata).unwrap();
+        assert_eq!(embedded_again, module);
+        assert!(read(&module[..module.len() - 1]).is_err());
+    }
+}
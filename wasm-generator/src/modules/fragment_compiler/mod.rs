@@ -1,4 +1,5 @@
 use crate::modules::configuration::Configuration;
+use crate::modules::constants::FRAGMENTS_LOCATION;
 use crate::modules::error::ApplicationError;
 use log::{error, info};
 use std::fs;
@@ -8,82 +9,395 @@ use std::process::Command;
 use std::sync::Arc;
 use std::time::Instant;
 
-use crate::modules::application::FragmentCompilationMetric;
+use crate::modules::application::{
+    CompilationMetrics, FragmentCompilationMetric, WorkspaceCompilationMetric,
+};
 use crate::modules::application::{FinalFragmentContext, Size};
 use crate::modules::util::thread_manager::rayon::RayonThreadManager;
 use crate::modules::util::thread_manager::ThreadManager;
 
+pub mod metadata;
+pub mod source_map;
+#[cfg(feature = "fragment-tests")]
+pub mod test_runner;
+
 pub fn run(fragments: &mut Vec<FinalFragmentContext>, config: Arc<Configuration>) {
-    // Create an instance of the thread manager
-    let mut thread_manager = RayonThreadManager::new();
-    if let Some(max_thread_pool) = config.max_thread_pool {
-        thread_manager.set_max_threads(max_thread_pool);
+    let workspace_path = PathBuf::from(&config.project).join(FRAGMENTS_LOCATION);
+
+    let result = format_code(&workspace_path);
+    if let Err(e) = result {
+        error!("Error formatting fragment code: {:?}", e);
+        std::process::exit(1);
+    }
+    if workspace_path.join("Cargo.lock").exists() {
+        let result = lock_dependencies(&workspace_path, &config);
+        if let Err(e) = result {
+            error!("Error locking fragment dependencies: {:?}", e);
+            std::process::exit(1);
+        }
+    }
+    if config.test_fragments.unwrap_or(false) {
+        run_tests(fragments, &workspace_path, &config);
+    }
+    let workspace_metric = match compile(fragments, &workspace_path, &config) {
+        Ok(workspace_metric) => workspace_metric,
+        Err(e) => {
+            error!("Error compiling fragments: {:?}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let result = create_components(fragments, config.as_ref());
+    if let Err(e) = result {
+        error!("Error creating fragment components: {:?}", e);
+        std::process::exit(1);
+    }
+
+    let result = generate_bindgen_modules(fragments, config.as_ref());
+    if let Err(e) = result {
+        error!("Error running wasm-bindgen on fragments: {:?}", e);
+        std::process::exit(1);
     }
 
-    let operation = get_compile_operation(&config);
-    thread_manager.process_mut(fragments, operation);
+    if config.optimize_wasm.unwrap_or(false) {
+        // Create an instance of the thread manager
+        let mut thread_manager = RayonThreadManager::new();
+        if let Some(max_thread_pool) = config.max_thread_pool {
+            thread_manager.set_max_threads(max_thread_pool);
+        }
+
+        let operation = get_optimize_operation(&config);
+        thread_manager.process_mut(fragments, operation);
+    }
+
+    let result = metadata::run(fragments, config.as_ref());
+    if let Err(e) = result {
+        error!("Error embedding metadata into fragments: {:?}", e);
+        std::process::exit(1);
+    }
 
     if config.benchmarks_dir.is_some() {
         // write compilation data to file
-        export_compilation_metrics(fragments, &config);
+        export_compilation_metrics(fragments, workspace_metric, &config);
     }
 }
 
-fn get_compile_operation<'a>(config: &Configuration) -> impl Fn(&mut FinalFragmentContext) + 'a {
-    let config = config.clone();
-    move |fragment: &mut FinalFragmentContext| {
-        let result = format_code(&fragment.directory.base_path);
-        if let Err(e) = result {
-            error!("Error formatting fragment code: {:?}", e);
+/// Runs the tests carried into the fragment crates and exits with an error if any of them fails.
+/// The tests are built before the fragments, since their modules take the place of the modules that are deployed.
+#[cfg(feature = "fragment-tests")]
+fn run_tests(fragments: &[FinalFragmentContext], workspace_path: &Path, config: &Configuration) {
+    match test_runner::run(fragments, workspace_path, config) {
+        Ok(0) => {}
+        Ok(failure_count) => {
+            error!("{} fragment tests failed as wasm", failure_count);
             std::process::exit(1);
         }
-        let result = compile(fragment, &config);
-        if let Err(e) = result {
-            error!("Error compiling fragment: {:?}", e);
+        Err(e) => {
+            error!("Error running fragment tests: {:?}", e);
             std::process::exit(1);
         }
-        if config.optimize_wasm.unwrap_or(false) {
-            let result = optimize(fragment, &config);
-            if let Err(e) = result {
-                error!("Error optimizing fragment: {:?}", e);
-                std::process::exit(1);
-            }
+    }
+}
+
+#[cfg(not(feature = "fragment-tests"))]
+fn run_tests(_fragments: &[FinalFragmentContext], _workspace_path: &Path, _config: &Configuration) {
+    error!(
+        "test_fragments requires the wasm-generator to be built with the fragment-tests feature"
+    );
+    std::process::exit(1);
+}
+
+/// Returns the directory in which the workspace build places the wasm modules of all fragments.
+pub fn get_wasm_target_directory(config: &Configuration) -> PathBuf {
+    let mut path = PathBuf::from(&config.project)
+        .join(FRAGMENTS_LOCATION)
+        .join("target")
+        .join("wasm32-unknown-unknown");
+    match config.release_mode.unwrap_or(false) {
+        true => path.push("release"),
+        false => path.push("debug"),
+    };
+    path
+}
+
+fn get_optimize_operation<'a>(config: &Configuration) -> impl Fn(&mut FinalFragmentContext) + 'a {
+    let config = config.clone();
+    move |fragment: &mut FinalFragmentContext| {
+        let result = optimize(fragment, &config);
+        if let Err(e) = result {
+            error!("Error optimizing fragment: {:?}", e);
+            std::process::exit(1);
         }
     }
 }
 
-/// This function builds the command name and arguments to compile the fragment to wasm, and passes it to run_command function for execution.
+/// This function builds the command name and arguments to compile all fragments of the workspace to wasm, and passes it to run_command function for execution.
+/// Since the fragments are built together, the compilation time is only measured for the whole workspace.
 fn compile(
-    fragment: &mut FinalFragmentContext,
+    fragments: &mut [FinalFragmentContext],
+    workspace_path: &Path,
     config: &Configuration,
-) -> Result<(), ApplicationError> {
-    let fragment_path = &fragment.directory.base_path;
-    let mut args = vec!["build", "--target", "wasm32-unknown-unknown"];
-    if config.release_mode.unwrap_or(false) {
-        args.push("--release");
-    }
-    info!("Compiling fragment: {:?}", &fragment_path.display());
+) -> Result<WorkspaceCompilationMetric, ApplicationError> {
+    let (args, envs) = get_build_command(workspace_path, config);
+    info!(
+        "Compiling fragments workspace: {:?}",
+        &workspace_path.display()
+    );
     let start_time = Instant::now();
-    let compilation_result = run_command("cargo", args, fragment_path);
-    let duration = start_time.elapsed();
-    fragment.compilation_data = FragmentCompilationMetric {
-        fragment_identifier: fragment_path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string(),
+    run_command(
+        "cargo",
+        args.iter().map(String::as_str).collect(),
+        workspace_path,
+        envs,
+    )?;
+    let workspace_metric = WorkspaceCompilationMetric {
         release_mode: config.release_mode.unwrap_or(false),
-        optimization_mode: config.optimize_wasm.unwrap_or(false),
-        compilation_time: duration,
-        ..Default::default()
+        fragment_count: fragments.len(),
+        compilation_time: start_time.elapsed(),
     };
+    for fragment in fragments.iter_mut() {
+        fragment.compilation_data = FragmentCompilationMetric {
+            fragment_identifier: fragment
+                .directory
+                .base_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            release_mode: config.release_mode.unwrap_or(false),
+            optimization_mode: config.optimize_wasm.unwrap_or(false),
+            source_hash: fragment.source_hash.clone(),
+            ..Default::default()
+        };
+    }
+    Ok(workspace_metric)
+}
 
-    compilation_result
+/// Returns the arguments and environment variables of the cargo command building the workspace to wasm.
+fn get_build_command(
+    workspace_path: &Path,
+    config: &Configuration,
+) -> (Vec<String>, Vec<(&'static str, String)>) {
+    let mut args = vec![
+        "build".to_string(),
+        "--workspace".to_string(),
+        "--target".to_string(),
+        "wasm32-unknown-unknown".to_string(),
+    ];
+    if config.release_mode.unwrap_or(false) {
+        args.push("--release".to_string());
+    }
+    if workspace_path.join("Cargo.lock").exists() {
+        args.push("--locked".to_string());
+    }
+    if config.offline.unwrap_or(false) {
+        args.push("--offline".to_string());
+    }
+    if let Some(max_thread_pool) = config.max_thread_pool {
+        args.push("--jobs".to_string());
+        args.push(max_thread_pool.to_string());
+    }
+    let mut envs = Vec::new();
+    if config.reproducible.unwrap_or(false) {
+        add_reproducible_rustflags(
+            &mut args,
+            &mut envs,
+            workspace_path,
+            config,
+            std::env::var("CARGO_ENCODED_RUSTFLAGS").ok(),
+            std::env::var("RUSTFLAGS").ok(),
+        );
+    }
+    // Release builds drop the DWARF information, which the hosts need to resolve trapping frames to source lines
+    if config.debug_info.unwrap_or(false) {
+        envs.push(("CARGO_PROFILE_RELEASE_DEBUG", "true".to_string()));
+        envs.push(("CARGO_PROFILE_RELEASE_STRIP", "none".to_string()));
+    }
+    (args, envs)
 }
 
-fn format_code(fragment_path: &Path) -> Result<(), ApplicationError> {
-    let args = vec!["fmt"];
-    run_command("cargo", args, fragment_path)
+/// Wraps the core modules built from the component crates into components, placed next to the core modules of the fragments.
+/// The WIT world embedded by wit-bindgen lets `wasm-tools` lift the exports to the canonical ABI.
+fn create_components(
+    fragments: &[FinalFragmentContext],
+    config: &Configuration,
+) -> Result<(), ApplicationError> {
+    let target_path = get_wasm_target_directory(config);
+    for component in fragments.iter().filter_map(|f| f.component.as_ref()) {
+        info!(
+            "Creating component: {:?}",
+            &component.directory.base_path.display()
+        );
+        run_command(
+            "wasm-tools",
+            vec![
+                "component",
+                "new",
+                component.core_module_identifier.as_str(),
+                "-o",
+                component.component_identifier.as_str(),
+            ],
+            &target_path,
+            Vec::new(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns the directory in which wasm-bindgen places the JS and wasm modules generated for the client fragments.
+pub fn get_bindgen_output_directory(config: &Configuration) -> PathBuf {
+    get_wasm_target_directory(config).join("bindgen")
+}
+
+/// Runs wasm-bindgen on the core modules built from the bindgen crates, generating a JS module exporting the functions of each fragment.
+fn generate_bindgen_modules(
+    fragments: &[FinalFragmentContext],
+    config: &Configuration,
+) -> Result<(), ApplicationError> {
+    let target_path = get_wasm_target_directory(config);
+    let output_directory = get_bindgen_output_directory(config);
+    let output_directory = output_directory.to_string_lossy();
+    for bindgen in fragments.iter().filter_map(|f| f.bindgen.as_ref()) {
+        info!(
+            "Generating JS bindings: {:?}",
+            &bindgen.directory.base_path.display()
+        );
+        run_command(
+            "wasm-bindgen",
+            vec![
+                bindgen.core_module_identifier.as_str(),
+                "--target",
+                "web",
+                "--out-dir",
+                &output_directory,
+                "--out-name",
+                bindgen.out_name.as_str(),
+            ],
+            &target_path,
+            Vec::new(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns the rustc flags replacing the machine specific paths embedded in the wasm modules, e.g. in panic messages,
+/// so that builds of the same sources in different locations produce identical modules.
+fn get_reproducible_rustflags(config: &Configuration) -> Vec<String> {
+    let cargo_home = get_cargo_home();
+    let project_path = fs::canonicalize(&config.project)
+        .unwrap_or_else(|_| PathBuf::from(&config.project))
+        .to_string_lossy()
+        .to_string();
+    vec![
+        format!(
+            "--remap-path-prefix={}=/cargo",
+            cargo_home.to_string_lossy()
+        ),
+        format!("--remap-path-prefix={}=/project", project_path),
+        format!("--remap-path-prefix={}=/project", config.project),
+    ]
+}
+
+/// Adds the reproducible flags to the rustflags the build would use without them. Cargo takes the rustflags from the
+/// first source that is set: `CARGO_ENCODED_RUSTFLAGS`, `RUSTFLAGS`, then the cargo configs, which include the one of
+/// the project since the workspace is inside it. Flags of the environment are extended and passed encoded, separated
+/// by the 0x1f character. Flags of the configs are extended with `--config`, as cargo concatenates the arrays of all
+/// config sources.
+fn add_reproducible_rustflags(
+    args: &mut Vec<String>,
+    envs: &mut Vec<(&'static str, String)>,
+    workspace_path: &Path,
+    config: &Configuration,
+    encoded_rustflags: Option<String>,
+    rustflags: Option<String>,
+) {
+    let reproducible_rustflags = get_reproducible_rustflags(config);
+    let existing_rustflags = match (encoded_rustflags, rustflags) {
+        (Some(encoded_rustflags), _) => Some(
+            encoded_rustflags
+                .split('\u{1f}')
+                .filter(|flag| !flag.is_empty())
+                .map(String::from)
+                .collect::<Vec<String>>(),
+        ),
+        (None, Some(rustflags)) => Some(rustflags.split_whitespace().map(String::from).collect()),
+        (None, None) => None,
+    };
+    match existing_rustflags {
+        Some(mut existing_rustflags) => {
+            existing_rustflags.extend(reproducible_rustflags);
+            envs.push(("CARGO_ENCODED_RUSTFLAGS", existing_rustflags.join("\u{1f}")));
+        }
+        None => {
+            let flags = toml::Value::Array(
+                reproducible_rustflags
+                    .into_iter()
+                    .map(toml::Value::String)
+                    .collect(),
+            );
+            args.push("--config".to_string());
+            args.push(format!(
+                "{}={}",
+                get_config_rustflags_key(workspace_path),
+                flags
+            ));
+        }
+    }
+}
+
+/// The flags of `target.<triple>.rustflags` take precedence over `build.rustflags`, so the reproducible flags are
+/// added to the former if any cargo config applying to the workspace sets it for the wasm target.
+fn get_config_rustflags_key(workspace_path: &Path) -> &'static str {
+    let mut config_paths = Vec::new();
+    for directory in workspace_path.ancestors() {
+        config_paths.push(directory.join(".cargo").join("config.toml"));
+        config_paths.push(directory.join(".cargo").join("config"));
+    }
+    config_paths.push(get_cargo_home().join("config.toml"));
+    config_paths.push(get_cargo_home().join("config"));
+
+    let sets_target_rustflags = config_paths.iter().any(|config_path| {
+        fs::read_to_string(config_path)
+            .ok()
+            .and_then(|content| content.parse::<toml::Table>().ok())
+            .and_then(|table| {
+                table
+                    .get("target")?
+                    .get("wasm32-unknown-unknown")?
+                    .get("rustflags")
+                    .cloned()
+            })
+            .is_some()
+    });
+    match sets_target_rustflags {
+        true => "target.wasm32-unknown-unknown.rustflags",
+        false => "build.rustflags",
+    }
+}
+
+fn get_cargo_home() -> PathBuf {
+    match std::env::var("CARGO_HOME") {
+        Ok(cargo_home) => PathBuf::from(cargo_home),
+        Err(_) => PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(".cargo"),
+    }
+}
+
+/// This function adapts the lockfile copied from the project to the fragments workspace.
+/// Only the fragment packages are updated, all dependencies keep the versions locked by the project and unused ones are pruned.
+fn lock_dependencies(
+    workspace_path: &Path,
+    config: &Configuration,
+) -> Result<(), ApplicationError> {
+    let mut args = vec!["update", "--workspace"];
+    if config.offline.unwrap_or(false) {
+        args.push("--offline");
+    }
+    run_command("cargo", args, workspace_path, Vec::new())
+}
+
+fn format_code(workspace_path: &Path) -> Result<(), ApplicationError> {
+    let args = vec!["fmt", "--all"];
+    run_command("cargo", args, workspace_path, Vec::new())
 }
 
 /// This function builds the command name and arguments to optimize the fragment to wasm, and passes it to run_command function for execution.
@@ -95,41 +409,46 @@ fn optimize(
         "Optimizing fragment: {:?}",
         &fragment.directory.base_path.display()
     );
-    let mut fragment_path = fragment
-        .directory
-        .base_path
-        .join("target")
-        .join("wasm32-unknown-unknown");
-    match config.release_mode.unwrap_or(false) {
-        true => fragment_path.push("release"),
-        false => fragment_path.push("debug"),
-    };
+    let target_path = get_wasm_target_directory(config);
 
     let fragment_identifier = fragment.fragment_identifier.clone();
     let start_time = Instant::now();
+    // In reproducible mode only the debug information is stripped, keeping the custom section holding the source hash.
+    // With debug information, wasm-opt keeps the name section and updates the DWARF sections to the optimized code.
+    let strip_flag = match (
+        config.debug_info.unwrap_or(false),
+        config.reproducible.unwrap_or(false),
+    ) {
+        (true, _) => "-g",
+        (false, true) => "--strip-debug",
+        (false, false) => "--strip",
+    };
     let optimization_result = run_command(
         "wasm-opt",
         vec![
-            "--strip",
+            strip_flag,
             "--vacuum",
             "-Oz",
             "-o",
             fragment_identifier.as_str(),
             fragment_identifier.as_str(),
         ],
-        &fragment_path,
+        &target_path,
+        Vec::new(),
     );
-    fragment.compilation_data.compilation_time += start_time.elapsed();
+    fragment.compilation_data.optimization_time = start_time.elapsed();
     optimization_result
 }
 
-fn run_command(
+pub(crate) fn run_command(
     command_name: &str,
     args: Vec<&str>,
     fragment_path: &Path,
+    envs: Vec<(&str, String)>,
 ) -> Result<(), ApplicationError> {
     let result = Command::new(command_name)
         .args(&args)
+        .envs(envs)
         .current_dir(fragment_path)
         .output()?;
     let command_str = format!(
@@ -154,33 +473,32 @@ fn run_command(
     }
 }
 
-fn export_compilation_metrics(fragments: &mut [FinalFragmentContext], config: &Configuration) {
+fn export_compilation_metrics(
+    fragments: &mut [FinalFragmentContext],
+    workspace_metric: WorkspaceCompilationMetric,
+    config: &Configuration,
+) {
     let benchmarks_dir = PathBuf::from(&config.benchmarks_dir.clone().unwrap_or_default());
     if !benchmarks_dir.exists() {
         fs::create_dir_all(&benchmarks_dir).expect("Unable to create benchmarks directory");
     }
-    let file_name = format!(
-        "compilation_data_{}.json",
-        chrono::Local::now().format("%Y-%m-%d_%H-%M-%S")
-    );
+    // Reproducible builds overwrite the same file instead of creating a timestamped one per run
+    let file_name = match config.reproducible.unwrap_or(false) {
+        true => "compilation_data.json".to_string(),
+        false => format!(
+            "compilation_data_{}.json",
+            chrono::Local::now().format("%Y-%m-%d_%H-%M-%S")
+        ),
+    };
     let file = File::create(benchmarks_dir.join(file_name)).expect("Unable to create file");
 
     // Iterate over the fragments to calculate WASM size and collect compilation data
-    let compilation_data: Vec<FragmentCompilationMetric> = fragments
+    let fragment_metrics: Vec<FragmentCompilationMetric> = fragments
         .iter_mut()
         .map(|fragment| {
             // Construct the path to the compiled WASM file
-            let mut fragment_path = fragment
-                .directory
-                .base_path
-                .join("target")
-                .join("wasm32-unknown-unknown");
-            if config.release_mode.unwrap_or(false) {
-                fragment_path.push("release");
-            } else {
-                fragment_path.push("debug");
-            }
-            fragment_path.push(&fragment.fragment_identifier); // Assuming this is the WASM file name
+            let fragment_path =
+                get_wasm_target_directory(config).join(&fragment.fragment_identifier);
 
             // Get the size of the WASM file
             let wasm_size_in_bytes = fs::metadata(&fragment_path).unwrap().len() as f64;
@@ -200,5 +518,94 @@ fn export_compilation_metrics(fragments: &mut [FinalFragmentContext], config: &C
         })
         .collect();
 
+    let compilation_data = CompilationMetrics {
+        workspace: workspace_metric,
+        fragments: fragment_metrics,
+    };
     serde_json::to_writer_pretty(file, &compilation_data).expect("Unable to write data");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_command_uses_the_workspace_lockfile() {
+        let workspace_path =
+            std::env::temp_dir().join(format!("dcm-build-command-{}", std::process::id()));
+        fs::create_dir_all(&workspace_path).unwrap();
+        let config: Configuration = toml::from_str(
+            r#"
+            project = "project"
+            server_fragments_dir = "server"
+            client_code_distributor_dir = "client"
+            offline = true
+            "#,
+        )
+        .unwrap();
+
+        let (unlocked_args, _) = get_build_command(&workspace_path, &config);
+        fs::write(workspace_path.join("Cargo.lock"), "version = 3\n").unwrap();
+        let (locked_args, _) = get_build_command(&workspace_path, &config);
+        fs::remove_dir_all(&workspace_path).unwrap();
+
+        assert!(!unlocked_args.contains(&"--locked".to_string()));
+        assert!(locked_args.contains(&"--locked".to_string()));
+        assert!(locked_args.contains(&"--offline".to_string()));
+    }
+
+    #[test]
+    fn test_reproducible_rustflags_extend_the_existing_flags() {
+        let workspace_path =
+            std::env::temp_dir().join(format!("dcm-rustflags-{}", std::process::id()));
+        let config: Configuration = toml::from_str(
+            r#"
+            project = "/home/dev/shop"
+            server_fragments_dir = "server"
+            client_code_distributor_dir = "client"
+            "#,
+        )
+        .unwrap();
+        let get_command = |encoded_rustflags: Option<&str>, rustflags: Option<&str>| {
+            let (mut args, mut envs) = (Vec::new(), Vec::new());
+            add_reproducible_rustflags(
+                &mut args,
+                &mut envs,
+                &workspace_path.join("shop").join("dcm").join("fragments"),
+                &config,
+                encoded_rustflags.map(String::from),
+                rustflags.map(String::from),
+            );
+            (args, envs)
+        };
+
+        let (args, envs) = get_command(None, Some("-C target-feature=+simd128"));
+        assert!(args.is_empty());
+        let encoded_rustflags = &envs[0].1;
+        assert!(encoded_rustflags
+            .starts_with("-C\u{1f}target-feature=+simd128\u{1f}--remap-path-prefix="));
+        assert!(encoded_rustflags.ends_with("\u{1f}--remap-path-prefix=/home/dev/shop=/project"));
+
+        let (_, envs) = get_command(Some("-Ctarget-feature=+simd128"), Some("-O"));
+        assert!(envs[0]
+            .1
+            .starts_with("-Ctarget-feature=+simd128\u{1f}--remap-path-prefix="));
+
+        // Flags of the project config are kept, since the flags passed with --config are appended to them
+        let (args, envs) = get_command(None, None);
+        assert!(envs.is_empty());
+        assert_eq!(args[0], "--config");
+        assert!(args[1].starts_with("build.rustflags=[\"--remap-path-prefix="));
+
+        let cargo_config_path = workspace_path.join("shop").join(".cargo");
+        fs::create_dir_all(&cargo_config_path).unwrap();
+        fs::write(
+            cargo_config_path.join("config.toml"),
+            "[target.wasm32-unknown-unknown]\nrustflags = [\"-Ctarget-feature=+simd128\"]\n",
+        )
+        .unwrap();
+        let (args, _) = get_command(None, None);
+        fs::remove_dir_all(&workspace_path).unwrap();
+        assert!(args[1].starts_with("target.wasm32-unknown-unknown.rustflags=[\"--remap"));
+    }
+}
@@ -0,0 +1,305 @@
+use crate::modules::application::FinalFragmentContext;
+use crate::modules::configuration::Configuration;
+use crate::modules::constants::FRAGMENTS_LOCATION;
+use crate::modules::error::ApplicationError;
+use crate::modules::fragment_compiler::get_wasm_target_directory;
+use crate::modules::source_code_analyzer::rust_file::RustFile;
+use crate::modules::source_code_analyzer::types::RustItemCommonProperties;
+use crate::modules::util::file_handler;
+use log::info;
+use serde_derive::Serialize;
+use std::path::{Path, PathBuf};
+use syn::spanned::Spanned;
+
+/// The version of the source map format, read by the hosts symbolicating traps.
+const SOURCE_MAP_VERSION: u32 = 1;
+
+/// Maps the lines of the generated `lib.rs` of a fragment to the project files its items were copied from.
+#[derive(Debug, Serialize, Default)]
+pub struct SourceMap {
+    pub version: u32,
+    /// The generated file, relative to the fragments workspace, as referenced by the debug information of the module
+    pub generated_file: String,
+    pub mappings: Vec<SourceMapping>,
+}
+
+/// The lines of an item of the generated file and the position of the item in the project.
+/// The start lines are those following the attributes of the item, since derives are added to the generated items.
+#[derive(Debug, Serialize, Default, PartialEq)]
+pub struct SourceMapping {
+    pub generated_start_line: usize,
+    pub generated_end_line: usize,
+    /// The project file, relative to the project
+    pub file: String,
+    pub start_line: usize,
+    /// The path of the item relative to the crate root, e.g. `shop::checkout`
+    pub item: String,
+}
+
+/// Writes the source map of every fragment next to its wasm module as `<module>.map.json`.
+/// The generated code is formatted before it is compiled, so the maps are created from the formatted sources.
+pub fn run(
+    fragments: &[FinalFragmentContext],
+    rust_files: &[RustFile],
+    config: &Configuration,
+) -> Result<(), ApplicationError> {
+    let workspace_path = PathBuf::from(&config.project).join(FRAGMENTS_LOCATION);
+    for fragment in fragments {
+        let lib_path = fragment.directory.base_path.join("src").join("lib.rs");
+        let generated_file = lib_path
+            .strip_prefix(&workspace_path)
+            .unwrap_or(&lib_path)
+            .to_string_lossy()
+            .to_string();
+        let source_map =
+            create_source_map(&file_handler::read(&lib_path)?, generated_file, rust_files)?;
+        let map_path = get_source_map_path(config, &fragment.fragment_identifier);
+        info!("Writing source map: {:?}", &map_path);
+        std::fs::write(&map_path, serde_json::to_string_pretty(&source_map)?)?;
+    }
+    Ok(())
+}
+
+/// Returns the path of the source map of a wasm module, e.g. `fragment_1.map.json` for `fragment_1.wasm`.
+pub fn get_source_map_path(config: &Configuration, wasm_identifier: &str) -> PathBuf {
+    get_wasm_target_directory(config).join(get_source_map_identifier(wasm_identifier))
+}
+
+pub fn get_source_map_identifier(wasm_identifier: &str) -> String {
+    format!(
+        "{}.map.json",
+        Path::new(wasm_identifier)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+    )
+}
+
+fn create_source_map(
+    generated_code: &str,
+    generated_file: String,
+    rust_files: &[RustFile],
+) -> Result<SourceMap, ApplicationError> {
+    let file = syn::parse_file(generated_code)?;
+    let mut mappings = Vec::new();
+    map_items(&file.items, &mut Vec::new(), rust_files, &mut mappings);
+    mappings.sort_by_key(|mapping| mapping.generated_start_line);
+    Ok(SourceMap {
+        version: SOURCE_MAP_VERSION,
+        generated_file,
+        mappings,
+    })
+}
+
+// The items are placed in modules following their module path in the project, bundles add a module per fragment in front
+fn map_items(
+    items: &[syn::Item],
+    module_path: &mut Vec<String>,
+    rust_files: &[RustFile],
+    mappings: &mut Vec<SourceMapping>,
+) {
+    for item in items {
+        if let syn::Item::Mod(item_mod) = item {
+            if let Some((_, items)) = &item_mod.content {
+                let is_bundle_module = module_path.is_empty()
+                    && item_mod.ident.to_string().starts_with("dcm_fragment_");
+                if !is_bundle_module {
+                    module_path.push(item_mod.ident.to_string());
+                }
+                map_items(items, module_path, rust_files, mappings);
+                if !is_bundle_module {
+                    module_path.pop();
+                }
+            }
+            continue;
+        }
+        let Some(name) = get_item_name(item) else {
+            continue;
+        };
+        let Some((rust_file, properties)) = find_project_item(item, &name, module_path, rust_files)
+        else {
+            continue;
+        };
+        // The code of the project item is parsed on its own, so its lines are relative to its start
+        let start_line = syn::parse_str::<syn::Item>(&properties.code)
+            .map(|project_item| get_first_line_after_attributes(&project_item) - 1)
+            .unwrap_or_default()
+            + properties.position.start_line;
+        mappings.push(SourceMapping {
+            generated_start_line: get_first_line_after_attributes(item),
+            generated_end_line: item.span().end().line,
+            file: rust_file.relative_filepath.to_string_lossy().to_string(),
+            start_line,
+            item: module_path
+                .iter()
+                .chain(std::iter::once(&name))
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("::"),
+        });
+    }
+}
+
+fn get_first_line_after_attributes(item: &syn::Item) -> usize {
+    let mut item = item.clone();
+    match &mut item {
+        syn::Item::Fn(item_fn) => item_fn.attrs.clear(),
+        syn::Item::Struct(item_struct) => item_struct.attrs.clear(),
+        syn::Item::Enum(item_enum) => item_enum.attrs.clear(),
+        syn::Item::Const(item_const) => item_const.attrs.clear(),
+        syn::Item::Static(item_static) => item_static.attrs.clear(),
+        syn::Item::Type(item_type) => item_type.attrs.clear(),
+        syn::Item::Impl(item_impl) => item_impl.attrs.clear(),
+        _ => {}
+    }
+    item.span().start().line
+}
+
+fn get_item_name(item: &syn::Item) -> Option<String> {
+    match item {
+        syn::Item::Fn(item_fn) => Some(item_fn.sig.ident.to_string()),
+        syn::Item::Struct(item_struct) => Some(item_struct.ident.to_string()),
+        syn::Item::Enum(item_enum) => Some(item_enum.ident.to_string()),
+        syn::Item::Const(item_const) => Some(item_const.ident.to_string()),
+        syn::Item::Static(item_static) => Some(item_static.ident.to_string()),
+        syn::Item::Type(item_type) => Some(item_type.ident.to_string()),
+        syn::Item::Impl(item_impl) => match &*item_impl.self_ty {
+            syn::Type::Path(type_path) => type_path
+                .path
+                .segments
+                .first()
+                .map(|segment| segment.ident.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the project item the generated item was copied from.
+/// An impl is identified by the functions it defines, since a struct may have several impls in the same module.
+fn find_project_item<'a>(
+    item: &syn::Item,
+    name: &str,
+    module_path: &[String],
+    rust_files: &'a [RustFile],
+) -> Option<(&'a RustFile, &'a RustItemCommonProperties)> {
+    rust_files.iter().find_map(|rust_file| {
+        let mut candidates: Vec<&RustItemCommonProperties> = match item {
+            syn::Item::Fn(_) => rust_file.functions.iter().map(|i| &i.properties).collect(),
+            syn::Item::Struct(_) => rust_file.structs.iter().map(|i| &i.properties).collect(),
+            syn::Item::Enum(_) => rust_file.enums.iter().map(|i| &i.properties).collect(),
+            syn::Item::Const(_) => rust_file.consts.iter().map(|i| &i.properties).collect(),
+            syn::Item::Static(_) => rust_file.statics.iter().map(|i| &i.properties).collect(),
+            syn::Item::Type(_) => rust_file
+                .type_definitions
+                .iter()
+                .map(|i| &i.properties)
+                .collect(),
+            syn::Item::Impl(item_impl) => rust_file
+                .impls
+                .iter()
+                .filter(|rust_impl| {
+                    rust_impl
+                        .functions
+                        .iter()
+                        .map(|f| f.properties.name.clone())
+                        .eq(item_impl
+                            .items
+                            .iter()
+                            .filter_map(|impl_item| match impl_item {
+                                syn::ImplItem::Fn(impl_item_fn) => {
+                                    Some(impl_item_fn.sig.ident.to_string())
+                                }
+                                _ => None,
+                            }))
+                })
+                .map(|i| &i.properties)
+                .collect(),
+            _ => Vec::new(),
+        };
+        candidates.retain(|properties| {
+            properties.name == name && properties.module_hierarchy == module_path
+        });
+        candidates
+            .first()
+            .map(|properties| (rust_file, *properties))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::application::MobileFragments;
+    use crate::modules::source_code_analyzer::ast_visitor::AstVisitor;
+    use crate::modules::source_code_analyzer::FilePath;
+    use syn::visit::Visit;
+
+    #[test]
+    fn test_create_source_map() {
+        let project_code = "pub mod shop {
+    /// A shopping cart
+    pub struct Cart {
+        pub total: u32,
+    }
+
+    impl Cart {
+        pub fn add(&mut self, price: u32) {
+            self.total += price;
+        }
+    }
+}
+";
+        let mut mobile_fragments = MobileFragments::default();
+        let mut ast_visitor = AstVisitor::new(
+            FilePath::from("src/lib.rs".to_string()),
+            project_code.to_string(),
+            &mut mobile_fragments,
+        );
+        ast_visitor.visit_file(&syn::parse_file(project_code).unwrap());
+        let mut rust_file = ast_visitor.rust_file;
+        rust_file.relative_filepath = PathBuf::from("src/lib.rs");
+
+        let generated_code = "pub fn alloc() {}
+pub mod dcm_fragment_cart {
+    use super::*;
+    pub mod shop {
+        use super::*;
+        #[derive(serde_derive::Serialize)]
+        pub struct Cart {
+            pub total: u32,
+        }
+        impl Cart {
+            pub fn add(&mut self, price: u32) {
+                self.total += price;
+            }
+        }
+    }
+}
+";
+        let source_map = create_source_map(
+            generated_code,
+            "bundle_cart/src/lib.rs".to_string(),
+            &[rust_file],
+        )
+        .unwrap();
+        assert_eq!(
+            source_map.mappings,
+            vec![
+                SourceMapping {
+                    generated_start_line: 7,
+                    generated_end_line: 9,
+                    file: "src/lib.rs".to_string(),
+                    start_line: 3,
+                    item: "shop::Cart".to_string(),
+                },
+                SourceMapping {
+                    generated_start_line: 10,
+                    generated_end_line: 14,
+                    file: "src/lib.rs".to_string(),
+                    start_line: 7,
+                    item: "shop::Cart".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use crate::modules::application::FinalFragmentContext;
+use crate::modules::configuration::Configuration;
+use crate::modules::dependency_resolver::test_collector::TESTS_FEATURE;
+use crate::modules::error::ApplicationError;
+use crate::modules::fragment_compiler::{
+    get_build_command, get_wasm_target_directory, run_command,
+};
+use code_distributor::fragment_executor::wasmtime;
+use log::{error, info};
+use std::path::Path;
+
+/// Builds the fragments carrying tests with the tests feature and calls the export of every test on the built module,
+/// each in a new instance as the code-distributor executes fragments. Returns the number of failed tests.
+pub fn run(
+    fragments: &[FinalFragmentContext],
+    workspace_path: &Path,
+    config: &Configuration,
+) -> Result<usize, ApplicationError> {
+    let tested_fragments: Vec<&FinalFragmentContext> = fragments
+        .iter()
+        .filter(|fragment| !fragment.tests.is_empty())
+        .collect();
+    if tested_fragments.is_empty() {
+        info!("No tests were carried into the fragments");
+        return Ok(0);
+    }
+
+    let (mut args, envs) = get_build_command(workspace_path, config);
+    let features = tested_fragments
+        .iter()
+        .map(|fragment| {
+            format!(
+                "{}/{}",
+                fragment
+                    .directory
+                    .base_path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy(),
+                TESTS_FEATURE
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+    args.extend(["--features".to_string(), features]);
+    info!("Compiling fragment tests: {:?}", workspace_path.display());
+    run_command(
+        "cargo",
+        args.iter().map(String::as_str).collect(),
+        workspace_path,
+        envs,
+    )?;
+
+    let module_count = tested_fragments.len();
+    let mut failure_count = 0;
+    for fragment in tested_fragments {
+        let module_info = wasmtime::load_module(
+            &get_wasm_target_directory(config).join(&fragment.fragment_identifier),
+        )
+        .map_err(|e| ApplicationError::StrError(e.to_string()))?;
+        for test in &fragment.tests {
+            match (
+                wasmtime::execute(&module_info, test.export_name.clone(), &[]),
+                test.should_panic,
+            ) {
+                (Ok(_), false) | (Err(_), true) => info!("Test {} ... ok", test.name),
+                (Ok(_), true) => {
+                    error!("Test {} ... FAILED: the test did not panic", test.name);
+                    failure_count += 1;
+                }
+                (Err(e), false) => {
+                    error!("Test {} ... FAILED: {}", test.name, describe_failure(&e));
+                    failure_count += 1;
+                }
+            }
+        }
+    }
+    info!(
+        "Ran the tests of {} fragment modules, {} failed",
+        module_count, failure_count
+    );
+    Ok(failure_count)
+}
+
+/// The traps of the engine are only described by their debug representation.
+fn describe_failure(error: &code_distributor::ApplicationError) -> String {
+    match error {
+        code_distributor::ApplicationError::WasmtimeError(trap) => format!("{:?}", trap),
+        e => e.to_string(),
+    }
+}
@@ -0,0 +1,289 @@
+use crate::modules::application::function_fragment::FunctionFragment;
+use crate::modules::application::object_fragment::ObjectFragment;
+use crate::modules::application::traits::fragment::Fragment;
+use crate::modules::fragment_generator::wasm_wrapper_generator::{
+    get_instance_expression, get_owned_type_annotation, returns_mutable_borrow,
+};
+use crate::modules::source_code_analyzer::types::rust_function::{
+    ReceiverKind, RustFunction, RustFunctionType,
+};
+
+/// Helpers converting the results of the exports to JS values.
+/// The JSON compatible serializer produces plain objects and numbers, like the MessagePack results decoded by the executor.
+const BINDGEN_HELPERS: &str = r#"
+#[derive(serde_derive::Serialize)]
+struct DcmBindgenResponse<'a, R, S, M> {
+    result: &'a R,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<&'a S>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mutated: Option<&'a M>,
+}
+
+fn dcm_to_js_value<R: serde::Serialize, S: serde::Serialize, M: serde::Serialize>(
+    result: &R,
+    state: Option<&S>,
+    mutated: Option<&M>,
+) -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue> {
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    let value = match (state.is_some(), mutated.is_some()) {
+        (false, false) => serde::Serialize::serialize(result, &serializer),
+        _ => serde::Serialize::serialize(
+            &DcmBindgenResponse {
+                result,
+                state,
+                mutated,
+            },
+            &serializer,
+        ),
+    };
+    value.map_err(Into::into)
+}
+"#;
+
+/// Helper for functions whose result borrows the instance or a `&mut` parameter mutably, the result is converted to a JS
+/// value before the changed values are serialized, like the wrappers of the pointer protocol do.
+const BINDGEN_CONVERTED_RESULT_HELPER: &str = r#"
+#[derive(serde_derive::Serialize)]
+struct DcmBindgenConvertedResponse<'a, S, M> {
+    #[serde(serialize_with = "serde_wasm_bindgen::preserve::serialize")]
+    result: wasm_bindgen::JsValue,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<&'a S>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mutated: Option<&'a M>,
+}
+
+fn dcm_converted_to_js_value<S: serde::Serialize, M: serde::Serialize>(
+    result: wasm_bindgen::JsValue,
+    state: Option<&S>,
+    mutated: Option<&M>,
+) -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue> {
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    serde::Serialize::serialize(
+        &DcmBindgenConvertedResponse {
+            result,
+            state,
+            mutated,
+        },
+        &serializer,
+    )
+    .map_err(Into::into)
+}
+"#;
+
+/// Returns the name a function is exported under to JS.
+/// Functions of impls are prefixed with their struct, since JS functions cannot be named like keywords such as `new`.
+fn get_export_name(function: &RustFunction) -> String {
+    match &function.struct_name {
+        Some(struct_name) => format!("{}_{}", struct_name, function.properties.name),
+        None => function.properties.name.clone(),
+    }
+}
+
+// Parameters are received as JS values and deserialized, errors are thrown as JS exceptions.
+// The parameter types are inferred from the call like in the wrappers of the pointer protocol.
+fn generate_deserialize_param_values(rust_function: &RustFunction) -> String {
+    rust_function
+        .parameters
+        .iter()
+        .enumerate()
+        .map(|(idx, param)| {
+            let mutability = if param.is_mutable { "mut " } else { "" };
+            format!(
+                "let {}param{}{} = serde_wasm_bindgen::from_value(param{})?;",
+                mutability,
+                idx,
+                get_owned_type_annotation(param),
+                idx
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n    ")
+}
+
+fn generate_function_call(rust_function: &RustFunction) -> String {
+    let args = rust_function
+        .parameters
+        .iter()
+        .enumerate()
+        .map(|(i, param)| match (param.is_reference, param.is_mutable) {
+            (true, true) => format!("&mut param{}", i),
+            (true, false) => format!("&param{}", i),
+            _ => format!("param{}", i),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let function_call = match rust_function.function_type {
+        RustFunctionType::FreeFunction => {
+            format!("let result = {}({});", rust_function.properties.name, args)
+        }
+        RustFunctionType::Method => {
+            let mutability = match rust_function.receiver {
+                Some(ReceiverKind::Mutable) => "mut ",
+                _ => "",
+            };
+            format!(
+                "let {}self_instance: {} = serde_wasm_bindgen::from_value(instance)?;
+    let result = {{ {}.{}({}) }};",
+                mutability,
+                rust_function.struct_name.as_ref().unwrap(),
+                get_instance_expression(rust_function),
+                rust_function.properties.name,
+                args
+            )
+        }
+        RustFunctionType::AssociatedFunction => format!(
+            "let result = {}::{}({});",
+            rust_function.struct_name.as_ref().unwrap(),
+            rust_function.properties.name,
+            args
+        ),
+    };
+
+    // A returned borrow may keep the instance or a `&mut` parameter mutably borrowed, which are serialized after the result
+    match returns_mutable_borrow(rust_function) {
+        true => format!(
+            "{}\n    let result = serde::Serialize::serialize(&result, &serde_wasm_bindgen::Serializer::json_compatible())?;",
+            function_call
+        ),
+        false => function_call,
+    }
+}
+
+// Functions changing their instance or parameters return an object holding the result along with the changed values,
+// with the same keys as the results of the pointer protocol. All other functions return their result as it is.
+fn generate_convert_result(rust_function: &RustFunction) -> String {
+    let state = match rust_function.receiver {
+        Some(ReceiverKind::Mutable) => "Some(&self_instance)",
+        _ => "None::<&()>",
+    };
+    let mutated_params = rust_function
+        .parameters
+        .iter()
+        .enumerate()
+        .filter(|(_, param)| param.is_mutable)
+        .map(|(i, _)| format!("&param{},", i))
+        .collect::<Vec<_>>();
+    let mutated = match mutated_params.is_empty() {
+        true => "None::<&()>".to_string(),
+        false => format!("Some(&({}))", mutated_params.join(" ")),
+    };
+    match returns_mutable_borrow(rust_function) {
+        true => format!("dcm_converted_to_js_value(result, {}, {})", state, mutated),
+        false => format!("dcm_to_js_value(&result, {}, {})", state, mutated),
+    }
+}
+
+// The helpers used by the wrappers of the given functions, unused helpers would be reported as dead code
+fn get_helpers(functions: &[&RustFunction]) -> String {
+    let mut helpers = String::new();
+    if functions
+        .iter()
+        .any(|function| !returns_mutable_borrow(function))
+    {
+        helpers.push_str(BINDGEN_HELPERS);
+    }
+    if functions
+        .iter()
+        .any(|function| returns_mutable_borrow(function))
+    {
+        helpers.push_str(BINDGEN_CONVERTED_RESULT_HELPER);
+    }
+    helpers
+}
+
+pub fn generate_wrapper(function: &RustFunction) -> String {
+    let mut params = Vec::new();
+    if function.function_type == RustFunctionType::Method {
+        params.push("instance: wasm_bindgen::JsValue".to_string());
+    }
+    params.extend(
+        (0..function.parameters.len()).map(|idx| format!("param{}: wasm_bindgen::JsValue", idx)),
+    );
+    format!(
+        r#"
+#[wasm_bindgen::prelude::wasm_bindgen(js_name = "{}")]
+pub fn bindgen__{}({}) -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue> {{
+    {}
+    {}
+    {}
+}}
+"#,
+        get_export_name(function),
+        function.properties.name,
+        params.join(", "),
+        generate_deserialize_param_values(function),
+        generate_function_call(function),
+        generate_convert_result(function),
+    )
+}
+
+/// Returns the code of a function fragment with its wasm-bindgen exports.
+pub fn run_for_function(fragment: &FunctionFragment) -> String {
+    format!(
+        "{}\n{}\n\n{}",
+        get_helpers(&[&fragment.rust_function]),
+        generate_wrapper(&fragment.rust_function),
+        fragment.get_code()
+    )
+}
+
+/// Returns the code of an impl fragment with the wasm-bindgen exports of all its functions.
+pub fn run_for_impl(fragment: &ObjectFragment) -> String {
+    let wrappers = fragment
+        .rust_impl
+        .functions
+        .iter()
+        .map(generate_wrapper)
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "{}\n{}\n\n{}",
+        get_helpers(&fragment.rust_impl.functions.iter().collect::<Vec<_>>()),
+        wrappers,
+        fragment.get_code()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_wrapper() {
+        let item_fn: syn::ImplItemFn =
+            syn::parse_str("pub fn apply(&mut self, code: &str, items: &mut Vec<u8>) -> u32 { 0 }")
+                .unwrap();
+        let mut rust_function: RustFunction = item_fn.into();
+        rust_function.struct_name = Some("Cart".to_string());
+        let wrapper = generate_wrapper(&rust_function);
+        assert!(
+            wrapper.contains("#[wasm_bindgen::prelude::wasm_bindgen(js_name = \"Cart_apply\")]")
+        );
+        assert!(wrapper.contains("(instance: wasm_bindgen::JsValue, param0: wasm_bindgen::JsValue, param1: wasm_bindgen::JsValue)"));
+        assert!(wrapper.contains("let param0: String = serde_wasm_bindgen::from_value(param0)?;"));
+        assert!(wrapper
+            .contains("let mut self_instance: Cart = serde_wasm_bindgen::from_value(instance)?;"));
+        assert!(
+            wrapper.contains("dcm_to_js_value(&result, Some(&self_instance), Some(&(&param1,)))")
+        );
+        syn::parse_file(&format!("{}{}", BINDGEN_HELPERS, wrapper)).unwrap();
+
+        // The result borrowing the instance is converted before the instance is serialized, it need not be `Clone`
+        let item_fn: syn::ImplItemFn =
+            syn::parse_str("pub fn entry(&mut self) -> &NonClone { todo!() }").unwrap();
+        let mut rust_function: RustFunction = item_fn.into();
+        rust_function.struct_name = Some("Cart".to_string());
+        let wrapper = generate_wrapper(&rust_function);
+        assert!(!wrapper.contains("to_owned"));
+        assert!(wrapper.contains("let result = { self_instance.entry() };\n    let result = serde::Serialize::serialize(&result, &serde_wasm_bindgen::Serializer::json_compatible())?;"));
+        assert!(wrapper
+            .contains("dcm_converted_to_js_value(result, Some(&self_instance), None::<&()>)"));
+        let helpers = get_helpers(&[&rust_function]);
+        assert!(helpers.contains("fn dcm_converted_to_js_value"));
+        assert!(!helpers.contains("fn dcm_to_js_value"));
+        syn::parse_file(&format!("{}{}", helpers, wrapper)).unwrap();
+    }
+}
@@ -0,0 +1,185 @@
+use crate::modules::application::traits::fragment::Fragment;
+use crate::modules::error::ApplicationError;
+use crate::modules::fragment_generator::helpers_generator::generate_abi_helpers;
+use crate::modules::source_code_analyzer::cargo_toml::ProjectCargoToml;
+use log::warn;
+use quote::ToTokens;
+use syn::visit_mut::VisitMut;
+use syn::{Ident, Path, PathSegment, UseTree, VisRestricted};
+
+/// Returns the package name of the crate a bundle is built from, which is also the name of its wasm module.
+pub fn get_bundle_package_name(bundle: &str) -> String {
+    format!("bundle_{}", bundle)
+}
+
+/// Bundle names become part of package names, so they are restricted to the characters allowed in them.
+pub fn is_valid_bundle_name(bundle: &str) -> bool {
+    !bundle.is_empty()
+        && bundle
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Returns the code of a bundle, the fragments are placed in their own modules so that their items cannot collide.
+/// The ABI helpers are placed once at the root of the bundle, where the wrappers of all fragments reach them through `use super::*`.
+pub fn generate_code(fragments: &[&dyn Fragment]) -> Result<String, ApplicationError> {
+    let functions: Vec<_> = fragments
+        .iter()
+        .flat_map(|fragment| fragment.get_functions())
+        .collect();
+    let mut code = generate_abi_helpers(&functions);
+    for fragment in fragments {
+        let module_name = get_fragment_module_name(&fragment.get_id());
+        let mut file = syn::parse_file(&fragment.get_code())?;
+        CratePathRewriter {
+            module_name: Ident::new(&module_name, proc_macro2::Span::call_site()),
+        }
+        .visit_file_mut(&mut file);
+        code.push_str(&format!(
+            "\npub mod {} {{\nuse super::*;\n{}\n}}\n",
+            module_name,
+            file.to_token_stream()
+        ));
+    }
+    Ok(code)
+}
+
+/// Returns the Cargo.toml of a bundle, depending on the dependencies of all its fragments.
+/// Fragments of the same project depend on the same versions, if they differ the version of the first fragment is kept.
+pub fn generate_cargo_toml(package_name: &str, fragments: &[&dyn Fragment]) -> ProjectCargoToml {
+    let mut cargo_toml = fragments
+        .first()
+        .map(|fragment| fragment.get_cargo_toml())
+        .unwrap_or_default();
+    cargo_toml.package.name = package_name.to_string();
+    let dependencies = cargo_toml.dependencies.get_or_insert_with(toml::Table::new);
+    for fragment in fragments.iter().skip(1) {
+        if let Some(features) = fragment.get_cargo_toml().features {
+            cargo_toml
+                .features
+                .get_or_insert_with(toml::Table::new)
+                .extend(features);
+        }
+        for (name, value) in fragment.get_cargo_toml().dependencies.unwrap_or_default() {
+            match dependencies.get(&name) {
+                Some(existing_value) if *existing_value != value => warn!(
+                    "Fragment {} depends on {} = {}, bundle {} keeps {}",
+                    fragment.get_id(),
+                    name,
+                    value,
+                    package_name,
+                    existing_value
+                ),
+                Some(_) => {}
+                None => {
+                    dependencies.insert(name, value);
+                }
+            }
+        }
+    }
+    cargo_toml
+}
+
+fn get_fragment_module_name(fragment_id: &str) -> String {
+    format!("dcm_fragment_{}", fragment_id.replace('-', "_"))
+}
+
+/// Rewrites the paths starting at the crate root, since the code of a fragment is moved into a module of the bundle.
+/// Paths inside macro invocations are not rewritten, since they are not parsed.
+struct CratePathRewriter {
+    module_name: Ident,
+}
+
+impl CratePathRewriter {
+    fn starts_at_crate_root(path: &Path) -> bool {
+        path.leading_colon.is_none()
+            && path
+                .segments
+                .first()
+                .is_some_and(|segment| segment.ident == "crate")
+    }
+}
+
+impl VisitMut for CratePathRewriter {
+    fn visit_path_mut(&mut self, path: &mut Path) {
+        if Self::starts_at_crate_root(path) && path.segments.len() > 1 {
+            path.segments
+                .insert(1, PathSegment::from(self.module_name.clone()));
+        }
+        syn::visit_mut::visit_path_mut(self, path);
+    }
+
+    fn visit_use_tree_mut(&mut self, use_tree: &mut UseTree) {
+        // Only the root of a use tree can be `crate`, so nested trees are left as they are
+        if let UseTree::Path(use_path) = use_tree {
+            if use_path.ident == "crate" {
+                let tree = std::mem::replace(
+                    &mut *use_path.tree,
+                    UseTree::Glob(syn::UseGlob {
+                        star_token: Default::default(),
+                    }),
+                );
+                *use_path.tree = UseTree::Path(syn::UsePath {
+                    ident: self.module_name.clone(),
+                    colon2_token: Default::default(),
+                    tree: Box::new(tree),
+                });
+            }
+        }
+    }
+
+    fn visit_item_use_mut(&mut self, item_use: &mut syn::ItemUse) {
+        if item_use.leading_colon.is_none() {
+            self.visit_use_tree_mut(&mut item_use.tree);
+        }
+    }
+
+    // `pub(crate)` keeps its meaning, only restrictions to modules below the crate root are rewritten
+    fn visit_vis_restricted_mut(&mut self, vis_restricted: &mut VisRestricted) {
+        if Self::starts_at_crate_root(&vis_restricted.path)
+            && vis_restricted.path.segments.len() > 1
+        {
+            vis_restricted
+                .path
+                .segments
+                .insert(1, PathSegment::from(self.module_name.clone()));
+            vis_restricted.in_token = Some(Default::default());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_crate_paths() {
+        let mut file = syn::parse_file(
+            "use crate::shop::{Cart, Item};
+            pub(crate) fn total(cart: &crate::shop::Cart) -> u32 { crate::shop::sum(&cart.items) }
+            pub(in crate::shop) struct Order;
+            mod inner { use crate::*; use std::fmt; }",
+        )
+        .unwrap();
+        CratePathRewriter {
+            module_name: Ident::new("dcm_fragment_1", proc_macro2::Span::call_site()),
+        }
+        .visit_file_mut(&mut file);
+        let code = file.to_token_stream().to_string();
+        assert!(code.contains("use crate :: dcm_fragment_1 :: shop :: { Cart , Item } ;"));
+        assert!(code
+            .contains("pub (crate) fn total (cart : & crate :: dcm_fragment_1 :: shop :: Cart)"));
+        assert!(code.contains("crate :: dcm_fragment_1 :: shop :: sum (& cart . items)"));
+        assert!(code.contains("pub (in crate :: dcm_fragment_1 :: shop) struct Order ;"));
+        assert!(code.contains("use crate :: dcm_fragment_1 :: * ;"));
+        assert!(code.contains("use std :: fmt ;"));
+    }
+
+    #[test]
+    fn test_is_valid_bundle_name() {
+        assert!(is_valid_bundle_name("checkout_2"));
+        assert!(!is_valid_bundle_name("Checkout"));
+        assert!(!is_valid_bundle_name("check-out"));
+        assert!(!is_valid_bundle_name(""));
+    }
+}
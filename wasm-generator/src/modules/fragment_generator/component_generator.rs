@@ -0,0 +1,1196 @@
+use crate::modules::application::function_fragment::FunctionFragment;
+use crate::modules::application::object_fragment::ObjectFragment;
+use crate::modules::application::traits::fragment::Fragment;
+use crate::modules::error::ApplicationError;
+use crate::modules::fragment_generator::wasm_wrapper_generator::get_owned_type_annotation;
+use crate::modules::source_code_analyzer::types::rust_enum::{RustEnum, RustEnumVariantFields};
+use crate::modules::source_code_analyzer::types::rust_function::{
+    ReceiverKind, RustFunction, RustFunctionType,
+};
+use crate::modules::source_code_analyzer::types::rust_struct::RustStruct;
+use crate::modules::util::type_mapper::TypeMapper;
+use heck::{ToKebabCase, ToSnakeCase, ToUpperCamelCase};
+use quote::ToTokens;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use syn::{Fields, GenericArgument, ItemEnum, ItemStruct, PathArguments, Type};
+
+/// The world every component exports its functions in
+pub const COMPONENT_WORLD: &str = "fragment";
+
+/// The module of the component crate the bindings generated by wit-bindgen are placed in
+const BINDINGS_MODULE: &str = "dcm_component";
+
+const WIT_KEYWORDS: &[&str] = &[
+    "use",
+    "type",
+    "func",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "s8",
+    "s16",
+    "s32",
+    "s64",
+    "float32",
+    "float64",
+    "char",
+    "resource",
+    "own",
+    "borrow",
+    "record",
+    "flags",
+    "variant",
+    "enum",
+    "bool",
+    "string",
+    "option",
+    "result",
+    "future",
+    "stream",
+    "list",
+    "as",
+    "from",
+    "static",
+    "interface",
+    "tuple",
+    "world",
+    "import",
+    "export",
+    "package",
+    "constructor",
+    "include",
+    "with",
+];
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "async", "await", "dyn", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "typeof", "unsized", "virtual", "yield", "try",
+];
+
+/// The sources of a component crate, generated next to the crate of a fragment.
+pub struct ComponentSources {
+    /// The WIT package defining the world exported by the component
+    pub wit: String,
+    /// The resolved code of the fragment, followed by the bindings exporting its functions
+    pub code: String,
+}
+
+/// Generates the component sources of a function fragment.
+pub fn run_for_function(
+    fragment: &FunctionFragment,
+    type_mapper: &TypeMapper,
+) -> Result<ComponentSources, ApplicationError> {
+    let mut builder = ComponentBuilder::new(type_mapper, None);
+    builder.add_function(None, &fragment.rust_function)?;
+    builder.build(&fragment.get_code())
+}
+
+/// Generates the component sources of an impl fragment.
+/// WIT records cannot have methods, so the methods are exported as functions receiving the instance as first parameter,
+/// and `&mut self` methods return the updated instance along with their result.
+pub fn run_for_impl(
+    fragment: &ObjectFragment,
+    type_mapper: &TypeMapper,
+) -> Result<ComponentSources, ApplicationError> {
+    let struct_name = fragment.get_name();
+    let mut builder = ComponentBuilder::new(type_mapper, Some(struct_name.clone()));
+    let export_prefix = get_wit_name(&struct_name)?;
+    for function in &fragment.rust_impl.functions {
+        builder.add_function(Some(&export_prefix), function)?;
+    }
+    builder.build(&fragment.get_code())
+}
+
+/// The representation of a Rust type in WIT and in the bindings generated from it.
+struct WitType {
+    wit: String,
+    rust: String,
+}
+
+impl WitType {
+    fn new(wit: &str, rust: &str) -> Self {
+        Self {
+            wit: wit.to_string(),
+            rust: rust.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+    /// From the bindings to the project types
+    FromWit,
+    /// From the project types to the bindings
+    ToWit,
+}
+
+struct ComponentBuilder<'a> {
+    type_mapper: &'a TypeMapper,
+    self_type: Option<String>,
+    /// The WIT definitions of the records, variants and enums, with the Rust type each of them is derived from
+    type_definitions: BTreeMap<String, (String, String)>,
+    /// The names of the Rust types whose definitions are being generated, used to detect recursive types
+    pending_types: BTreeSet<String>,
+    /// The conversions between the bindings and the project types, with the module the project type is defined in
+    conversions: Vec<(Vec<String>, String)>,
+    exports: Vec<String>,
+    guest_functions: Vec<String>,
+}
+
+impl<'a> ComponentBuilder<'a> {
+    fn new(type_mapper: &'a TypeMapper, self_type: Option<String>) -> Self {
+        Self {
+            type_mapper,
+            self_type,
+            type_definitions: BTreeMap::new(),
+            pending_types: BTreeSet::new(),
+            conversions: Vec::new(),
+            exports: Vec::new(),
+            guest_functions: Vec::new(),
+        }
+    }
+
+    fn build(self, fragment_code: &str) -> Result<ComponentSources, ApplicationError> {
+        let mut wit = format!("package dcm:fragment;\n\nworld {} {{\n", COMPONENT_WORLD);
+        for (definition, _) in self.type_definitions.values() {
+            wit.push_str(definition);
+            wit.push('\n');
+        }
+        for export in &self.exports {
+            wit.push_str(&format!("    export {};\n", export));
+        }
+        wit.push_str("}\n");
+        validate_wit(&wit)?;
+
+        let mut code = insert_conversions(fragment_code, &self.conversions)?;
+        code.push_str(&format!(
+            r#"
+mod {bindings} {{
+    wit_bindgen::generate!({{
+        path: "wit",
+        world: "{world}",
+        exports: {{
+            world: super::DcmComponent,
+        }},
+    }});
+}}
+
+struct DcmComponent;
+
+impl {bindings}::Guest for DcmComponent {{
+{functions}}}
+"#,
+            bindings = BINDINGS_MODULE,
+            world = COMPONENT_WORLD,
+            functions = self.guest_functions.join("\n"),
+        ));
+        Ok(ComponentSources { wit, code })
+    }
+
+    /// Adds the export of a function, and the implementation of the export calling the function.
+    fn add_function(
+        &mut self,
+        export_prefix: Option<&str>,
+        function: &RustFunction,
+    ) -> Result<(), ApplicationError> {
+        let function_name = &function.properties.name;
+        if function.parameters.iter().any(|param| param.is_mutable) {
+            return Err(type_conversion_error(format!(
+                "`{}` takes `&mut` parameters, which cannot be expressed in WIT",
+                function_name
+            )));
+        }
+        let export_name = match export_prefix {
+            Some(prefix) => format!("{}-{}", prefix, get_wit_name(function_name)?),
+            None => get_wit_name(function_name)?,
+        };
+
+        let mut wit_params = Vec::new();
+        let mut rust_params = Vec::new();
+        let mut body = Vec::new();
+        let instance_type = match &function.receiver {
+            Some(receiver) => {
+                let struct_name = function.struct_name.clone().unwrap_or_default();
+                let instance_type = self.map_named_type(&struct_name)?;
+                wit_params.push(format!("instance: {}", instance_type.wit));
+                rust_params.push(format!("instance: {}", instance_type.rust));
+                let mutability = match receiver {
+                    ReceiverKind::Mutable => "mut ",
+                    _ => "",
+                };
+                body.push(format!(
+                    "let {}instance: crate::{} = instance.into();",
+                    mutability, struct_name
+                ));
+                Some(instance_type)
+            }
+            None => None,
+        };
+        for (index, param) in function.parameters.iter().enumerate() {
+            let ty = parse_type(&param.rust_type)?;
+            let wit_type = self.map_type(&ty)?;
+            wit_params.push(format!(
+                "{}: {}",
+                escape_wit_name(&get_wit_name(&param.name)?),
+                wit_type.wit
+            ));
+            rust_params.push(format!("param{}: {}", index, wit_type.rust));
+            body.push(format!(
+                "let param{}{} = {};",
+                index,
+                get_owned_type_annotation(param),
+                self.convert(&ty, &format!("param{}", index), Direction::FromWit)
+            ));
+        }
+
+        let args = function
+            .parameters
+            .iter()
+            .enumerate()
+            .map(|(index, param)| match param.is_reference {
+                true => format!("&param{}", index),
+                false => format!("param{}", index),
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        let call = match function.function_type {
+            RustFunctionType::FreeFunction => format!("crate::{}({})", function_name, args),
+            RustFunctionType::Method => match &function.receiver {
+                Some(receiver) => {
+                    format!(
+                        "{}.{}({})",
+                        receiver.wrap_instance("instance"),
+                        function_name,
+                        args
+                    )
+                }
+                None => format!("instance.{}({})", function_name, args),
+            },
+            RustFunctionType::AssociatedFunction => format!(
+                "crate::{}::{}({})",
+                function.struct_name.clone().unwrap_or_default(),
+                function_name,
+                args
+            ),
+        };
+
+        let return_type = parse_type(&function.return_type.rust_type)?;
+        let result_type = self.map_optional_type(&return_type)?;
+        match &result_type {
+            Some(_) => body.push(format!("let result = {};", call)),
+            None => body.push(format!("{};", call)),
+        }
+        if result_type.is_some() && function.return_type.is_reference {
+            body.push("let result = result.to_owned();".to_string());
+        }
+        // Only `&mut self` methods return the instance, since other receivers cannot change it
+        let returned_instance = match function.receiver {
+            Some(ReceiverKind::Mutable) => instance_type,
+            _ => None,
+        };
+        let (wit_result, rust_result) = match (result_type, returned_instance) {
+            (None, None) => (String::new(), String::new()),
+            (Some(result_type), None) => {
+                body.push(self.convert(&return_type, "result", Direction::ToWit));
+                (result_type.wit, result_type.rust)
+            }
+            (None, Some(instance_type)) => {
+                body.push("instance.into()".to_string());
+                (instance_type.wit, instance_type.rust)
+            }
+            (Some(result_type), Some(instance_type)) => {
+                body.push(format!(
+                    "({}, instance.into())",
+                    self.convert(&return_type, "result", Direction::ToWit)
+                ));
+                (
+                    format!("tuple<{}, {}>", result_type.wit, instance_type.wit),
+                    format!("({}, {})", result_type.rust, instance_type.rust),
+                )
+            }
+        };
+
+        self.exports.push(format!(
+            "{}: func({}){}",
+            escape_wit_name(&export_name),
+            wit_params.join(", "),
+            match wit_result.is_empty() {
+                true => String::new(),
+                false => format!(" -> {}", wit_result),
+            }
+        ));
+        self.guest_functions.push(format!(
+            "    fn {}({}){} {{\n        {}\n    }}\n",
+            get_binding_name(&export_name),
+            rust_params.join(", "),
+            match rust_result.is_empty() {
+                true => String::new(),
+                false => format!(" -> {}", rust_result),
+            },
+            body.join("\n        ")
+        ));
+        Ok(())
+    }
+
+    /// Maps a type which may be the unit type, which is represented by the absence of a type in WIT.
+    fn map_optional_type(&mut self, ty: &Type) -> Result<Option<WitType>, ApplicationError> {
+        match ty {
+            Type::Tuple(type_tuple) if type_tuple.elems.is_empty() => Ok(None),
+            _ => self.map_type(ty).map(Some),
+        }
+    }
+
+    fn map_type(&mut self, ty: &Type) -> Result<WitType, ApplicationError> {
+        match ty {
+            Type::Reference(type_reference) => self.map_type(&type_reference.elem),
+            Type::Paren(type_paren) => self.map_type(&type_paren.elem),
+            Type::Group(type_group) => self.map_type(&type_group.elem),
+            Type::Slice(type_slice) => Ok(list(self.map_type(&type_slice.elem)?)),
+            Type::Array(type_array) => Ok(list(self.map_type(&type_array.elem)?)),
+            Type::Tuple(type_tuple) if !type_tuple.elems.is_empty() => {
+                let elems = type_tuple
+                    .elems
+                    .iter()
+                    .map(|elem| self.map_type(elem))
+                    .collect::<Result<Vec<WitType>, ApplicationError>>()?;
+                Ok(tuple(elems))
+            }
+            Type::Path(type_path) => {
+                let Some(last_segment) = type_path.path.segments.last() else {
+                    return Err(unsupported_type(ty));
+                };
+                let generic_types = get_generic_types(&last_segment.arguments);
+                let type_name = last_segment.ident.to_string();
+                match (type_name.as_str(), generic_types.as_slice()) {
+                    ("u8" | "u16" | "u32" | "u64", _) => Ok(WitType::new(&type_name, &type_name)),
+                    ("i8" | "i16" | "i32" | "i64", _) => {
+                        Ok(WitType::new(&type_name.replace('i', "s"), &type_name))
+                    }
+                    // The fragments are compiled for wasm32, whose pointer sized integers are 32 bit wide
+                    ("usize", _) => Ok(WitType::new("u32", "u32")),
+                    ("isize", _) => Ok(WitType::new("s32", "i32")),
+                    ("f32", _) => Ok(WitType::new("float32", "f32")),
+                    ("f64", _) => Ok(WitType::new("float64", "f64")),
+                    ("bool", _) => Ok(WitType::new("bool", "bool")),
+                    ("char", _) => Ok(WitType::new("char", "char")),
+                    ("String" | "str", _) => Ok(WitType::new("string", "String")),
+                    ("Vec" | "VecDeque" | "LinkedList" | "HashSet" | "BTreeSet", [inner]) => {
+                        Ok(list(self.map_type(inner)?))
+                    }
+                    ("HashMap" | "BTreeMap", [key, value]) => {
+                        let entry = tuple(vec![self.map_type(key)?, self.map_type(value)?]);
+                        Ok(list(entry))
+                    }
+                    ("Option", [inner]) => {
+                        let inner = self.map_type(inner)?;
+                        Ok(WitType {
+                            wit: format!("option<{}>", inner.wit),
+                            rust: format!("Option<{}>", inner.rust),
+                        })
+                    }
+                    ("Result", [ok, err]) => {
+                        let ok = self.map_optional_type(ok)?;
+                        let err = self.map_optional_type(err)?;
+                        let wit = match (&ok, &err) {
+                            (None, None) => "result".to_string(),
+                            (Some(ok), None) => format!("result<{}>", ok.wit),
+                            (None, Some(err)) => format!("result<_, {}>", err.wit),
+                            (Some(ok), Some(err)) => format!("result<{}, {}>", ok.wit, err.wit),
+                        };
+                        let get_rust = |wit_type: Option<WitType>| {
+                            wit_type.map(|t| t.rust).unwrap_or_else(|| "()".to_string())
+                        };
+                        let rust = format!("Result<{}, {}>", get_rust(ok), get_rust(err));
+                        Ok(WitType { wit, rust })
+                    }
+                    ("Box", [inner]) => self.map_type(inner),
+                    ("Self", _) => match self.self_type.clone() {
+                        Some(self_type) => self.map_named_type(&self_type),
+                        None => Err(unsupported_type(ty)),
+                    },
+                    (name, _) => match self.type_mapper.get_aliased_type(name) {
+                        Some(aliased_type) if !self.pending_types.contains(name) => {
+                            self.pending_types.insert(name.to_string());
+                            let wit_type = self.map_type(&aliased_type.clone());
+                            self.pending_types.remove(name);
+                            wit_type
+                        }
+                        _ => self.map_named_type(name),
+                    },
+                }
+            }
+            _ => Err(unsupported_type(ty)),
+        }
+    }
+
+    /// Maps a struct to a record, and an enum to an enum or a variant, depending on whether its variants have fields.
+    fn map_named_type(&mut self, name: &str) -> Result<WitType, ApplicationError> {
+        let wit_name = get_wit_name(name)?;
+        let wit_type = WitType {
+            wit: escape_wit_name(&wit_name),
+            rust: format!("{}::{}", BINDINGS_MODULE, wit_name.to_upper_camel_case()),
+        };
+        if let Some((_, rust_name)) = self.type_definitions.get(&wit_name) {
+            return match rust_name == name {
+                true => Ok(wit_type),
+                false => Err(type_conversion_error(format!(
+                    "`{}` and `{}` have the same name in WIT",
+                    rust_name, name
+                ))),
+            };
+        }
+        if !self.pending_types.insert(name.to_string()) {
+            return Err(type_conversion_error(format!(
+                "`{}` is recursive, which cannot be expressed in WIT",
+                name
+            )));
+        }
+        let definition = if let Some(rust_struct) = self.type_mapper.get_struct(name) {
+            self.define_record(rust_struct, &wit_name)
+        } else if let Some(rust_enum) = self.type_mapper.get_enum(name) {
+            self.define_variant(rust_enum, &wit_name)
+        } else {
+            Err(type_conversion_error(format!(
+                "`{}` is not a struct or enum of the project",
+                name
+            )))
+        };
+        self.pending_types.remove(name);
+        self.type_definitions
+            .insert(wit_name, (definition?, name.to_string()));
+        Ok(wit_type)
+    }
+
+    fn define_record(
+        &mut self,
+        rust_struct: &RustStruct,
+        wit_name: &str,
+    ) -> Result<String, ApplicationError> {
+        let name = &rust_struct.properties.name;
+        let item_struct = syn::parse_str::<ItemStruct>(&rust_struct.properties.code)?;
+        if !item_struct.generics.params.is_empty() {
+            return Err(type_conversion_error(format!(
+                "`{}` is generic, which cannot be expressed in WIT",
+                name
+            )));
+        }
+        let Fields::Named(fields) = &item_struct.fields else {
+            return Err(type_conversion_error(format!(
+                "`{}` has no named fields, which records require",
+                name
+            )));
+        };
+        if fields.named.is_empty() {
+            return Err(type_conversion_error(format!(
+                "`{}` has no fields, which records require",
+                name
+            )));
+        }
+
+        let binding_type = format!(
+            "crate::{}::{}",
+            BINDINGS_MODULE,
+            wit_name.to_upper_camel_case()
+        );
+        let mut wit_fields = Vec::new();
+        let mut from_wit_fields = Vec::new();
+        let mut to_wit_fields = Vec::new();
+        for field in &fields.named {
+            let field_name = field.ident.as_ref().unwrap().to_string();
+            let field_wit_name = get_wit_name(&field_name)?;
+            let field_type = self.map_type(&field.ty)?;
+            wit_fields.push(format!(
+                "        {}: {},\n",
+                escape_wit_name(&field_wit_name),
+                field_type.wit
+            ));
+            let binding_field = get_binding_name(&field_wit_name);
+            from_wit_fields.push(format!(
+                "{}: {}",
+                field_name,
+                self.convert(
+                    &field.ty,
+                    &format!("value.{}", binding_field),
+                    Direction::FromWit
+                )
+            ));
+            to_wit_fields.push(format!(
+                "{}: {}",
+                binding_field,
+                self.convert(
+                    &field.ty,
+                    &format!("value.{}", field_name),
+                    Direction::ToWit
+                )
+            ));
+        }
+        self.conversions.push((
+            rust_struct.properties.module_hierarchy.clone(),
+            generate_from_impls(
+                name,
+                &binding_type,
+                &format!("Self {{ {} }}", from_wit_fields.join(", ")),
+                &format!("Self {{ {} }}", to_wit_fields.join(", ")),
+            ),
+        ));
+        Ok(format!(
+            "    record {} {{\n{}    }}\n",
+            escape_wit_name(wit_name),
+            wit_fields.concat()
+        ))
+    }
+
+    fn define_variant(
+        &mut self,
+        rust_enum: &RustEnum,
+        wit_name: &str,
+    ) -> Result<String, ApplicationError> {
+        let name = &rust_enum.properties.name;
+        let item_enum = syn::parse_str::<ItemEnum>(&rust_enum.properties.code)?;
+        if !item_enum.generics.params.is_empty() {
+            return Err(type_conversion_error(format!(
+                "`{}` is generic, which cannot be expressed in WIT",
+                name
+            )));
+        }
+        if rust_enum.variants.is_empty() {
+            return Err(type_conversion_error(format!(
+                "`{}` has no variants, which cannot be expressed in WIT",
+                name
+            )));
+        }
+
+        let binding_type = format!(
+            "crate::{}::{}",
+            BINDINGS_MODULE,
+            wit_name.to_upper_camel_case()
+        );
+        let is_enum = rust_enum
+            .variants
+            .iter()
+            .all(|variant| matches!(variant.fields, RustEnumVariantFields::Unit));
+        let mut wit_cases = Vec::new();
+        let mut from_wit_arms = Vec::new();
+        let mut to_wit_arms = Vec::new();
+        for variant in &rust_enum.variants {
+            let case_wit_name = get_wit_name(&variant.name)?;
+            let binding_case = format!("{}::{}", binding_type, case_wit_name.to_upper_camel_case());
+            let own_case = format!("{}::{}", name, variant.name);
+            match &variant.fields {
+                RustEnumVariantFields::Unit => {
+                    wit_cases.push(escape_wit_name(&case_wit_name));
+                    from_wit_arms.push(format!("{} => {}", binding_case, own_case));
+                    to_wit_arms.push(format!("{} => {}", own_case, binding_case));
+                }
+                RustEnumVariantFields::Unnamed(types) => {
+                    let types = types
+                        .iter()
+                        .map(|ty| parse_type(ty))
+                        .collect::<Result<Vec<Type>, ApplicationError>>()?;
+                    let payload_types = types.iter().map(|ty| self.map_type(ty)).collect::<Result<
+                        Vec<WitType>,
+                        ApplicationError,
+                    >>(
+                    )?;
+                    let payload = match payload_types.len() {
+                        1 => payload_types.into_iter().next().unwrap(),
+                        _ => tuple(payload_types),
+                    };
+                    wit_cases.push(format!(
+                        "{}({})",
+                        escape_wit_name(&case_wit_name),
+                        payload.wit
+                    ));
+                    let names = (0..types.len())
+                        .map(|index| format!("v{}", index))
+                        .collect::<Vec<String>>();
+                    let convert_all = |direction: Direction| {
+                        types
+                            .iter()
+                            .zip(&names)
+                            .map(|(ty, name)| self.convert(ty, name, direction))
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    };
+                    let (from_wit_payload, to_wit_payload) = match types.len() {
+                        1 => (
+                            convert_all(Direction::FromWit),
+                            convert_all(Direction::ToWit),
+                        ),
+                        _ => (
+                            format!(
+                                "{{ let ({}) = v; {}({}) }}",
+                                names.join(", "),
+                                own_case,
+                                convert_all(Direction::FromWit)
+                            ),
+                            format!("({})", convert_all(Direction::ToWit)),
+                        ),
+                    };
+                    match types.len() {
+                        1 => from_wit_arms.push(format!(
+                            "{}(v0) => {}({})",
+                            binding_case, own_case, from_wit_payload
+                        )),
+                        _ => from_wit_arms
+                            .push(format!("{}(v) => {}", binding_case, from_wit_payload)),
+                    }
+                    to_wit_arms.push(format!(
+                        "{}({}) => {}({})",
+                        own_case,
+                        names.join(", "),
+                        binding_case,
+                        to_wit_payload
+                    ));
+                }
+                RustEnumVariantFields::Named(fields) => {
+                    // Variant cases have a single payload, the fields are moved to a record named after the case
+                    let record_wit_name = format!("{}-{}", wit_name, case_wit_name);
+                    let record_binding_type = format!(
+                        "crate::{}::{}",
+                        BINDINGS_MODULE,
+                        record_wit_name.to_upper_camel_case()
+                    );
+                    let mut wit_fields = Vec::new();
+                    let mut from_wit_fields = Vec::new();
+                    let mut to_wit_fields = Vec::new();
+                    for field in fields {
+                        let ty = parse_type(&field.rust_type)?;
+                        let field_wit_name = get_wit_name(&field.name)?;
+                        let binding_field = get_binding_name(&field_wit_name);
+                        wit_fields.push(format!(
+                            "        {}: {},\n",
+                            escape_wit_name(&field_wit_name),
+                            self.map_type(&ty)?.wit
+                        ));
+                        from_wit_fields.push(format!(
+                            "{}: {}",
+                            field.name,
+                            self.convert(&ty, &format!("v.{}", binding_field), Direction::FromWit)
+                        ));
+                        to_wit_fields.push(format!(
+                            "{}: {}",
+                            binding_field,
+                            self.convert(&ty, &field.name, Direction::ToWit)
+                        ));
+                    }
+                    if self.type_definitions.contains_key(&record_wit_name) {
+                        return Err(type_conversion_error(format!(
+                            "The payload of `{}` has the same name as another type in WIT",
+                            own_case
+                        )));
+                    }
+                    self.type_definitions.insert(
+                        record_wit_name.clone(),
+                        (
+                            format!(
+                                "    record {} {{\n{}    }}\n",
+                                escape_wit_name(&record_wit_name),
+                                wit_fields.concat()
+                            ),
+                            own_case.clone(),
+                        ),
+                    );
+                    wit_cases.push(format!(
+                        "{}({})",
+                        escape_wit_name(&case_wit_name),
+                        escape_wit_name(&record_wit_name)
+                    ));
+                    from_wit_arms.push(format!(
+                        "{}(v) => {} {{ {} }}",
+                        binding_case,
+                        own_case,
+                        from_wit_fields.join(", ")
+                    ));
+                    to_wit_arms.push(format!(
+                        "{} {{ {} }} => {}({} {{ {} }})",
+                        own_case,
+                        fields
+                            .iter()
+                            .map(|field| field.name.clone())
+                            .collect::<Vec<String>>()
+                            .join(", "),
+                        binding_case,
+                        record_binding_type,
+                        to_wit_fields.join(", ")
+                    ));
+                }
+            }
+        }
+        self.conversions.push((
+            rust_enum.properties.module_hierarchy.clone(),
+            generate_from_impls(
+                name,
+                &binding_type,
+                &format!("match value {{ {} }}", from_wit_arms.join(", ")),
+                &format!("match value {{ {} }}", to_wit_arms.join(", ")),
+            ),
+        ));
+        let keyword = match is_enum {
+            true => "enum",
+            false => "variant",
+        };
+        Ok(format!(
+            "    {} {} {{\n{}    }}\n",
+            keyword,
+            escape_wit_name(wit_name),
+            wit_cases
+                .iter()
+                .map(|case| format!("        {},\n", case))
+                .collect::<String>()
+        ))
+    }
+
+    /// Returns the expression converting `expr` between a type of the project and its representation in the bindings.
+    /// The types must have been mapped before, project types are converted by the generated `From` implementations.
+    fn convert(&self, ty: &Type, expr: &str, direction: Direction) -> String {
+        match ty {
+            Type::Reference(type_reference) => self.convert(&type_reference.elem, expr, direction),
+            Type::Paren(type_paren) => self.convert(&type_paren.elem, expr, direction),
+            Type::Group(type_group) => self.convert(&type_group.elem, expr, direction),
+            Type::Slice(type_slice) => self.convert_sequence(&type_slice.elem, expr, direction),
+            Type::Array(type_array) => match direction {
+                Direction::ToWit => self.convert_sequence(&type_array.elem, expr, direction),
+                Direction::FromWit => format!(
+                    "{{ let values: Vec<_> = {}; values.try_into().unwrap_or_else(|_| panic!(\"Unexpected array length\")) }}",
+                    self.convert_sequence(&type_array.elem, expr, direction)
+                ),
+            },
+            Type::Tuple(type_tuple) => {
+                let names = (0..type_tuple.elems.len())
+                    .map(|index| format!("v{}", index))
+                    .collect::<Vec<String>>();
+                let converted = type_tuple
+                    .elems
+                    .iter()
+                    .zip(&names)
+                    .map(|(elem, name)| self.convert(elem, name, direction))
+                    .collect::<Vec<String>>();
+                let trailing_comma = if names.len() == 1 { "," } else { "" };
+                format!(
+                    "{{ let ({}{}) = {}; ({}{}) }}",
+                    names.join(", "),
+                    trailing_comma,
+                    expr,
+                    converted.join(", "),
+                    trailing_comma
+                )
+            }
+            Type::Path(type_path) => {
+                let Some(last_segment) = type_path.path.segments.last() else {
+                    return expr.to_string();
+                };
+                let generic_types = get_generic_types(&last_segment.arguments);
+                let type_name = last_segment.ident.to_string();
+                match (type_name.as_str(), generic_types.as_slice(), direction) {
+                    ("usize", _, Direction::ToWit) => format!("({} as u32)", expr),
+                    ("isize", _, Direction::ToWit) => format!("({} as i32)", expr),
+                    ("usize" | "isize", _, Direction::FromWit) => {
+                        format!("({} as {})", expr, type_name)
+                    }
+                    (
+                        "u8" | "u16" | "u32" | "u64" | "i8" | "i16" | "i32" | "i64" | "f32"
+                        | "f64" | "bool" | "char" | "String" | "str",
+                        _,
+                        _,
+                    ) => expr.to_string(),
+                    ("Vec" | "VecDeque" | "LinkedList" | "HashSet" | "BTreeSet", [inner], _) => {
+                        self.convert_sequence(inner, expr, direction)
+                    }
+                    ("HashMap" | "BTreeMap", [key, value], _) => format!(
+                        "{}.into_iter().map(|(k, v)| ({}, {})).collect()",
+                        expr,
+                        self.convert(key, "k", direction),
+                        self.convert(value, "v", direction)
+                    ),
+                    ("Option", [inner], _) => match self.convert(inner, "v", direction) {
+                        // Values represented by the same type in the bindings are passed as they are
+                        converted if converted == "v" => expr.to_string(),
+                        converted => format!("{}.map(|v| {})", expr, converted),
+                    },
+                    ("Result", [ok, err], _) => {
+                        let mut converted = expr.to_string();
+                        let ok = self.convert(ok, "v", direction);
+                        if ok != "v" {
+                            converted.push_str(&format!(".map(|v| {})", ok));
+                        }
+                        let err = self.convert(err, "e", direction);
+                        if err != "e" {
+                            converted.push_str(&format!(".map_err(|e| {})", err));
+                        }
+                        converted
+                    }
+                    ("Box", [inner], Direction::ToWit) => {
+                        self.convert(inner, &format!("(*{})", expr), direction)
+                    }
+                    ("Box", [inner], Direction::FromWit) => {
+                        format!("Box::new({})", self.convert(inner, expr, direction))
+                    }
+                    (name, _, _) => match self.type_mapper.get_aliased_type(name) {
+                        Some(aliased_type) => self.convert(aliased_type, expr, direction),
+                        None => format!("{}.into()", expr),
+                    },
+                }
+            }
+            _ => expr.to_string(),
+        }
+    }
+
+    fn convert_sequence(&self, elem: &Type, expr: &str, direction: Direction) -> String {
+        format!(
+            "{}.into_iter().map(|v| {}).collect()",
+            expr,
+            self.convert(elem, "v", direction)
+        )
+    }
+}
+
+fn list(elem: WitType) -> WitType {
+    WitType {
+        wit: format!("list<{}>", elem.wit),
+        rust: format!("Vec<{}>", elem.rust),
+    }
+}
+
+fn tuple(elems: Vec<WitType>) -> WitType {
+    let trailing_comma = if elems.len() == 1 { "," } else { "" };
+    WitType {
+        wit: format!(
+            "tuple<{}>",
+            elems
+                .iter()
+                .map(|elem| elem.wit.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        ),
+        rust: format!(
+            "({}{})",
+            elems
+                .iter()
+                .map(|elem| elem.rust.as_str())
+                .collect::<Vec<&str>>()
+                .join(", "),
+            trailing_comma
+        ),
+    }
+}
+
+fn generate_from_impls(name: &str, binding_type: &str, from_wit: &str, to_wit: &str) -> String {
+    format!(
+        "impl From<{binding_type}> for {name} {{
+    fn from(value: {binding_type}) -> Self {{
+        {from_wit}
+    }}
+}}
+
+impl From<{name}> for {binding_type} {{
+    fn from(value: {name}) -> Self {{
+        {to_wit}
+    }}
+}}"
+    )
+}
+
+/// Returns the WIT name of a Rust identifier. WIT names are kebab case, and their words cannot start with a digit.
+fn get_wit_name(name: &str) -> Result<String, ApplicationError> {
+    let mut words: Vec<String> = Vec::new();
+    for word in name.to_kebab_case().split('-') {
+        match (
+            word.starts_with(|c: char| c.is_ascii_digit()),
+            words.last_mut(),
+        ) {
+            (true, Some(last_word)) => last_word.push_str(word),
+            _ => words.push(word.to_string()),
+        }
+    }
+    let wit_name = words.join("-");
+    let is_valid = wit_name.split('-').all(|word| {
+        word.starts_with(|c: char| c.is_ascii_lowercase())
+            && word
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+    });
+    match is_valid {
+        true => Ok(wit_name),
+        false => Err(type_conversion_error(format!(
+            "`{}` cannot be named in WIT",
+            name
+        ))),
+    }
+}
+
+/// WIT keywords are used as names by prefixing them with `%`.
+fn escape_wit_name(wit_name: &str) -> String {
+    match WIT_KEYWORDS.contains(&wit_name) {
+        true => format!("%{}", wit_name),
+        false => wit_name.to_string(),
+    }
+}
+
+/// Returns the name wit-bindgen gives to a function, parameter or field in the bindings.
+fn get_binding_name(wit_name: &str) -> String {
+    let name = wit_name.to_snake_case();
+    match RUST_KEYWORDS.contains(&name.as_str()) {
+        true => format!("{}_", name),
+        false => name,
+    }
+}
+
+fn validate_wit(wit: &str) -> Result<(), ApplicationError> {
+    let package = wit_parser::UnresolvedPackage::parse(Path::new("world.wit"), wit)
+        .map_err(|e| type_conversion_error(format!("Invalid WIT generated: {:#}", e)))?;
+    wit_parser::Resolve::default()
+        .push(package)
+        .map_err(|e| type_conversion_error(format!("Invalid WIT generated: {:#}", e)))?;
+    Ok(())
+}
+
+/// Places the conversions in the modules the converted types are defined in, where their private fields are accessible.
+fn insert_conversions(
+    code: &str,
+    conversions: &[(Vec<String>, String)],
+) -> Result<String, ApplicationError> {
+    let mut file = syn::parse_file(code)?;
+    for (module_hierarchy, conversion) in conversions {
+        let mut items = &mut file.items;
+        for module_name in module_hierarchy {
+            let module = items.iter_mut().find_map(|item| match item {
+                syn::Item::Mod(item_mod) if item_mod.ident == module_name => {
+                    item_mod.content.as_mut()
+                }
+                _ => None,
+            });
+            items = match module {
+                Some((_, module_items)) => module_items,
+                None => {
+                    return Err(type_conversion_error(format!(
+                        "Module `{}` is missing in the fragment",
+                        module_hierarchy.join("::")
+                    )))
+                }
+            };
+        }
+        items.extend(syn::parse_file(conversion)?.items);
+    }
+    Ok(file.to_token_stream().to_string())
+}
+
+fn parse_type(rust_type: &str) -> Result<Type, ApplicationError> {
+    Ok(syn::parse_str::<Type>(rust_type)?)
+}
+
+fn get_generic_types(arguments: &PathArguments) -> Vec<&Type> {
+    match arguments {
+        PathArguments::AngleBracketed(arguments) => arguments
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn unsupported_type(ty: &Type) -> ApplicationError {
+    type_conversion_error(format!(
+        "`{}` cannot be expressed in WIT",
+        ty.to_token_stream()
+    ))
+}
+
+fn type_conversion_error(message: String) -> ApplicationError {
+    ApplicationError::TypeConversionError { message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::source_code_analyzer::rust_file::RustFile;
+    use crate::modules::source_code_analyzer::types::rust_impl::RustImpl;
+
+    const SOURCE_CODE: &str = r#"
+        pub mod shop {
+            use super::*;
+            pub struct Cart { items: Vec<Item>, discount: Option<u8>, status: Status }
+            pub struct Item { name: String, quantity: usize }
+            pub enum Status { Open, Shipped(u64), Cancelled { reason: String } }
+            pub struct Node { children: Vec<Node> }
+            impl Cart {
+                pub fn new() -> Self { todo!() }
+                pub fn add(&mut self, item: Item) {}
+                pub fn total(&self, prices: &HashMap<String, f64>) -> f64 { 0.0 }
+                pub fn checkout(self) -> Result<Status, String> { todo!() }
+            }
+        }
+        pub use shop::Cart;
+        pub fn count(items: &[shop::Item], text: &str) -> (u32, bool) { (0, false) }
+    "#;
+
+    fn get_rust_file() -> (RustFile, syn::File) {
+        let syntax_tree = syn::parse_file(SOURCE_CODE).unwrap();
+        let mut rust_file = RustFile::default();
+        for item in &syntax_tree.items {
+            let syn::Item::Mod(item_mod) = item else {
+                continue;
+            };
+            for item in &item_mod.content.as_ref().unwrap().1 {
+                let code = item.to_token_stream().to_string();
+                match item.clone() {
+                    syn::Item::Struct(item_struct) => {
+                        let mut rust_struct: RustStruct = item_struct.into();
+                        rust_struct.properties.code = code;
+                        rust_struct.properties.module_hierarchy = vec!["shop".to_string()];
+                        rust_file.structs.push(rust_struct);
+                    }
+                    syn::Item::Enum(item_enum) => {
+                        let mut rust_enum: RustEnum = item_enum.into();
+                        rust_enum.properties.code = code;
+                        rust_enum.properties.module_hierarchy = vec!["shop".to_string()];
+                        rust_file.enums.push(rust_enum);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        (rust_file, syntax_tree)
+    }
+
+    fn get_impl_fragment(syntax_tree: &syn::File) -> ObjectFragment {
+        let syn::Item::Mod(item_mod) = &syntax_tree.items[0] else {
+            unreachable!()
+        };
+        let item_impl = item_mod
+            .content
+            .as_ref()
+            .unwrap()
+            .1
+            .iter()
+            .find_map(|item| match item {
+                syn::Item::Impl(item_impl) => Some(item_impl.clone()),
+                _ => None,
+            })
+            .unwrap();
+        let mut rust_impl: RustImpl = item_impl.into();
+        rust_impl.properties.code = SOURCE_CODE.to_string();
+        ObjectFragment {
+            id: "cart".to_string(),
+            cargo_toml_content: Default::default(),
+            initial_execution_location: Default::default(),
+            crates: Vec::new(),
+            dependencies: Vec::new(),
+            fragment_type: Default::default(),
+            module_hierarchy: vec!["shop".to_string()],
+            rust_struct: Default::default(),
+            rust_impl,
+            mutable_global_state: Vec::new(),
+            bundle: None,
+            tests: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_run_for_impl() {
+        let (rust_file, syntax_tree) = get_rust_file();
+        let type_mapper = TypeMapper::new(&[rust_file], false);
+        let sources = run_for_impl(&get_impl_fragment(&syntax_tree), &type_mapper).unwrap();
+
+        assert!(sources.wit.contains("record cart {\n        items: list<item>,\n        discount: option<u8>,\n        status: status,\n    }"));
+        assert!(sources
+            .wit
+            .contains("record item {\n        name: string,\n        quantity: u32,\n    }"));
+        assert!(sources.wit.contains("variant status {\n        open,\n        shipped(u64),\n        cancelled(status-cancelled),\n    }"));
+        assert!(sources.wit.contains("export cart-new: func() -> cart;"));
+        assert!(sources
+            .wit
+            .contains("export cart-add: func(instance: cart, item: item) -> cart;"));
+        assert!(sources.wit.contains(
+            "export cart-total: func(instance: cart, prices: list<tuple<string, float64>>) -> float64;"
+        ));
+        assert!(sources
+            .wit
+            .contains("export cart-checkout: func(instance: cart) -> result<status, string>;"));
+
+        assert!(sources
+            .code
+            .contains("impl From < crate :: dcm_component :: Item > for Item"));
+        assert!(sources
+            .code
+            .contains("quantity : (value . quantity as usize)"));
+        assert!(sources.code.contains("fn cart_add(instance: dcm_component::Cart, param0: dcm_component::Item) -> dcm_component::Cart"));
+        assert!(sources
+            .code
+            .contains("let mut instance: crate::Cart = instance.into();"));
+        assert!(sources
+            .code
+            .contains("let param0 = param0.into_iter().map(|(k, v)| (k, v)).collect();"));
+        assert!(sources.code.contains("result.map(|v| v.into())\n"));
+        syn::parse_file(&sources.code).unwrap();
+    }
+
+    #[test]
+    fn test_run_for_function() {
+        let (rust_file, syntax_tree) = get_rust_file();
+        let type_mapper = TypeMapper::new(&[rust_file], false);
+        let syn::Item::Fn(item_fn) = syntax_tree.items.last().unwrap().clone() else {
+            unreachable!()
+        };
+        let mut rust_function: RustFunction = item_fn.into();
+        rust_function.properties.code = SOURCE_CODE.to_string();
+        let fragment = FunctionFragment {
+            id: "count".to_string(),
+            cargo_toml_content: Default::default(),
+            initial_execution_location: Default::default(),
+            crates: Vec::new(),
+            dependencies: Vec::new(),
+            fragment_type: Default::default(),
+            rust_function,
+            module_hierarchy: Vec::new(),
+            mutable_global_state: Vec::new(),
+            bundle: None,
+            tests: Vec::new(),
+        };
+        let sources = run_for_function(&fragment, &type_mapper).unwrap();
+        assert!(sources
+            .wit
+            .contains("export count: func(items: list<item>, text: string) -> tuple<u32, bool>;"));
+        assert!(sources.code.contains("let param1: String = param1;"));
+        assert!(sources
+            .code
+            .contains("let result = crate::count(&param0, &param1);"));
+    }
+
+    #[test]
+    fn test_unsupported_types() {
+        let (rust_file, _) = get_rust_file();
+        let type_mapper = TypeMapper::new(&[rust_file], false);
+        let mut builder = ComponentBuilder::new(&type_mapper, None);
+        assert!(builder.map_type(&parse_type("Node").unwrap()).is_err());
+        assert!(builder.map_type(&parse_type("u128").unwrap()).is_err());
+        assert!(builder.map_type(&parse_type("Rc<u8>").unwrap()).is_err());
+        assert_eq!(
+            builder
+                .map_type(&parse_type("Option<&[i16]>").unwrap())
+                .unwrap()
+                .wit,
+            "option<list<s16>>"
+        );
+    }
+
+    #[test]
+    fn test_get_wit_name() {
+        assert_eq!(
+            get_wit_name("get_http_2_response").unwrap(),
+            "get-http2-response"
+        );
+        assert_eq!(get_wit_name("OrderManager").unwrap(), "order-manager");
+        assert_eq!(escape_wit_name("type"), "%type");
+        assert_eq!(get_binding_name("type"), "type_");
+    }
+}
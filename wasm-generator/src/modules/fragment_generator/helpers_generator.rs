@@ -1,25 +1,58 @@
 use crate::modules::application::traits::fragment::Fragment;
 use crate::modules::application::MobileFragments;
+use crate::modules::fragment_generator::wasm_wrapper_generator::returns_mutable_borrow;
+use crate::modules::source_code_analyzer::types::rust_function::RustFunction;
 
+/// Fragments built into a bundle share the helpers placed at the root of the bundle, see `generate_abi_helpers`.
 pub fn generate_helper(mobile_fragments: &mut MobileFragments) {
-    for executable_fragment in mobile_fragments.functions.iter_mut() {
+    for executable_fragment in mobile_fragments
+        .functions
+        .iter_mut()
+        .filter(|fragment| fragment.bundle.is_none())
+    {
         executable_fragment.rust_function.properties.code = format!(
             "{}\n{}",
-            HELPER_FUNCTIONS, executable_fragment.rust_function.properties.code
+            generate_abi_helpers(&executable_fragment.get_functions()),
+            executable_fragment.rust_function.properties.code
         );
     }
-    for executable_fragment in mobile_fragments.impls.iter_mut() {
+    for executable_fragment in mobile_fragments
+        .impls
+        .iter_mut()
+        .filter(|fragment| fragment.bundle.is_none())
+    {
         executable_fragment.set_code(format!(
             "{}\n{}",
-            HELPER_FUNCTIONS,
+            generate_abi_helpers(&executable_fragment.get_functions()),
             executable_fragment.get_code()
         ));
     }
 }
 
+/// The version of the binary interface between the hosts and the fragments, see "Fragment ABI" in the readme.
+/// It must be incremented whenever the layout of the parameters or the results changes.
+pub const ABI_VERSION: u32 = 3;
+
+// Returns the helpers used by the wrappers of the given functions, unused helpers would be reported as dead code
+pub(crate) fn generate_abi_helpers(functions: &[RustFunction]) -> String {
+    let mut helpers = format!(
+        "{}\nconst DCM_ABI_VERSION: u32 = {};\n",
+        HELPER_FUNCTIONS, ABI_VERSION
+    );
+    if functions.iter().any(returns_mutable_borrow) {
+        helpers.push_str(ENCODED_RESULT_HELPER);
+    }
+    helpers
+}
+
 const HELPER_FUNCTIONS: &str = r#"
 use serde_derive::{Deserialize, Serialize};
 
+#[no_mangle]
+pub extern "C" fn dcm_abi_version() -> u32 {
+    DCM_ABI_VERSION
+}
+
 #[no_mangle]
 pub extern "C" fn alloc(size: usize) -> *mut u8 {
     let layout = std::alloc::Layout::from_size_align(size, 4).unwrap();
@@ -54,17 +87,118 @@ fn extract_parameters(params_ptr: *const u8, parameter_count: usize) -> Vec<Vec<
     parameters
 }
 
-fn serialize_result(map: serde_json::Map<String, serde_json::Value>) -> *const u8 {
-    let result_json = serde_json::Value::Object(map);
-    let result_json_string = serde_json::to_string(&result_json).unwrap();
-    let result_bytes = rmp_serde::to_vec_named(&result_json_string).unwrap();
-    let len_bytes = (result_bytes.len() as u32).to_le_bytes();
+#[derive(Serialize)]
+struct DcmResponse<R, S, M> {
+    result: R,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<S>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mutated: Option<M>,
+}
+
+#[derive(Serialize)]
+struct DcmError<'a> {
+    code: &'a str,
+    message: String,
+}
+
+const DCM_STATUS_OK: u8 = 0;
+const DCM_STATUS_ERROR: u8 = 1;
+const DCM_RESPONSE_HEADER_SIZE: usize = 8;
+
+static DCM_PANIC_MESSAGE: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+
+// Panics abort on wasm targets, so the message is captured before the instance traps and handed out by dcm_take_panic
+fn dcm_install_panic_hook() {
+    static INSTALL_PANIC_HOOK: std::sync::Once = std::sync::Once::new();
+    INSTALL_PANIC_HOOK.call_once(|| {
+        std::panic::set_hook(Box::new(|panic_info| {
+            if let Ok(mut panic_message) = DCM_PANIC_MESSAGE.lock() {
+                *panic_message = Some(panic_info.to_string());
+            }
+        }));
+    });
+}
+
+#[no_mangle]
+pub extern "C" fn dcm_take_panic() -> *const u8 {
+    let panic_message = DCM_PANIC_MESSAGE.lock().ok().and_then(|mut panic_message| panic_message.take());
+    match panic_message {
+        Some(message) => serialize_error("Panic", message),
+        None => std::ptr::null(),
+    }
+}
+
+fn serialize_result<R: serde::Serialize, S: serde::Serialize, M: serde::Serialize>(
+    result: &R,
+    state: Option<&S>,
+    mutated: Option<&M>,
+) -> *const u8 {
+    match rmp_serde::to_vec_named(&DcmResponse { result, state, mutated }) {
+        Ok(payload) => write_response(DCM_STATUS_OK, payload),
+        Err(e) => serialize_error("SerializationFailed", e.to_string()),
+    }
+}
 
-    let mut combined = Vec::new();
-    combined.extend_from_slice(&len_bytes);
-    combined.extend(&result_bytes);
+fn serialize_error(code: &str, message: String) -> *const u8 {
+    let payload = rmp_serde::to_vec_named(&DcmError { code, message }).unwrap_or_default();
+    write_response(DCM_STATUS_ERROR, payload)
+}
 
-    let leaked = combined.leak();
+fn write_response(status: u8, payload: Vec<u8>) -> *const u8 {
+    let mut combined = Vec::with_capacity(DCM_RESPONSE_HEADER_SIZE + payload.len());
+    combined.push(DCM_ABI_VERSION as u8);
+    combined.push(status);
+    combined.extend_from_slice(&[0, 0]);
+    combined.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    combined.extend(&payload);
+
+    // The buffer is released by the host through dcm_free_result, which derives its size from the header
+    let leaked = Box::leak(combined.into_boxed_slice());
     leaked.as_ptr()
 }
+
+#[no_mangle]
+pub extern "C" fn dcm_free_result(ptr: *mut u8) {
+    if ptr.is_null() {
+        return;
+    }
+    unsafe {
+        let len_bytes = std::slice::from_raw_parts(ptr.add(4), 4);
+        let payload_len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+        let buffer = std::ptr::slice_from_raw_parts_mut(ptr, DCM_RESPONSE_HEADER_SIZE + payload_len);
+        drop(Box::from_raw(buffer));
+    }
+}
+"#;
+
+// Used by the functions returning a borrow of their instance or of a `&mut` parameter, whose result is encoded before
+// the borrow ends and the instance and parameters are serialized. The map is written as rmp_serde writes DcmResponse.
+const ENCODED_RESULT_HELPER: &str = r#"
+fn serialize_encoded_result<S: serde::Serialize, M: serde::Serialize>(
+    result: Result<Vec<u8>, rmp_serde::encode::Error>,
+    state: Option<&S>,
+    mutated: Option<&M>,
+) -> *const u8 {
+    let fields = [
+        ("result", Some(result)),
+        ("state", state.map(rmp_serde::to_vec_named)),
+        ("mutated", mutated.map(rmp_serde::to_vec_named)),
+    ];
+    let mut payload = Vec::new();
+    let field_count = fields.iter().filter(|(_, value)| value.is_some()).count();
+    // Writing to a Vec cannot fail
+    rmp::encode::write_map_len(&mut payload, field_count as u32).unwrap();
+    for (key, value) in fields {
+        match value {
+            Some(Ok(bytes)) => {
+                rmp::encode::write_str(&mut payload, key).unwrap();
+                payload.extend(bytes);
+            }
+            Some(Err(e)) => return serialize_error("SerializationFailed", e.to_string()),
+            None => {}
+        }
+    }
+    write_response(DCM_STATUS_OK, payload)
+}
 "#;
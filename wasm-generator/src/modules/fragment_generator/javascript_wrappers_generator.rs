@@ -2,14 +2,98 @@ use crate::modules::application::function_fragment::FunctionFragment;
 use crate::modules::application::object_fragment::ObjectFragment;
 use crate::modules::application::traits::fragment::Fragment;
 use crate::modules::application::MobileFragments;
-use crate::modules::source_code_analyzer::types::rust_function::{RustFunction, RustFunctionType};
-use crate::modules::util;
+use crate::modules::source_code_analyzer::types::rust_function::{
+    ParameterInfo, ReceiverKind, RustFunction, RustFunctionType,
+};
+use crate::modules::util::type_mapper::{get_property_name, NamedType, TypeMapper};
+use std::collections::BTreeSet;
+
+/// Returns whether an associated function creates an instance of its struct, which the JS glue wraps in a class instance.
+pub(crate) fn is_constructor(function: &RustFunction) -> bool {
+    let Some(struct_name) = function.struct_name.as_ref() else {
+        return false;
+    };
+    let return_type = &function.return_type.rust_type;
+    function.function_type == RustFunctionType::AssociatedFunction
+        && (return_type.contains("Self")
+            || return_type.contains(struct_name)
+            || return_type.contains(format!("Result<{},", struct_name).as_str())
+            || return_type.contains(format!("Option<{}>", struct_name).as_str())
+            || return_type.contains("Option<Self>")
+            || return_type.contains("Result<Self,"))
+}
+
+// Returns whether a method returns a new instance of its struct, e.g. a builder method consuming `self`
+fn returns_self(function: &RustFunction) -> bool {
+    let return_type = &function.return_type.rust_type;
+    !function.return_type.is_reference
+        && (return_type == "Self" || function.struct_name.as_ref() == Some(return_type))
+}
+
+// The values of `&mut` parameters are returned in `mutated`, in the order of the parameters, and written back into the
+// arguments passed by the caller. Primitives are passed by value in JS and cannot be written back.
+fn generate_write_back(params: &[ParameterInfo], response: &str) -> String {
+    params
+        .iter()
+        .filter(|param| param.is_mutable)
+        .enumerate()
+        .map(|(i, param)| {
+            format!(
+                "    writeBack({}, {}.mutated[{}]);\n",
+                param.name, response, i
+            )
+        })
+        .collect()
+}
+
+// With `js_bigint`, the 64 and 128 bit integers of the response are converted to BigInt, the ones decoded as numbers
+// included. Returns the conversion of the response held by `response`, or nothing if it holds no such integers.
+fn generate_bigint_conversion(
+    function: &RustFunction,
+    type_mapper: &TypeMapper,
+    response: &str,
+) -> String {
+    let self_type = function.struct_name.as_deref();
+    let mut members = Vec::new();
+    if let Some(schema) = type_mapper.get_bigint_schema(&function.return_type.rust_type, self_type)
+    {
+        members.push(format!("result: {}", schema));
+    }
+    if function.receiver == Some(ReceiverKind::Mutable) {
+        if let Some(schema) = type_mapper.get_bigint_schema("Self", self_type) {
+            members.push(format!("state: {}", schema));
+        }
+    }
+    let mutated = function
+        .parameters
+        .iter()
+        .filter(|param| param.is_mutable)
+        .map(|param| type_mapper.get_bigint_schema(&param.rust_type, self_type))
+        .collect::<Vec<Option<String>>>();
+    if mutated.iter().any(Option::is_some) {
+        let mutated = mutated
+            .into_iter()
+            .map(|schema| schema.unwrap_or_else(|| "null".to_string()))
+            .collect::<Vec<String>>();
+        members.push(format!("mutated: {{ tuple: [{}] }}", mutated.join(", ")));
+    }
+    match members.is_empty() {
+        true => String::new(),
+        false => format!(
+            "    {} = toBigInt({}, {{ fields: {{ {} }} }});\n",
+            response,
+            response,
+            members.join(", ")
+        ),
+    }
+}
 
 // Function to generate JavaScript function or method based on RustFunction
 fn generate_js_function(
     fragment_id: &String,
     function: &RustFunction,
     function_type: RustFunctionType,
+    type_mapper: &TypeMapper,
 ) -> String {
     let mut js_function_code = String::new();
 
@@ -20,16 +104,30 @@ fn generate_js_function(
         .into_iter()
         .map(|param| param.name)
         .collect::<Vec<_>>();
-    let json_return_type = &function.return_type.js_type;
+    let self_type = function.struct_name.as_deref();
+    let json_return_type = match is_constructor(function) {
+        true => format!("{} | null", self_type.unwrap_or("unknown")),
+        false => type_mapper.get_js_type(&function.return_type.rust_type, self_type),
+    };
 
     // Create parameter list
     let param_names_str = param_names.join(", ");
+    // Tells the executors to decode 64 bit integers exactly
+    let use_bigint = match type_mapper.uses_bigint() {
+        true => ", true",
+        false => "",
+    };
 
     // Create jsDoc lines
     let jsdoc_lines: Vec<String> = params
         .iter()
-        .enumerate()
-        .map(|(_, param)| format!("   * @param {{{}}} {}", param.js_type, param.name))
+        .map(|param| {
+            format!(
+                "   * @param {{{}}} {}",
+                type_mapper.get_js_type(&param.rust_type, self_type),
+                param.name
+            )
+        })
         .collect();
 
     let return_jsdoc_line = format!(
@@ -67,52 +165,76 @@ fn generate_js_function(
 
     // Generate the logic for executing the Rust function
     let mut execute_call = String::new();
+    let response = if function_type == RustFunctionType::AssociatedFunction {
+        "res"
+    } else {
+        "result"
+    };
+    let write_back = generate_write_back(params, response);
+    let bigint_conversion = generate_bigint_conversion(function, type_mapper, response);
     match function_type {
         RustFunctionType::FreeFunction => {
             let execute_call_base = format!(
-                "    let result = await cdm.execute(\"{}\", \"{}\", [{}]);\n",
-                fragment_id, function_name, param_names_str
+                "    let result = await cdm.execute(\"{}\", \"{}\", [{}]{});\n",
+                fragment_id, function_name, param_names_str, use_bigint
             );
             let return_logic = String::from("    return result.result;\n");
 
             execute_call.push_str(&execute_call_base);
+            execute_call.push_str(&bigint_conversion);
+            execute_call.push_str(&write_back);
             execute_call.push_str(&return_logic);
         }
         RustFunctionType::Method => {
+            // Instances consumed by a `self` method cannot be used afterwards, like in Rust
+            let receiver_logic = match &function.receiver {
+                Some(receiver) if receiver.consumes_instance() => {
+                    "    ensureNotConsumed(this);\n    consumedInstances.add(this);\n"
+                }
+                _ => "    ensureNotConsumed(this);\n",
+            };
             let execute_call_base = format!(
-                "    let result = await cdm.execute(\"{}\", \"{}\", [{}, this]);\n Object.assign(this, result.state);\n",
-                fragment_id, function_name, param_names_str
+                "    let result = await cdm.execute(\"{}\", \"{}\", [{}, this]{});\n",
+                fragment_id, function_name, param_names_str, use_bigint
             );
+            // Only `&mut self` methods return the state of the instance
+            let state_logic = match function.receiver {
+                Some(ReceiverKind::Mutable) => "    Object.assign(this, result.state);\n",
+                _ => "",
+            };
 
-            let return_logic = String::from("    return result.result;\n");
+            let return_logic = if returns_self(function) {
+                String::from(
+                    "    const newInstance = new this.constructor();\n    Object.assign(newInstance, result.result);\n    return newInstance;\n"
+                )
+            } else {
+                String::from("    return result.result;\n")
+            };
 
+            execute_call.push_str(receiver_logic);
             execute_call.push_str(&execute_call_base);
+            execute_call.push_str(&bigint_conversion);
+            execute_call.push_str(state_logic);
+            execute_call.push_str(&write_back);
             execute_call.push_str(&return_logic);
         }
         RustFunctionType::AssociatedFunction => {
-            let struct_name = function.struct_name.as_ref().unwrap();
-            let return_type = &function.return_type.rust_type;
-            let is_constructor = return_type.contains("Self")
-                || return_type.contains(struct_name)
-                || return_type.contains(format!("Result<{},", struct_name).as_str())
-                || return_type.contains(format!("Option<{}>", struct_name).as_str())
-                || return_type.contains("Option<Self>")
-                || return_type.contains("Result<Self,");
-
             let execute_call_base = format!(
-                "      let res = await cdm.execute(\"{}\", \"{}\", [{}]);\n",
-                fragment_id, function_name, param_names_str
+                "      let res = await cdm.execute(\"{}\", \"{}\", [{}]{});\n",
+                fragment_id, function_name, param_names_str, use_bigint
             );
 
-            let return_logic = if is_constructor {
+            let return_logic = if is_constructor(function) {
                 String::from(
                     "      const result = res.result;\n   if (result !== null && result !== undefined) {\n        const newInstance = new this();\n        Object.assign(newInstance, result);\n        return newInstance;\n    }\n    return null;\n"
                 )
             } else {
-                String::from("    return result;\n")
+                String::from("    return res.result;\n")
             };
 
             execute_call.push_str(&execute_call_base);
+            execute_call.push_str(&bigint_conversion);
+            execute_call.push_str(&write_back);
             execute_call.push_str(&return_logic);
         }
     }
@@ -128,7 +250,10 @@ fn generate_js_function(
     js_function_code
 }
 
-fn generate_js_glue_for_free_functions(function_fragments: &Vec<FunctionFragment>) -> String {
+fn generate_js_glue_for_free_functions(
+    function_fragments: &Vec<FunctionFragment>,
+    type_mapper: &TypeMapper,
+) -> String {
     let mut js_code = String::new();
 
     // Handle free functions
@@ -137,13 +262,17 @@ fn generate_js_glue_for_free_functions(function_fragments: &Vec<FunctionFragment
             &fragment.id,
             &fragment.rust_function,
             fragment.rust_function.function_type.clone(),
+            type_mapper,
         ));
     }
 
     js_code
 }
 
-fn generate_js_glue_for_impl_block(impl_fragments: &Vec<ObjectFragment>) -> String {
+fn generate_js_glue_for_impl_block(
+    impl_fragments: &Vec<ObjectFragment>,
+    type_mapper: &TypeMapper,
+) -> String {
     let mut js_code = String::new();
 
     // Handle impl blocks
@@ -153,25 +282,23 @@ fn generate_js_glue_for_impl_block(impl_fragments: &Vec<ObjectFragment>) -> Stri
 
         // Generate JavaScript properties based on struct_data
         for field in &impl_fragment.rust_struct.fields {
-            js_code.push_str(&format!("  /** @type {{{}}} */\n", field.js_type));
+            js_code.push_str(&format!(
+                "  /** @type {{{}}} */\n",
+                type_mapper.get_js_type(&field.rust_type, Some(class_name))
+            ));
             js_code.push_str(&format!(
                 "  {} = {};\n",
-                field.name,
-                util::get_default_value_for_js_type(&field.js_type)
+                get_property_name(&field.serialized_name),
+                type_mapper.get_default_value(&field.rust_type)
             ));
         }
 
         for function in &impl_fragment.rust_impl.functions {
-            // reference types can be used internally but cannot be exposed outside of WebAssembly module
-            if function.return_type.is_reference
-                || function.parameters.iter().any(|p| p.is_reference)
-            {
-                continue;
-            }
             js_code.push_str(&generate_js_function(
                 &impl_fragment.id,
                 function,
                 function.function_type.clone(),
+                type_mapper,
             ));
         }
 
@@ -181,7 +308,144 @@ fn generate_js_glue_for_impl_block(impl_fragments: &Vec<ObjectFragment>) -> Stri
     js_code
 }
 
-pub fn run(mobile_fragments: &MobileFragments) -> String {
+/// Generates JSDoc typedefs for the structs and enums used by the exported functions, so editors can check the values
+/// passed to and returned from the fragments.
+fn generate_js_typedefs(mobile_fragments: &MobileFragments, type_mapper: &TypeMapper) -> String {
+    let mut js_code = String::new();
+    for named_type in type_mapper.get_reachable_types(
+        get_exported_rust_types(mobile_fragments),
+        &get_class_names(mobile_fragments),
+    ) {
+        match named_type {
+            NamedType::Struct(rust_struct) => {
+                js_code.push_str(&format!(
+                    "/**\n * @typedef {{Object}} {}\n",
+                    rust_struct.properties.name
+                ));
+                for field in &rust_struct.fields {
+                    js_code.push_str(&format!(
+                        " * @property {{{}}} {}\n",
+                        type_mapper.get_js_type(&field.rust_type, None),
+                        get_property_name(&field.serialized_name)
+                    ));
+                }
+                js_code.push_str(" */\n\n");
+            }
+            NamedType::Enum(rust_enum) => js_code.push_str(&format!(
+                "/** @typedef {{{}}} {} */\n\n",
+                type_mapper.get_enum_union(rust_enum),
+                rust_enum.properties.name
+            )),
+        }
+    }
+    js_code
+}
+
+/// Generates `toBigInt`, which converts the integers of a value located by a schema of `TypeMapper::get_bigint_schema`
+/// to BigInt, and the schemas of the structs and enums it refers to.
+fn generate_bigint_helpers(type_mapper: &TypeMapper) -> String {
+    let type_schemas = type_mapper
+        .get_bigint_type_schemas()
+        .into_iter()
+        .map(|(name, schema)| format!("    {}: {},\n", name, schema))
+        .collect::<String>();
+    format!(
+        r#"
+const BIGINT_TYPES = {{
+{}}};
+
+function toBigInt(value, schema) {{
+    if (value === null || value === undefined) {{
+        return value;
+    }}
+    if (schema === 'bigint') {{
+        return BigInt(value);
+    }}
+    if (schema.type) {{
+        return toBigInt(value, BIGINT_TYPES[schema.type]);
+    }}
+    if (schema.array) {{
+        return value.map((item) => toBigInt(item, schema.array));
+    }}
+    if (schema.tuple) {{
+        return value.map((item, i) => schema.tuple[i] ? toBigInt(item, schema.tuple[i]) : item);
+    }}
+    if (schema.record) {{
+        for (const key of Object.keys(value)) {{
+            value[key] = toBigInt(value[key], schema.record);
+        }}
+        return value;
+    }}
+    if (schema.fields) {{
+        for (const [key, field] of Object.entries(schema.fields)) {{
+            if (key in value) {{
+                value[key] = toBigInt(value[key], field);
+            }}
+        }}
+        return value;
+    }}
+    // Enums, the variant is named by the tag or, if externally tagged, by the only key of the object
+    if (schema.tag === undefined) {{
+        const variant = typeof value === 'object' ? Object.keys(value)[0] : undefined;
+        if (variant !== undefined && schema.variants[variant]) {{
+            value[variant] = toBigInt(value[variant], schema.variants[variant]);
+        }}
+        return value;
+    }}
+    const variant = schema.variants[value[schema.tag]];
+    if (!variant) {{
+        return value;
+    }}
+    if (schema.content === undefined) {{
+        return toBigInt(value, variant);
+    }}
+    value[schema.content] = toBigInt(value[schema.content], variant);
+    return value;
+}}
+"#,
+        type_schemas
+    )
+}
+
+/// The names of the mobile impl structs, which are exported as classes.
+pub(crate) fn get_class_names(mobile_fragments: &MobileFragments) -> BTreeSet<String> {
+    mobile_fragments
+        .impls
+        .iter()
+        .map(|fragment| fragment.get_name())
+        .collect()
+}
+
+/// The Rust types appearing in the exported signatures and in the fields of the exported classes.
+pub(crate) fn get_exported_rust_types(mobile_fragments: &MobileFragments) -> Vec<String> {
+    let mut rust_types = Vec::new();
+    let mut collect_signature_types = |function: &RustFunction| {
+        rust_types.extend(function.parameters.iter().map(|p| p.rust_type.clone()));
+        rust_types.push(function.return_type.rust_type.clone());
+    };
+    for fragment in &mobile_fragments.functions {
+        collect_signature_types(&fragment.rust_function);
+    }
+    for fragment in &mobile_fragments.impls {
+        fragment
+            .rust_impl
+            .functions
+            .iter()
+            .for_each(&mut collect_signature_types);
+    }
+    for fragment in &mobile_fragments.impls {
+        rust_types.extend(
+            fragment
+                .rust_struct
+                .fields
+                .iter()
+                .map(|f| f.rust_type.clone()),
+        );
+    }
+    rust_types
+}
+
+pub fn run(mobile_fragments: &MobileFragments, type_mapper: &TypeMapper) -> String {
     let mut js_code = String::new();
     js_code.push_str("import CodeDistributionManager from './index.js';\n\nlet cdm = null;\n\n");
     js_code.push_str(
@@ -198,16 +462,90 @@ function ensureInitialized() {
         throw new Error('Initialize function must be called before using the exported functions.');
     }
 }
+
+const consumedInstances = new WeakSet();
+
+function ensureNotConsumed(instance) {
+    if (consumedInstances.has(instance)) {
+        throw new Error(`This ${instance.constructor.name} was consumed by a previous call and cannot be used anymore.`);
+    }
+}
+
+function writeBack(target, value) {
+    if (Array.isArray(target)) {
+        target.splice(0, target.length, ...value);
+    } else if (target !== null && typeof target === 'object') {
+        for (const key of Object.keys(target)) {
+            if (!(key in value)) {
+                delete target[key];
+            }
+        }
+        Object.assign(target, value);
+    }
+}
         "#,
     );
 
+    if type_mapper.uses_bigint() {
+        js_code.push_str(&generate_bigint_helpers(type_mapper));
+    }
+
+    // Generate JSDoc typedefs for the structs and enums used by the exported functions
+    js_code.push_str(&generate_js_typedefs(mobile_fragments, type_mapper));
+
     // Generate JS glue code for free functions
     js_code.push_str(&generate_js_glue_for_free_functions(
         &mobile_fragments.functions,
+        type_mapper,
     ));
 
     // Generate JS glue code for impl blocks
-    js_code.push_str(&generate_js_glue_for_impl_block(&mobile_fragments.impls));
+    js_code.push_str(&generate_js_glue_for_impl_block(
+        &mobile_fragments.impls,
+        type_mapper,
+    ));
 
     js_code
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::source_code_analyzer::ast_visitor::AstVisitor;
+    use crate::modules::source_code_analyzer::FilePath;
+    use syn::visit::Visit;
+
+    #[test]
+    fn test_bigint_conversion() {
+        let source_code = r#"
+            pub struct Item { pub name: String, pub id: u64 }
+            /// @mobile id = "ids"
+            pub fn ids(items: &mut Vec<Item>, limit: u32) -> (u32, Vec<i64>) { (0, Vec::new()) }
+            pub struct Counter { pub count: u64, pub label: String }
+            /// @mobile id = "counter"
+            impl Counter {
+                pub fn increment(&mut self) -> u8 { 0 }
+            }
+        "#;
+        let mut mobile_fragments = MobileFragments::default();
+        let mut ast_visitor = AstVisitor::new(
+            FilePath::from("src/lib.rs".to_string()),
+            source_code.to_string(),
+            &mut mobile_fragments,
+        );
+        ast_visitor.visit_file(&syn::parse_file(source_code).unwrap());
+        let rust_files = vec![ast_visitor.rust_file];
+        let counter = rust_files[0].structs[1].clone();
+        mobile_fragments.impls[0].set_struct_for_impl(counter);
+
+        let js_code = run(&mobile_fragments, &TypeMapper::new(&rust_files, true));
+        assert!(js_code.contains("    Counter: { fields: { count: \"bigint\" } },\n    Item: { fields: { id: \"bigint\" } },\n"));
+        assert!(js_code.contains("    let result = await cdm.execute(\"ids\", \"ids\", [items, limit], true);\n    result = toBigInt(result, { fields: { result: { tuple: [null, { array: \"bigint\" }] }, mutated: { tuple: [{ array: { type: \"Item\" } }] } } });\n"));
+        assert!(js_code.contains("    result = toBigInt(result, { fields: { state: { type: \"Counter\" } } });\n    Object.assign(this, result.state);\n"));
+        assert!(js_code.contains("  /** @type {bigint} */\n  count = 0n;\n"));
+
+        let js_code = run(&mobile_fragments, &TypeMapper::new(&rust_files, false));
+        assert!(!js_code.contains("toBigInt"));
+        assert!(js_code.contains("cdm.execute(\"ids\", \"ids\", [items, limit]);"));
+    }
+}
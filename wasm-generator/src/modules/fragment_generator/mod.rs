@@ -1,45 +1,99 @@
-mod helpers_generator;
+pub mod bindgen_generator;
+pub mod bundle_generator;
+pub mod component_generator;
+pub(crate) mod helpers_generator;
 pub mod javascript_wrappers_generator;
+pub mod typescript_declarations_generator;
 pub mod wasm_wrapper_generator;
 
+use crate::modules::application::function_fragment::ExecutionLocation;
 use crate::modules::application::traits::fragment::Fragment;
-use crate::modules::application::ExecutableFragmentDataForCodeDistributor;
-use crate::modules::application::{FinalFragmentContext, MobileFragments};
+use crate::modules::application::{
+    ExecutableFragmentDataForCodeDistributor, FragmentManifest, MANIFEST_VERSION,
+};
+use crate::modules::application::{
+    FinalFragmentContext, FragmentBindgenContext, FragmentComponentContext, FragmentSource,
+    MobileFragments,
+};
 use crate::modules::configuration::Configuration;
 use crate::modules::constants::{FRAGMENTS_LOCATION, TEMP_PATH};
+use crate::modules::fragment_generator::component_generator::ComponentSources;
+use crate::modules::source_code_analyzer::cargo_toml::ProjectCargoToml;
+use crate::modules::source_code_analyzer::rust_file::RustFile;
 use crate::modules::util::file_handler::DirectoryContext;
 use crate::modules::util::id_generator::FragmentIdGenerator;
+use crate::modules::util::type_mapper::TypeMapper;
 use crate::modules::util::{file_handler, id_generator};
+use crate::modules::{fragment_compiler, portability_analyzer};
+use log::{error, info, warn};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+/// The version of wit-bindgen the component crates are generated for
+const WIT_BINDGEN_VERSION: &str = "0.16.0";
+/// The versions of wasm-bindgen and serde-wasm-bindgen the bindgen crates are generated for.
+/// The wasm-bindgen CLI processing the modules must have the same version as the crate.
+const WASM_BINDGEN_VERSION: &str = "=0.2.89";
+const SERDE_WASM_BINDGEN_VERSION: &str = "0.6.3";
+
+/// Writes the fragment manifest to executable_fragments.json for use by the code distributors, see "Fragment Manifest" in the readme.
+/// The modules are read to record their hashes and sizes, so this runs after the fragments are compiled.
 pub fn export_fragments_data(mobile_fragments: &MobileFragments, config: Arc<Configuration>) {
-    // export minified executable_fragments to executable_fragments.json for use by the codedistributor
-    let final_function_fragments = mobile_fragments
+    let fragments = mobile_fragments
         .functions
         .iter()
-        .map(|f| f.clone().into())
-        .collect::<Vec<ExecutableFragmentDataForCodeDistributor>>();
-    let final_impl_fragments = mobile_fragments
-        .impls
-        .iter()
-        .map(|f| f.clone().into())
+        .map(|f| create_manifest_entry(f, f.clone().into(), &config))
+        .chain(
+            mobile_fragments
+                .impls
+                .iter()
+                .map(|f| create_manifest_entry(f, f.clone().into(), &config)),
+        )
         .collect::<Vec<ExecutableFragmentDataForCodeDistributor>>();
-    let final_fragments_combined = final_function_fragments
-        .into_iter()
-        .chain(final_impl_fragments)
-        .collect::<Vec<ExecutableFragmentDataForCodeDistributor>>();
-    let final_fragments_minimal_json =
-        serde_json::to_string_pretty(&final_fragments_combined).unwrap();
+    let manifest = FragmentManifest {
+        version: MANIFEST_VERSION,
+        generator_version: env!("CARGO_PKG_VERSION").to_string(),
+        abi_version: helpers_generator::ABI_VERSION,
+        fragments,
+    };
     file_handler::writeln(
         &PathBuf::from(&config.project)
             .join(TEMP_PATH)
             .join("executable_fragments.json"),
-        final_fragments_minimal_json,
+        serde_json::to_string_pretty(&manifest).unwrap(),
     )
     .expect("Failed to write to executable_fragments.json");
 }
 
+fn create_manifest_entry(
+    fragment: &impl Fragment,
+    mut entry: ExecutableFragmentDataForCodeDistributor,
+    config: &Configuration,
+) -> ExecutableFragmentDataForCodeDistributor {
+    let module_path = fragment_compiler::get_wasm_target_directory(config).join(&entry.module.file);
+    match std::fs::read(&module_path) {
+        Ok(module) => {
+            entry.module.size = module.len() as u64;
+            entry.module.hash = Sha256::digest(&module)
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+        }
+        Err(e) => warn!(
+            "Failed to read {:?} for the fragment manifest: {}",
+            module_path, e
+        ),
+    }
+    entry.allowed_locations = match entry.has_mutable_global_state {
+        true => vec![entry.execution_location.clone()],
+        false => portability_analyzer::get_allowed_locations(fragment),
+    };
+    entry.source = FragmentSource::new(fragment, config);
+    entry
+}
+
 fn create_fragment_path(identifier: &String, project_root: &String, path: &str) -> PathBuf {
     let fragments_path = PathBuf::from(project_root).join(path);
     fragments_path.join(identifier)
@@ -54,14 +108,65 @@ pub fn check_duplicate_and_assign_missing_ids(mobile_fragments: &mut MobileFragm
     id_generator::assign_missing_ids(&mut mobile_fragments.impls, &mut fragment_id_generator);
 }
 
+/// Assigns the fragments to the bundles defined in the configuration, which take precedence over the bundles defined in the CFD.
+pub fn assign_bundles(mobile_fragments: &mut MobileFragments, config: &Configuration) {
+    let mut bundle_assignments = BTreeMap::new();
+    for (bundle, fragment_ids) in config.bundles.clone().unwrap_or_default() {
+        for fragment_id in fragment_ids {
+            if let Some(previous_bundle) =
+                bundle_assignments.insert(fragment_id.clone(), bundle.clone())
+            {
+                error!(
+                    "Fragment {} is assigned to bundles {} and {}",
+                    fragment_id, previous_bundle, bundle
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+    let fragments = mobile_fragments
+        .functions
+        .iter_mut()
+        .map(|fragment| fragment as &mut dyn Fragment)
+        .chain(
+            mobile_fragments
+                .impls
+                .iter_mut()
+                .map(|fragment| fragment as &mut dyn Fragment),
+        );
+    for fragment in fragments {
+        if let Some(bundle) = bundle_assignments.remove(&fragment.get_id()) {
+            fragment.set_bundle(Some(bundle));
+        }
+        if let Some(bundle) = fragment.get_bundle() {
+            if !bundle_generator::is_valid_bundle_name(&bundle) {
+                error!(
+                    "Bundle name {} of fragment {} may only contain lowercase letters, digits and underscores",
+                    bundle,
+                    fragment.get_id()
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+    for fragment_id in bundle_assignments.keys() {
+        warn!("Bundled fragment {} does not exist", fragment_id);
+    }
+}
+
 pub fn generate_wasm_wrapper(mobile_fragments: &mut MobileFragments) {
     wasm_wrapper_generator::generate_wrapper_for_free_functions(&mut mobile_fragments.functions);
     wasm_wrapper_generator::generate_wrapper_for_impls(&mut mobile_fragments.impls);
     helpers_generator::generate_helper(mobile_fragments);
 }
 
-pub fn generate_js_wrappers(mobile_fragments: &MobileFragments, config: Arc<Configuration>) {
-    let js_wrappers = javascript_wrappers_generator::run(mobile_fragments);
+pub fn generate_js_wrappers(
+    mobile_fragments: &MobileFragments,
+    rust_files: &[RustFile],
+    config: Arc<Configuration>,
+) {
+    let type_mapper = TypeMapper::new(rust_files, config.js_bigint.unwrap_or(false));
+    let js_wrappers = javascript_wrappers_generator::run(mobile_fragments, &type_mapper);
     file_handler::writeln(
         &PathBuf::from(&config.project)
             .join(TEMP_PATH)
@@ -69,10 +174,163 @@ pub fn generate_js_wrappers(mobile_fragments: &MobileFragments, config: Arc<Conf
         js_wrappers,
     )
     .expect("Failed to write to js_wrappers.js");
+
+    let ts_declarations = typescript_declarations_generator::run(mobile_fragments, &type_mapper);
+    file_handler::writeln(
+        &PathBuf::from(&config.project)
+            .join(TEMP_PATH)
+            .join("js_wrappers.d.ts"),
+        ts_declarations,
+    )
+    .expect("Failed to write to js_wrappers.d.ts");
+}
+
+/// Generates a component crate next to the crate of every fragment whose signatures can be expressed in WIT.
+/// Fragments which cannot are only built to core modules, the returned components are keyed by the package name of their fragment.
+pub fn generate_components(
+    mobile_fragments: &MobileFragments,
+    rust_files: &[RustFile],
+    config: Arc<Configuration>,
+) -> BTreeMap<String, FragmentComponentContext> {
+    let type_mapper = TypeMapper::new(rust_files, config.js_bigint.unwrap_or(false));
+    let mut components = BTreeMap::new();
+    let function_sources = mobile_fragments.functions.iter().map(|fragment| {
+        let sources = component_generator::run_for_function(fragment, &type_mapper);
+        (fragment as &dyn Fragment, sources)
+    });
+    let impl_sources = mobile_fragments.impls.iter().map(|fragment| {
+        let sources = component_generator::run_for_impl(fragment, &type_mapper);
+        (fragment as &dyn Fragment, sources)
+    });
+    for (fragment, sources) in function_sources.chain(impl_sources) {
+        if let Some(bundle) = fragment.get_bundle() {
+            info!(
+                "Fragment {} is not built as a component, since it is built into bundle {}",
+                fragment.get_id(),
+                bundle
+            );
+            continue;
+        }
+        match sources {
+            Ok(sources) => {
+                let component = generate_component(fragment, sources, &config);
+                components.insert(fragment.get_package_name(), component);
+            }
+            Err(e) => warn!(
+                "Fragment {} is not built as a component: {}",
+                fragment.get_id(),
+                e
+            ),
+        }
+    }
+    components
+}
+
+fn generate_component(
+    fragment: &dyn Fragment,
+    sources: ComponentSources,
+    config: &Configuration,
+) -> FragmentComponentContext {
+    let package_name = format!("{}_component", fragment.get_package_name());
+    let directory = generate_derived_crate(
+        fragment,
+        &package_name,
+        &[("wit-bindgen", WIT_BINDGEN_VERSION)],
+        vec![("wit/world.wit", sources.wit), ("src/lib.rs", sources.code)],
+        config,
+    );
+    FragmentComponentContext {
+        directory,
+        core_module_identifier: format!("{}.wasm", package_name),
+        component_identifier: format!("{}.component.wasm", fragment.get_package_name()),
+    }
+}
+
+/// Generates a crate exporting every client fragment through wasm-bindgen, so that JS can call it without the executor.
+/// Server fragments keep the pointer protocol only, the returned crates are keyed by the package name of their fragment.
+pub fn generate_bindgen_crates(
+    mobile_fragments: &MobileFragments,
+    config: Arc<Configuration>,
+) -> BTreeMap<String, FragmentBindgenContext> {
+    let function_sources = mobile_fragments.functions.iter().map(|fragment| {
+        let code = bindgen_generator::run_for_function(fragment);
+        (fragment as &dyn Fragment, code)
+    });
+    let impl_sources = mobile_fragments.impls.iter().map(|fragment| {
+        let code = bindgen_generator::run_for_impl(fragment);
+        (fragment as &dyn Fragment, code)
+    });
+    function_sources
+        .chain(impl_sources)
+        .filter(|(fragment, _)| {
+            fragment.get_execution_location() == ExecutionLocation::Client
+                && fragment.get_bundle().is_none()
+        })
+        .map(|(fragment, code)| {
+            let package_name = format!("{}_bindgen", fragment.get_package_name());
+            let directory = generate_derived_crate(
+                fragment,
+                &package_name,
+                &[
+                    ("wasm-bindgen", WASM_BINDGEN_VERSION),
+                    ("serde-wasm-bindgen", SERDE_WASM_BINDGEN_VERSION),
+                ],
+                vec![("src/lib.rs", code)],
+                &config,
+            );
+            let context = FragmentBindgenContext {
+                directory,
+                core_module_identifier: format!("{}.wasm", package_name),
+                out_name: fragment.get_package_name(),
+            };
+            (fragment.get_package_name(), context)
+        })
+        .collect()
+}
+
+/// Writes a crate built from the code of a fragment next to the crate of the fragment, with additional dependencies.
+fn generate_derived_crate(
+    fragment: &dyn Fragment,
+    package_name: &str,
+    additional_dependencies: &[(&str, &str)],
+    files: Vec<(&str, String)>,
+    config: &Configuration,
+) -> DirectoryContext {
+    let crate_path = create_fragment_path(
+        &package_name.to_string(),
+        &config.project,
+        FRAGMENTS_LOCATION,
+    );
+    let directory = DirectoryContext::new(&crate_path);
+
+    let mut cargo_toml = fragment.get_cargo_toml();
+    cargo_toml.package.name = package_name.to_string();
+    let dependencies = cargo_toml.dependencies.get_or_insert_with(toml::Table::new);
+    for (name, version) in additional_dependencies {
+        dependencies.insert(name.to_string(), toml::Value::String(version.to_string()));
+    }
+    let toml_content = toml::to_string(&cargo_toml).unwrap();
+
+    // Create Cargo.toml
+    let cargo_file = directory
+        .create_file(crate_path.join("Cargo.toml"))
+        .expect("Failed to create Cargo.toml");
+    file_handler::write_to_file(cargo_file, toml_content).expect("Failed to write to Cargo.toml");
+
+    for (relative_path, content) in files {
+        let file = directory
+            .create_file(crate_path.join(relative_path))
+            .unwrap_or_else(|_| panic!("Failed to create {}", relative_path));
+        file_handler::write_to_file(file, content)
+            .unwrap_or_else(|_| panic!("Failed to write to {}", relative_path));
+    }
+    directory
 }
 
 pub fn generate(
     mobile_fragments: &MobileFragments,
+    mut components: BTreeMap<String, FragmentComponentContext>,
+    mut bindgen_crates: BTreeMap<String, FragmentBindgenContext>,
     config: Arc<Configuration>,
 ) -> Vec<FinalFragmentContext> {
     let mut generated_fragments: Vec<FinalFragmentContext> = Vec::new();
@@ -86,42 +344,268 @@ pub fn generate(
         config.clone(),
         &mut generated_fragments,
     );
+    generate_bundles(mobile_fragments, config.clone(), &mut generated_fragments);
+    for fragment in generated_fragments.iter_mut() {
+        let package_name = fragment
+            .directory
+            .base_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        fragment.component = components.remove(&package_name);
+        fragment.bindgen = bindgen_crates.remove(&package_name);
+    }
+    generate_workspace(&generated_fragments, config);
     generated_fragments
 }
 
+/// Generates a Cargo workspace containing all the fragment crates, so that they are built together and share their dependencies.
+fn generate_workspace(generated_fragments: &[FinalFragmentContext], config: Arc<Configuration>) {
+    let workspace_path = PathBuf::from(&config.project).join(FRAGMENTS_LOCATION);
+    let workspace_directory = DirectoryContext::new(&workspace_path);
+
+    let members = generated_fragments
+        .iter()
+        .flat_map(|fragment| {
+            let component_directory = fragment
+                .component
+                .as_ref()
+                .map(|component| &component.directory);
+            let bindgen_directory = fragment.bindgen.as_ref().map(|bindgen| &bindgen.directory);
+            std::iter::once(&fragment.directory)
+                .chain(component_directory)
+                .chain(bindgen_directory)
+        })
+        .filter_map(|directory| directory.base_path.file_name())
+        .map(|member| toml::Value::String(member.to_string_lossy().to_string()))
+        .collect::<Vec<toml::Value>>();
+    let mut workspace = toml::Table::new();
+    workspace.insert("members".to_string(), toml::Value::Array(members));
+    workspace.insert("resolver".to_string(), toml::Value::String("2".to_string()));
+    let mut workspace_cargo_toml = toml::Table::new();
+    workspace_cargo_toml.insert("workspace".to_string(), toml::Value::Table(workspace));
+
+    let cargo_file = workspace_directory
+        .create_file("Cargo.toml")
+        .expect("Failed to create workspace Cargo.toml");
+    file_handler::write_to_file(cargo_file, workspace_cargo_toml.to_string())
+        .expect("Failed to write to workspace Cargo.toml");
+
+    // The project's lockfile is shared with the workspace, so that the fragments build against the same dependency versions
+    if let Ok(lockfile_content) =
+        file_handler::read(&PathBuf::from(&config.project).join("Cargo.lock"))
+    {
+        let lock_file = workspace_directory
+            .create_file("Cargo.lock")
+            .expect("Failed to create Cargo.lock");
+        file_handler::write_to_file(lock_file, lockfile_content)
+            .expect("Failed to write to Cargo.lock");
+    }
+}
+
 fn generate_fragments(
     executable_fragments: &[impl Fragment],
     config: Arc<Configuration>,
     generated_fragments: &mut Vec<FinalFragmentContext>,
 ) {
     for executable_fragment in executable_fragments.iter() {
-        let fragment_path = create_fragment_path(
+        // Bundled fragments are built into the crates of their bundles
+        if executable_fragment.get_bundle().is_some() {
+            continue;
+        }
+        let mut fragment_context = generate_fragment_crate(
             &executable_fragment.get_package_name(),
-            &config.project,
-            FRAGMENTS_LOCATION,
-        );
-
-        let fragment_context = FinalFragmentContext::new(
-            DirectoryContext::new(&fragment_path),
             executable_fragment.get_wasm_identifier(),
+            &executable_fragment.get_cargo_toml(),
+            executable_fragment.get_code(),
+            &config,
         );
+        fragment_context.sources = vec![FragmentSource::new(executable_fragment, &config)];
+        fragment_context.tests = executable_fragment.get_tests();
+        generated_fragments.push(fragment_context);
+    }
+}
 
-        // Create Cargo.toml
-        let cargo_file = fragment_context
-            .directory
-            .create_file(fragment_path.join("Cargo.toml"))
-            .expect("Failed to create Cargo.toml");
-        let toml_content = toml::to_string(&executable_fragment.get_cargo_toml()).unwrap();
-        file_handler::write_to_file(cargo_file, toml_content)
-            .expect("Failed to write to Cargo.toml");
-
-        // Create lib.rs
-        let lib_file = fragment_context
-            .directory
-            .create_file(fragment_path.join("src/lib.rs"))
-            .expect("Failed to create lib.rs");
-        file_handler::write_to_file(lib_file, executable_fragment.get_code().clone())
-            .expect("Failed to write to lib.rs");
+/// Generates a crate for every bundle, containing the code of all fragments assigned to it.
+fn generate_bundles(
+    mobile_fragments: &MobileFragments,
+    config: Arc<Configuration>,
+    generated_fragments: &mut Vec<FinalFragmentContext>,
+) {
+    let mut bundles: BTreeMap<String, Vec<&dyn Fragment>> = BTreeMap::new();
+    let fragments = mobile_fragments
+        .functions
+        .iter()
+        .map(|fragment| fragment as &dyn Fragment)
+        .chain(
+            mobile_fragments
+                .impls
+                .iter()
+                .map(|fragment| fragment as &dyn Fragment),
+        );
+    for fragment in fragments {
+        if let Some(bundle) = fragment.get_bundle() {
+            bundles.entry(bundle).or_default().push(fragment);
+        }
+    }
+    for (bundle, fragments) in bundles {
+        let package_name = bundle_generator::get_bundle_package_name(&bundle);
+        let code = bundle_generator::generate_code(&fragments).expect("Failed to generate bundle");
+        let cargo_toml = bundle_generator::generate_cargo_toml(&package_name, &fragments);
+        let mut fragment_context = generate_fragment_crate(
+            &package_name,
+            format!("{}.wasm", package_name),
+            &cargo_toml,
+            code,
+            &config,
+        );
+        fragment_context.sources = fragments
+            .iter()
+            .map(|fragment| FragmentSource::new(*fragment, &config))
+            .collect();
+        fragment_context.tests = fragments
+            .iter()
+            .flat_map(|fragment| fragment.get_tests())
+            .collect();
         generated_fragments.push(fragment_context);
     }
 }
+
+fn generate_fragment_crate(
+    package_name: &String,
+    wasm_identifier: String,
+    cargo_toml: &ProjectCargoToml,
+    mut code: String,
+    config: &Configuration,
+) -> FinalFragmentContext {
+    let fragment_path = create_fragment_path(package_name, &config.project, FRAGMENTS_LOCATION);
+
+    let mut fragment_context =
+        FinalFragmentContext::new(DirectoryContext::new(&fragment_path), wasm_identifier);
+    let toml_content = toml::to_string(cargo_toml).unwrap();
+    if config.reproducible.unwrap_or(false) {
+        let source_hash = get_source_hash(&toml_content, &code);
+        info!("Source hash of fragment {}: {}", package_name, source_hash);
+        code.push_str(&generate_source_hash_section(&source_hash));
+        fragment_context.source_hash = Some(source_hash);
+    }
+
+    // Create Cargo.toml
+    let cargo_file = fragment_context
+        .directory
+        .create_file(fragment_path.join("Cargo.toml"))
+        .expect("Failed to create Cargo.toml");
+    file_handler::write_to_file(cargo_file, toml_content).expect("Failed to write to Cargo.toml");
+
+    // Create lib.rs
+    let lib_file = fragment_context
+        .directory
+        .create_file(fragment_path.join("src/lib.rs"))
+        .expect("Failed to create lib.rs");
+    file_handler::write_to_file(lib_file, code).expect("Failed to write to lib.rs");
+    fragment_context
+}
+
+/// Returns the hex encoded SHA-256 hash of the generated Cargo.toml and lib.rs of a fragment.
+fn get_source_hash(toml_content: &str, code: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(toml_content.as_bytes());
+    hasher.update(code.as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Generates a static placed in the `dcm.source_hash` custom section, so that every wasm module identifies the sources it was built from.
+fn generate_source_hash_section(source_hash: &str) -> String {
+    format!(
+        "\n#[used]\n#[link_section = \"dcm.source_hash\"]\nstatic DCM_SOURCE_HASH: [u8; {}] = *b\"{}\";\n",
+        source_hash.len(),
+        source_hash
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn create_config(project: &std::path::Path) -> Arc<Configuration> {
+        Arc::new(
+            toml::from_str(&format!(
+                r#"
+                project = "{}"
+                server_fragments_dir = "server"
+                client_code_distributor_dir = "client"
+                "#,
+                project.display()
+            ))
+            .unwrap(),
+        )
+    }
+
+    fn create_fragment(workspace_path: &std::path::Path, name: &str) -> FinalFragmentContext {
+        FinalFragmentContext::new(
+            DirectoryContext::new(workspace_path.join(name)),
+            format!("{}.wasm", name),
+        )
+    }
+
+    #[test]
+    fn test_generate_workspace_copies_project_lockfile() {
+        let project = std::env::temp_dir().join(format!("dcm-lockfile-{}", std::process::id()));
+        fs::create_dir_all(&project).unwrap();
+        let lockfile = "version = 3\n\n[[package]]\nname = \"serde\"\nversion = \"1.0.163\"\n";
+        fs::write(project.join("Cargo.lock"), lockfile).unwrap();
+        let workspace_path = project.join(FRAGMENTS_LOCATION);
+
+        generate_workspace(
+            &[create_fragment(&workspace_path, "fibonacci")],
+            create_config(&project),
+        );
+
+        let workspace_lockfile = fs::read_to_string(workspace_path.join("Cargo.lock"));
+        fs::remove_dir_all(&project).unwrap();
+        assert_eq!(workspace_lockfile.unwrap(), lockfile);
+    }
+
+    #[test]
+    fn test_generate_workspace_members() {
+        let project = std::env::temp_dir().join(format!("dcm-members-{}", std::process::id()));
+        let workspace_path = project.join(FRAGMENTS_LOCATION);
+        let mut fibonacci = create_fragment(&workspace_path, "fibonacci");
+        fibonacci.bindgen = Some(FragmentBindgenContext {
+            directory: DirectoryContext::new(workspace_path.join("fibonacci_bindgen")),
+            ..Default::default()
+        });
+        let mut factorial = create_fragment(&workspace_path, "factorial");
+        factorial.component = Some(FragmentComponentContext {
+            directory: DirectoryContext::new(workspace_path.join("factorial_component")),
+            ..Default::default()
+        });
+
+        generate_workspace(&[fibonacci, factorial], create_config(&project));
+
+        let cargo_toml = fs::read_to_string(workspace_path.join("Cargo.toml")).unwrap();
+        fs::remove_dir_all(&project).unwrap();
+        let cargo_toml: toml::Table = toml::from_str(&cargo_toml).unwrap();
+        let workspace = cargo_toml["workspace"].as_table().unwrap();
+        assert_eq!(
+            workspace["members"],
+            toml::Value::Array(
+                [
+                    "fibonacci",
+                    "fibonacci_bindgen",
+                    "factorial",
+                    "factorial_component"
+                ]
+                .map(|member| toml::Value::String(member.to_string()))
+                .to_vec()
+            )
+        );
+        assert_eq!(workspace["resolver"].as_str(), Some("2"));
+    }
+}
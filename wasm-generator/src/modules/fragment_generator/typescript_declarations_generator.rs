@@ -0,0 +1,164 @@
+use crate::modules::application::traits::fragment::Fragment;
+use crate::modules::application::MobileFragments;
+use crate::modules::fragment_generator::javascript_wrappers_generator::{
+    get_class_names, get_exported_rust_types, is_constructor,
+};
+use crate::modules::source_code_analyzer::types::rust_function::{RustFunction, RustFunctionType};
+use crate::modules::util::type_mapper::{get_property_name, NamedType, TypeMapper};
+
+/// Generates the TypeScript declarations of the JS wrappers generated by `javascript_wrappers_generator::run`.
+/// Structs and enums reachable from the signatures of the exported functions are declared as interfaces and
+/// union types, mobile impls as classes.
+pub fn run(mobile_fragments: &MobileFragments, type_mapper: &TypeMapper) -> String {
+    let mut ts_code = String::new();
+    ts_code.push_str("export declare function initialize(config: unknown): Promise<void>;\n\n");
+
+    for named_type in type_mapper.get_reachable_types(
+        get_exported_rust_types(mobile_fragments),
+        &get_class_names(mobile_fragments),
+    ) {
+        match named_type {
+            NamedType::Struct(rust_struct) => {
+                ts_code.push_str(&format!(
+                    "export interface {} {{\n",
+                    rust_struct.properties.name
+                ));
+                for field in &rust_struct.fields {
+                    ts_code.push_str(&format!(
+                        "  {}: {};\n",
+                        get_property_name(&field.serialized_name),
+                        type_mapper.get_js_type(&field.rust_type, None)
+                    ));
+                }
+                ts_code.push_str("}\n\n");
+            }
+            NamedType::Enum(rust_enum) => ts_code.push_str(&format!(
+                "export type {} = {};\n\n",
+                rust_enum.properties.name,
+                type_mapper.get_enum_union(rust_enum)
+            )),
+        }
+    }
+
+    for fragment in &mobile_fragments.functions {
+        ts_code.push_str(&format!(
+            "export declare function {}({}): Promise<{}>;\n\n",
+            fragment.id,
+            get_ts_parameters(&fragment.rust_function, type_mapper, None),
+            type_mapper.get_js_type(&fragment.rust_function.return_type.rust_type, None)
+        ));
+    }
+
+    for fragment in &mobile_fragments.impls {
+        let class_name = fragment.get_name();
+        ts_code.push_str(&format!("export declare class {} {{\n", class_name));
+        for field in &fragment.rust_struct.fields {
+            ts_code.push_str(&format!(
+                "  {}: {};\n",
+                get_property_name(&field.serialized_name),
+                type_mapper.get_js_type(&field.rust_type, Some(&class_name))
+            ));
+        }
+        for function in &fragment.rust_impl.functions {
+            let parameters = get_ts_parameters(function, type_mapper, Some(&class_name));
+            let return_type = match is_constructor(function) {
+                true => format!("{} | null", class_name),
+                false => {
+                    type_mapper.get_js_type(&function.return_type.rust_type, Some(&class_name))
+                }
+            };
+            let modifier = match function.function_type {
+                RustFunctionType::AssociatedFunction => "static ",
+                _ => "",
+            };
+            ts_code.push_str(&format!(
+                "  {}{}({}): Promise<{}>;\n",
+                modifier, function.properties.name, parameters, return_type
+            ));
+        }
+        ts_code.push_str("}\n\n");
+    }
+
+    ts_code
+}
+
+fn get_ts_parameters(
+    function: &RustFunction,
+    type_mapper: &TypeMapper,
+    self_type: Option<&str>,
+) -> String {
+    function
+        .parameters
+        .iter()
+        .map(|param| {
+            format!(
+                "{}: {}",
+                param.name,
+                type_mapper.get_js_type(&param.rust_type, self_type)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::source_code_analyzer::ast_visitor::AstVisitor;
+    use crate::modules::source_code_analyzer::FilePath;
+    use syn::visit::Visit;
+
+    #[test]
+    fn test_generate_declarations() {
+        let source_code = r#"
+            #[serde(rename_all = "camelCase")]
+            pub struct Item { pub name: String, pub unit_price: u32 }
+            #[serde(tag = "kind")]
+            pub enum Discount { None, Fixed { amount: u32 } }
+            pub struct Internal { pub id: u8 }
+            /// @mobile id = "total", initial_execution_location = "server"
+            pub fn total(items: Vec<Item>, discount: Option<Discount>) -> f64 { 0.0 }
+            pub struct Cart { pub items: Vec<Item> }
+            /// @mobile id = "cart"
+            impl Cart {
+                pub fn new() -> Self { Self { items: Vec::new() } }
+                pub fn add(&mut self, item: Item) {}
+                pub fn count(&self) -> usize { self.items.len() }
+            }
+        "#;
+        let mut mobile_fragments = MobileFragments::default();
+        let mut ast_visitor = AstVisitor::new(
+            FilePath::from("src/lib.rs".to_string()),
+            source_code.to_string(),
+            &mut mobile_fragments,
+        );
+        ast_visitor.visit_file(&syn::parse_file(source_code).unwrap());
+        let rust_files = vec![ast_visitor.rust_file];
+        let cart = rust_files[0].structs[2].clone();
+        mobile_fragments.impls[0].set_struct_for_impl(cart);
+        let type_mapper = TypeMapper::new(&rust_files, false);
+
+        assert_eq!(
+            run(&mobile_fragments, &type_mapper),
+            r#"export declare function initialize(config: unknown): Promise<void>;
+
+export type Discount = { kind: "None" } | { kind: "Fixed"; amount: number };
+
+export interface Item {
+  name: string;
+  unitPrice: number;
+}
+
+export declare function total(items: Array<Item>, discount: Discount | null): Promise<number>;
+
+export declare class Cart {
+  items: Array<Item>;
+  static new(): Promise<Cart | null>;
+  add(item: Item): Promise<void>;
+  count(): Promise<number>;
+}
+
+"#
+        );
+    }
+}
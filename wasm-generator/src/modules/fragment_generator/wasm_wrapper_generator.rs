@@ -1,40 +1,95 @@
 use crate::modules::application::function_fragment::FunctionFragment;
 use crate::modules::application::object_fragment::ObjectFragment;
 use crate::modules::application::traits::fragment::Fragment;
-use crate::modules::source_code_analyzer::types::rust_function::{RustFunction, RustFunctionType};
+use crate::modules::source_code_analyzer::types::rust_function::{
+    ParameterInfo, ReceiverKind, RustFunction, RustFunctionType,
+};
+use syn::Type;
+
+// Returns the annotation of the owned value a borrowed parameter is deserialized into, when it cannot be inferred from the call.
+// Borrowed slices and strings are unsized, the wrapper owns a Vec or String instead and passes a borrow of it.
+pub(crate) fn get_owned_type_annotation(param: &ParameterInfo) -> &'static str {
+    match syn::parse_str::<Type>(&param.rust_type) {
+        Ok(Type::Reference(type_reference)) => match *type_reference.elem {
+            Type::Slice(_) => ": Vec<_>",
+            Type::Path(type_path) if type_path.path.is_ident("str") => ": String",
+            _ => "",
+        },
+        _ => "",
+    }
+}
 
 // Helper function to generate parameter values
+// The parameter types are inferred from the call, since they may not be nameable from the crate root the wrappers are placed in.
+// Parameters that cannot be deserialized are reported to the host instead of trapping.
 fn generate_deserialize_param_values(rust_function: &RustFunction) -> String {
     let mut param_values = Vec::new();
     for (idx, param) in rust_function.parameters.iter().enumerate() {
-        let param_indexed_name = format!("param{}", idx);
+        let mutability = if param.is_mutable { "mut " } else { "" };
         param_values.push(format!(
-            "let {}: {} = rmp_serde::from_slice(&parameters[{}]).unwrap();",
-            param_indexed_name, param.rust_type, idx
+            "let {}param{}{} = match rmp_serde::from_slice(&parameters[{}]) {{
+        Ok(value) => value,
+        Err(e) => return serialize_error(\"InvalidParameter\", format!(\"Invalid parameter `{}`: {{}}\", e)),
+    }};",
+            mutability,
+            idx,
+            get_owned_type_annotation(param),
+            idx,
+            param.name.replace('{', "{{").replace('}', "}}")
         ));
     }
     param_values.join("\n    ")
 }
 
+// Helper function to generate the check of the parameter count, methods receive their instance as additional parameter
+fn generate_parameter_count_check(rust_function: &RustFunction) -> String {
+    let expected_count = match rust_function.function_type {
+        RustFunctionType::Method => rust_function.parameters.len() + 1,
+        _ => rust_function.parameters.len(),
+    };
+    format!(
+        "if parameter_count != {} {{
+        return serialize_error(\"InvalidParameterCount\", format!(\"Expected {} parameters, got {{}}\", parameter_count));
+    }}",
+        expected_count, expected_count
+    )
+}
+
 // Helper function to generate function call
+// Borrowed parameters are passed as borrows of the deserialized values, which are owned by the wrapper.
 fn generate_function_call(rust_function: &RustFunction) -> String {
-    let args = (0..rust_function.parameters.len())
-        .map(|i| format!("param{}", i))
+    let args = rust_function
+        .parameters
+        .iter()
+        .enumerate()
+        .map(|(i, param)| match (param.is_reference, param.is_mutable) {
+            (true, true) => format!("&mut param{}", i),
+            (true, false) => format!("&param{}", i),
+            _ => format!("param{}", i),
+        })
         .collect::<Vec<_>>()
         .join(", ");
 
-    match rust_function.function_type {
+    let function_call = match rust_function.function_type {
         RustFunctionType::FreeFunction => {
             format!("let result = {}({});", rust_function.properties.name, args)
         }
         RustFunctionType::Method => {
+            let mutability = match rust_function.receiver {
+                Some(ReceiverKind::Mutable) => "mut ",
+                _ => "",
+            };
             format!(
-                "let mut self_instance: {} = rmp_serde::from_slice(&parameters[parameters.len()-1]).unwrap(); \
-                 let result: {} = {{ self_instance.{}({}) }};
-                ",
+                "let {}self_instance: {} = match rmp_serde::from_slice(&parameters[parameters.len()-1]) {{
+        Ok(value) => value,
+        Err(e) => return serialize_error(\"InvalidParameter\", format!(\"Invalid instance: {{}}\", e)),
+    }};
+    let result = {{ {}.{}({}) }};",
+                mutability,
                 rust_function.struct_name.as_ref().unwrap(),
-                rust_function.return_type.rust_type,
-                rust_function.properties.name, args
+                get_instance_expression(rust_function),
+                rust_function.properties.name,
+                args
             )
         }
         RustFunctionType::AssociatedFunction => format!(
@@ -43,44 +98,103 @@ fn generate_function_call(rust_function: &RustFunction) -> String {
             rust_function.properties.name,
             args
         ),
+    };
+
+    // A returned borrow may keep the instance or a `&mut` parameter mutably borrowed, which are serialized after the result
+    match returns_mutable_borrow(rust_function) {
+        true => format!(
+            "{}\n    let result = rmp_serde::to_vec_named(&result);",
+            function_call
+        ),
+        false => function_call,
+    }
+}
+
+// Methods taking `self: Box<Self>`, `self: Rc<Self>` or `self: Arc<Self>` are called on the wrapped instance
+pub(crate) fn get_instance_expression(rust_function: &RustFunction) -> String {
+    match &rust_function.receiver {
+        Some(receiver) => receiver.wrap_instance("self_instance"),
+        None => "self_instance".to_string(),
+    }
+}
+
+pub(crate) fn returns_mutable_borrow(rust_function: &RustFunction) -> bool {
+    rust_function.return_type.is_reference
+        && (rust_function.receiver == Some(ReceiverKind::Mutable)
+            || rust_function.parameters.iter().any(|p| p.is_mutable))
+}
+
+// The state of the instance is returned for `&mut self` methods only, since other receivers cannot change it.
+// The values of `&mut` parameters are returned in their order, so the host can write them back.
+fn generate_serialize_result(rust_function: &RustFunction) -> String {
+    let state = match rust_function.receiver {
+        Some(ReceiverKind::Mutable) => "Some(&self_instance)",
+        _ => "None::<&()>",
+    };
+    let mutated_params = rust_function
+        .parameters
+        .iter()
+        .enumerate()
+        .filter(|(_, param)| param.is_mutable)
+        .map(|(i, _)| format!("&param{},", i))
+        .collect::<Vec<_>>();
+    let mutated = match mutated_params.is_empty() {
+        true => "None::<&()>".to_string(),
+        false => format!("Some(&({}))", mutated_params.join(" ")),
+    };
+    match returns_mutable_borrow(rust_function) {
+        true => format!("serialize_encoded_result(result, {}, {})", state, mutated),
+        false => format!("serialize_result(&result, {}, {})", state, mutated),
     }
 }
 
-fn generate_add_state(function_type: RustFunctionType) -> String {
-    if function_type != RustFunctionType::Method {
-        return "".to_string();
+// Fragments built into a bundle export their wrappers prefixed with their id, since their functions may have the same names.
+fn generate_export_attribute(function_name: &str, bundle_export_prefix: Option<&str>) -> String {
+    match bundle_export_prefix {
+        Some(prefix) => format!("#[export_name = \"{}{}\"]", prefix, function_name),
+        None => "#[no_mangle]".to_string(),
     }
-    r#"map.insert("state".to_string(), serde_json::json!(self_instance));"#.to_string()
 }
 
-pub fn generate_wrapper(function: &RustFunction) -> String {
+/// Returns the prefix of the exports of a fragment built into a bundle, see "Bundles" in the readme.
+pub fn get_bundle_export_prefix(fragment_id: &str) -> String {
+    format!("{}/", fragment_id)
+}
+
+pub fn generate_wrapper(function: &RustFunction, bundle_export_prefix: Option<&str>) -> String {
     let function_name = format!("execute__{}", function.properties.name);
     let param_values = generate_deserialize_param_values(function);
     let function_call = generate_function_call(function);
     let wrapper_code = format!(
         r#"
-#[no_mangle]
+{}
 pub extern "C" fn {}(params_ptr: *const u8, parameter_count: usize) -> *const u8 {{
+    dcm_install_panic_hook();
+    {}
     let parameters = extract_parameters(params_ptr, parameter_count);
     {}
     {}
-    let mut map = serde_json::Map::new();
     {}
-    map.insert("result".to_string(), serde_json::json!(result));
-    serialize_result(map)
 }}
 "#,
+        generate_export_attribute(&function_name, bundle_export_prefix),
         function_name,
+        generate_parameter_count_check(function),
         param_values,
         function_call,
-        generate_add_state(function.function_type.clone()),
+        generate_serialize_result(function),
     );
     wrapper_code
 }
 
 pub fn generate_wrapper_for_free_functions(function_fragments: &mut [FunctionFragment]) {
     for fragment in function_fragments.iter_mut() {
-        let wrapper_code = generate_wrapper(&fragment.rust_function);
+        let bundle_export_prefix = fragment
+            .bundle
+            .as_ref()
+            .map(|_| get_bundle_export_prefix(&fragment.id));
+        let wrapper_code =
+            generate_wrapper(&fragment.rust_function, bundle_export_prefix.as_deref());
         fragment.rust_function.properties.code = format!(
             "{}\n\n{}",
             wrapper_code, fragment.rust_function.properties.code
@@ -90,17 +204,210 @@ pub fn generate_wrapper_for_free_functions(function_fragments: &mut [FunctionFra
 
 pub fn generate_wrapper_for_impls(impl_fragments: &mut [ObjectFragment]) {
     for fragment in impl_fragments.iter_mut() {
+        let bundle_export_prefix = fragment
+            .bundle
+            .as_ref()
+            .map(|_| get_bundle_export_prefix(&fragment.id));
         let mut final_wrapper_code = String::new();
         for function in fragment.rust_impl.functions.iter_mut() {
-            // reference types can be used internally but cannot be exposed outside of WebAssembly module
-            if function.return_type.is_reference
-                || function.parameters.iter().any(|p| p.is_reference)
-            {
-                continue;
-            }
-            let wrapper_code = generate_wrapper(function);
+            let wrapper_code = generate_wrapper(function, bundle_export_prefix.as_deref());
             final_wrapper_code = format!("{}\n\n{}", final_wrapper_code, wrapper_code);
         }
         fragment.set_code(format!("{}\n\n{}", final_wrapper_code, fragment.get_code()));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::fragment_generator::helpers_generator::generate_abi_helpers;
+    use crate::modules::util::compile_check::test_generated_crate;
+
+    // Calls a wrapper natively as the hosts do and returns the payload of its response
+    const CALL_WRAPPER: &str = r#"
+    fn call(wrapper: extern "C" fn(*const u8, usize) -> *const u8, parameters: &[Vec<u8>]) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        for parameter in parameters {
+            buffer.extend_from_slice(&(parameter.len() as u32).to_le_bytes());
+            buffer.extend_from_slice(parameter);
+            buffer.resize(buffer.len().next_multiple_of(4), 0);
+        }
+        let params_ptr = alloc(buffer.len());
+        unsafe {
+            std::ptr::copy_nonoverlapping(buffer.as_ptr(), params_ptr, buffer.len());
+            let response = wrapper(params_ptr, parameters.len());
+            let header = std::slice::from_raw_parts(response, DCM_RESPONSE_HEADER_SIZE);
+            assert_eq!(header[1], DCM_STATUS_OK);
+            let payload_len = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+            std::slice::from_raw_parts(response.add(DCM_RESPONSE_HEADER_SIZE), payload_len).to_vec()
+        }
+    }
+"#;
+
+    #[test]
+    fn test_generated_wrappers_compile_for_borrowed_results() {
+        let item_fn: syn::ItemFn = syn::parse_str(
+            "pub fn apply<'a>(code: &str, items: &[u32], entry: &'a mut Entry) -> &'a Entry { entry }",
+        )
+        .unwrap();
+        let impl_item_fn: syn::ImplItemFn =
+            syn::parse_str("pub fn add(&mut self, name: String) -> &Entry { todo!() }").unwrap();
+        let mut method: RustFunction = impl_item_fn.into();
+        method.struct_name = Some("Ledger".to_string());
+        let functions = [item_fn.into(), method];
+
+        // The returned entries are not Clone, so they are serialized before the write-back borrows are taken
+        let code = format!(
+            "{}\n{}\n{}\n{}\n{}",
+            generate_abi_helpers(&functions),
+            CALL_WRAPPER,
+            generate_wrapper(&functions[0], None),
+            generate_wrapper(&functions[1], None),
+            r#"
+            #[derive(Serialize, Deserialize, Debug, PartialEq)]
+            pub struct Entry { pub name: String, pub total: u32 }
+
+            #[derive(Serialize, Deserialize, Debug, PartialEq)]
+            pub struct Ledger { pub entries: Vec<Entry> }
+
+            pub fn apply<'a>(code: &str, items: &[u32], entry: &'a mut Entry) -> &'a Entry {
+                entry.name = code.to_string();
+                entry.total = items.iter().sum();
+                entry
+            }
+
+            impl Ledger {
+                pub fn add(&mut self, name: String) -> &Entry {
+                    self.entries.push(Entry { name, total: 0 });
+                    self.entries.last().unwrap()
+                }
+            }
+
+            #[test]
+            fn test_borrowed_results() {
+                let entry = Entry { name: String::new(), total: 0 };
+                let payload = call(execute__apply, &[
+                    rmp_serde::to_vec_named("A1").unwrap(),
+                    rmp_serde::to_vec_named(&[2u32, 3]).unwrap(),
+                    rmp_serde::to_vec_named(&entry).unwrap(),
+                ]);
+                let updated = Entry { name: "A1".to_string(), total: 5 };
+                let expected = rmp_serde::to_vec_named(&DcmResponse {
+                    result: &updated,
+                    state: None::<&()>,
+                    mutated: Some(&(&updated,)),
+                });
+                assert_eq!(payload, expected.unwrap());
+
+                let ledger = Ledger { entries: Vec::new() };
+                let payload = call(execute__add, &[
+                    rmp_serde::to_vec_named("rent").unwrap(),
+                    rmp_serde::to_vec_named(&ledger).unwrap(),
+                ]);
+                let added = Entry { name: "rent".to_string(), total: 0 };
+                let ledger = Ledger { entries: vec![Entry { name: "rent".to_string(), total: 0 }] };
+                let expected = rmp_serde::to_vec_named(&DcmResponse {
+                    result: &added,
+                    state: Some(&ledger),
+                    mutated: None::<&()>,
+                });
+                assert_eq!(payload, expected.unwrap());
+            }
+            "#
+        );
+        test_generated_crate("borrowed_results", &code).unwrap();
+    }
+
+    #[test]
+    fn test_generated_wrappers_compile_for_typed_receivers() {
+        let get_method = |code: &str| {
+            let impl_item_fn: syn::ImplItemFn = syn::parse_str(code).unwrap();
+            let mut method: RustFunction = impl_item_fn.into();
+            method.struct_name = Some("Counter".to_string());
+            generate_wrapper(&method, None)
+        };
+        let code = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}\n{}",
+            generate_abi_helpers(&[]),
+            CALL_WRAPPER,
+            get_method("pub fn increment(self: &mut Self, step: u32) -> u32 { todo!() }"),
+            get_method("pub fn get(self: &Self) -> u32 { todo!() }"),
+            get_method("pub fn into_value(self: Box<Self>) -> u32 { todo!() }"),
+            get_method("pub fn share(self: std::rc::Rc<Self>) -> u32 { todo!() }"),
+            r#"
+            #[derive(Serialize, Deserialize, Debug, PartialEq)]
+            pub struct Counter { pub value: u32 }
+
+            impl Counter {
+                pub fn increment(self: &mut Self, step: u32) -> u32 {
+                    self.value += step;
+                    self.value
+                }
+
+                pub fn get(self: &Self) -> u32 {
+                    self.value
+                }
+
+                pub fn into_value(self: Box<Self>) -> u32 {
+                    self.value
+                }
+
+                pub fn share(self: std::rc::Rc<Self>) -> u32 {
+                    std::rc::Rc::clone(&self).value
+                }
+            }
+
+            #[test]
+            fn test_typed_receivers() {
+                let counter = rmp_serde::to_vec_named(&Counter { value: 1 }).unwrap();
+                let payload = call(execute__increment, &[rmp_serde::to_vec_named(&2u32).unwrap(), counter.clone()]);
+                let expected = rmp_serde::to_vec_named(&DcmResponse {
+                    result: 3u32,
+                    state: Some(&Counter { value: 3 }),
+                    mutated: None::<&()>,
+                });
+                assert_eq!(payload, expected.unwrap());
+
+                let expected = rmp_serde::to_vec_named(&DcmResponse {
+                    result: 1u32,
+                    state: None::<&()>,
+                    mutated: None::<&()>,
+                })
+                .unwrap();
+                assert_eq!(call(execute__get, &[counter.clone()]), expected);
+                assert_eq!(call(execute__into_value, &[counter.clone()]), expected);
+                assert_eq!(call(execute__share, &[counter]), expected);
+            }
+            "#
+        );
+        test_generated_crate("typed_receivers", &code).unwrap();
+    }
+
+    #[test]
+    fn test_generate_wrapper_returns_state_for_mutating_receivers_only() {
+        let get_wrapper = |code: &str| {
+            let item_fn: syn::ImplItemFn = syn::parse_str(code).unwrap();
+            let mut rust_function: RustFunction = item_fn.into();
+            rust_function.struct_name = Some("Cart".to_string());
+            generate_wrapper(&rust_function, None)
+        };
+        let wrapper = get_wrapper("pub fn total(&self) -> u32 { 0 }");
+        assert!(wrapper.contains("let self_instance: Cart = "));
+        assert!(wrapper.contains("serialize_result(&result, None::<&()>, None::<&()>)"));
+        let wrapper = get_wrapper("pub fn clear(&mut self) {}");
+        assert!(wrapper.contains("let mut self_instance: Cart = "));
+        assert!(wrapper.contains("serialize_result(&result, Some(&self_instance), None::<&()>)"));
+        let wrapper = get_wrapper("pub fn checkout(self) -> Order { todo!() }");
+        assert!(wrapper.contains("serialize_result(&result, None::<&()>, None::<&()>)"));
+    }
+
+    #[test]
+    fn test_generate_wrapper_for_bundled_fragment() {
+        let item_fn: syn::ItemFn =
+            syn::parse_str("pub fn add(a: u32, b: u32) -> u32 { a + b }").unwrap();
+        let wrapper = generate_wrapper(&item_fn.into(), Some(&get_bundle_export_prefix("math")));
+        assert!(wrapper
+            .contains("#[export_name = \"math/execute__add\"]\npub extern \"C\" fn execute__add("));
+        assert!(!wrapper.contains("#[no_mangle]"));
+    }
+}
@@ -7,6 +7,9 @@ pub mod error;
 pub mod fragment_compiler;
 pub mod fragment_generator;
 pub mod language_server_protocol;
+pub mod portability_analyzer;
 pub mod post_compilation_processor;
 pub mod source_code_analyzer;
 pub mod util;
+#[cfg(feature = "verify")]
+pub mod verifier;
@@ -0,0 +1,119 @@
+use syn::visit::{visit_item_static, visit_type_path, Visit};
+use syn::{ItemMacro, ItemStatic, Macro, StaticMutability, StmtMacro, TypePath};
+
+/// Types whose contents can be changed through a shared reference, which makes a `static` of such a type mutable.
+const INTERIOR_MUTABILITY_TYPES: [&str; 7] = [
+    "Cell",
+    "RefCell",
+    "UnsafeCell",
+    "Mutex",
+    "RwLock",
+    "OnceCell",
+    "OnceLock",
+];
+
+/// Returns a description of every piece of mutable global state in the code:
+/// `static mut` items, statics with interior mutability and `thread_local!` or `lazy_static!` declarations.
+/// Such state is reset on every call when the fragment runs on the server, since the distributor creates a new instance per call,
+/// but persists across calls in the browser.
+pub fn find_mutable_global_state(code: &str) -> Vec<String> {
+    let mut visitor = GlobalStateVisitor::default();
+    if let Ok(syntax_tree) = syn::parse_file(code) {
+        visitor.visit_file(&syntax_tree);
+    }
+    visitor.global_state
+}
+
+#[derive(Default)]
+struct GlobalStateVisitor {
+    global_state: Vec<String>,
+}
+
+impl<'ast> Visit<'ast> for GlobalStateVisitor {
+    fn visit_item_static(&mut self, node: &'ast ItemStatic) {
+        if let StaticMutability::Mut(_) = node.mutability {
+            self.global_state.push(format!("static mut {}", node.ident));
+        } else {
+            let mut type_visitor = InteriorMutabilityVisitor::default();
+            type_visitor.visit_type(&node.ty);
+            if let Some(type_name) = type_visitor.type_name {
+                self.global_state
+                    .push(format!("static {} of type {}", node.ident, type_name));
+            }
+        }
+        visit_item_static(self, node);
+    }
+
+    fn visit_item_macro(&mut self, node: &'ast ItemMacro) {
+        self.check_macro(&node.mac);
+    }
+
+    /// Macros invoked inside function bodies are parsed as statements instead of items.
+    fn visit_stmt_macro(&mut self, node: &'ast StmtMacro) {
+        self.check_macro(&node.mac);
+    }
+}
+
+impl GlobalStateVisitor {
+    fn check_macro(&mut self, mac: &Macro) {
+        if let Some(macro_name) = mac.path.segments.last() {
+            if macro_name.ident == "thread_local" || macro_name.ident == "lazy_static" {
+                self.global_state
+                    .push(format!("{}! declaration", macro_name.ident));
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct InteriorMutabilityVisitor {
+    type_name: Option<String>,
+}
+
+impl<'ast> Visit<'ast> for InteriorMutabilityVisitor {
+    fn visit_type_path(&mut self, node: &'ast TypePath) {
+        if let Some(last_segment) = node.path.segments.last() {
+            let type_name = last_segment.ident.to_string();
+            if INTERIOR_MUTABILITY_TYPES.contains(&type_name.as_str())
+                || type_name.starts_with("Atomic")
+            {
+                self.type_name.get_or_insert(type_name);
+            }
+        }
+        visit_type_path(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_mutable_global_state() {
+        let code = r#"
+            static mut COUNTER: u32 = 0;
+            static VISITS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+            static CACHE: once_cell::sync::Lazy<Mutex<Vec<u32>>> = once_cell::sync::Lazy::new(Default::default);
+            static NAME: &str = "shop";
+            thread_local! {
+                static BUFFER: RefCell<Vec<u8>> = RefCell::new(Vec::new());
+            }
+            pub fn next_id() -> u32 {
+                thread_local! {
+                    static NEXT_ID: Cell<u32> = Cell::new(0);
+                }
+                NEXT_ID.with(|id| id.replace(id.get() + 1))
+            }
+        "#;
+        assert_eq!(
+            find_mutable_global_state(code),
+            vec![
+                "static mut COUNTER",
+                "static VISITS of type AtomicUsize",
+                "static CACHE of type Mutex",
+                "thread_local! declaration",
+                "thread_local! declaration",
+            ]
+        );
+    }
+}
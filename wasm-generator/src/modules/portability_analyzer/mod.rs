@@ -0,0 +1,430 @@
+use crate::modules::application::function_fragment::ExecutionLocation;
+use crate::modules::application::traits::fragment::Fragment;
+use crate::modules::application::MobileFragments;
+use crate::modules::configuration::{Configuration, PortabilityCheckLevel};
+use log::{error, warn};
+use std::fmt;
+use std::sync::Arc;
+
+pub mod global_state;
+use syn::visit::{
+    visit_expr_call, visit_expr_unsafe, visit_impl_item_fn, visit_item_fn, visit_path, Visit,
+};
+use syn::{
+    Attribute, Expr, ExprCall, ExprUnsafe, ImplItemFn, ItemFn, ItemForeignMod, ItemUse, LitStr,
+    Path, Token, UseTree,
+};
+
+/// Std APIs that are not backed by the host when running as wasm, along with the capability they require.
+const NON_PORTABLE_PATHS: [(&str, PortabilityIssueKind); 7] = [
+    ("std::fs", PortabilityIssueKind::Filesystem),
+    ("std::net", PortabilityIssueKind::Network),
+    ("std::thread", PortabilityIssueKind::Threads),
+    ("std::process", PortabilityIssueKind::Process),
+    ("std::env", PortabilityIssueKind::Environment),
+    ("std::time::Instant", PortabilityIssueKind::Clock),
+    ("std::time::SystemTime", PortabilityIssueKind::Clock),
+];
+
+/// Crates binding native libraries, whose functions are called from `unsafe` code.
+const FFI_CRATES: [&str; 6] = ["libc", "winapi", "windows", "windows_sys", "jni", "objc"];
+
+/// Checks the final resolved code of every fragment for APIs that are not available on the target it is executed on.
+/// Depending on the configured check level of the target, the issues are reported as warnings or fail the build.
+/// It also records the mutable global state of every fragment, which ties the fragment to its initial execution location.
+pub fn run(mobile_fragments: &mut MobileFragments, config: Arc<Configuration>) {
+    record_mutable_global_state(&mut mobile_fragments.functions);
+    record_mutable_global_state(&mut mobile_fragments.impls);
+
+    let mut denied_issue_count = 0;
+    denied_issue_count += check_fragments(&mobile_fragments.functions, &config);
+    denied_issue_count += check_fragments(&mobile_fragments.impls, &config);
+    if denied_issue_count > 0 {
+        error!(
+            "Found {} portability issues in fragments, aborting",
+            denied_issue_count
+        );
+        std::process::exit(1);
+    }
+}
+
+fn record_mutable_global_state(fragments: &mut [impl Fragment]) {
+    for fragment in fragments.iter_mut() {
+        let mutable_global_state = global_state::find_mutable_global_state(&fragment.get_code());
+        if !mutable_global_state.is_empty() {
+            warn!(
+                "Fragment {} keeps mutable global state ({}). It is reset on every call on the server but persists across calls on the client, so the fragment will not be moved from its initial execution location",
+                fragment.get_id(),
+                mutable_global_state.join(", ")
+            );
+        }
+        fragment.set_mutable_global_state(mutable_global_state);
+    }
+}
+
+/// Reports the portability issues of the fragments and returns the number of issues that should fail the build.
+fn check_fragments(fragments: &[impl Fragment], config: &Configuration) -> usize {
+    let mut denied_issue_count = 0;
+    for fragment in fragments {
+        let execution_location = fragment.get_execution_location();
+        let check_level = match execution_location {
+            ExecutionLocation::Client => config.client_portability_check.clone(),
+            ExecutionLocation::Server => config.server_portability_check.clone(),
+        }
+        .unwrap_or_default();
+        if check_level == PortabilityCheckLevel::Off {
+            continue;
+        }
+
+        for issue in analyze(&fragment.get_code()) {
+            let message = format!(
+                "Fragment {} ({:?}) is not portable to wasm: {}",
+                fragment.get_id(),
+                execution_location,
+                issue
+            );
+            match check_level {
+                PortabilityCheckLevel::Deny => {
+                    error!("{}", message);
+                    denied_issue_count += 1;
+                }
+                _ => warn!("{}", message),
+            }
+        }
+    }
+    denied_issue_count
+}
+
+/// Returns the locations the code of a fragment can be executed on, starting with its initial execution location.
+/// Fragments are built for wasm32-unknown-unknown on both targets, and neither the browser nor the wasmtime engine of
+/// the code-distributor provides host functions for the reported APIs, so fragments with issues are not moved.
+/// The initial location is always included, since the build only fails there if the check level denies the issues.
+pub fn get_allowed_locations(fragment: &impl Fragment) -> Vec<ExecutionLocation> {
+    let initial_location = fragment.get_execution_location();
+    match analyze(&fragment.get_code()).is_empty() {
+        true => {
+            let mut allowed_locations = vec![initial_location.clone()];
+            allowed_locations.extend(
+                [ExecutionLocation::Client, ExecutionLocation::Server]
+                    .into_iter()
+                    .filter(|location| *location != initial_location),
+            );
+            allowed_locations
+        }
+        false => vec![initial_location],
+    }
+}
+
+/// Returns all the non-portable API usages, `extern` blocks, linked native libraries and `unsafe` calls into FFI crates
+/// found in the code.
+pub fn analyze(code: &str) -> Vec<PortabilityIssue> {
+    let mut visitor = PortabilityVisitor::default();
+    match syn::parse_file(code) {
+        Ok(syntax_tree) => visitor.visit_file(&syntax_tree),
+        Err(e) => warn!(
+            "Failed to parse fragment code for portability analysis: {}",
+            e
+        ),
+    }
+    visitor.issues
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PortabilityIssueKind {
+    Filesystem,
+    Network,
+    Threads,
+    Process,
+    Environment,
+    Clock,
+    Ffi,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortabilityIssue {
+    pub kind: PortabilityIssueKind,
+    /// The offending path, or the kind of the offending item.
+    pub usage: String,
+    /// The function in which the usage occurs, if any.
+    pub function_name: Option<String>,
+}
+
+impl fmt::Display for PortabilityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} usage `{}`", self.kind, self.usage)?;
+        if let Some(function_name) = &self.function_name {
+            write!(f, " in function `{}`", function_name)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct PortabilityVisitor {
+    issues: Vec<PortabilityIssue>,
+    current_function: Option<String>,
+    /// Whether the visited code is in an `unsafe` block or function
+    in_unsafe: bool,
+}
+
+impl PortabilityVisitor {
+    fn check_path(&mut self, path: &str) {
+        if let Some((_, kind)) = NON_PORTABLE_PATHS
+            .iter()
+            .find(|(prefix, _)| path == *prefix || path.starts_with(&format!("{}::", prefix)))
+        {
+            self.issues.push(PortabilityIssue {
+                kind: kind.clone(),
+                usage: path.to_string(),
+                function_name: self.current_function.clone(),
+            });
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for PortabilityVisitor {
+    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+        let previous_function = self.current_function.replace(node.sig.ident.to_string());
+        let previous_unsafe = std::mem::replace(&mut self.in_unsafe, node.sig.unsafety.is_some());
+        visit_item_fn(self, node);
+        self.current_function = previous_function;
+        self.in_unsafe = previous_unsafe;
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        let previous_function = self.current_function.replace(node.sig.ident.to_string());
+        let previous_unsafe = std::mem::replace(&mut self.in_unsafe, node.sig.unsafety.is_some());
+        visit_impl_item_fn(self, node);
+        self.current_function = previous_function;
+        self.in_unsafe = previous_unsafe;
+    }
+
+    fn visit_expr_unsafe(&mut self, node: &'ast ExprUnsafe) {
+        let previous_unsafe = std::mem::replace(&mut self.in_unsafe, true);
+        visit_expr_unsafe(self, node);
+        self.in_unsafe = previous_unsafe;
+    }
+
+    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
+        if let (true, Expr::Path(expr_path)) = (self.in_unsafe, &*node.func) {
+            let segments = &expr_path.path.segments;
+            if segments.len() > 1 && FFI_CRATES.contains(&segments[0].ident.to_string().as_str()) {
+                self.issues.push(PortabilityIssue {
+                    kind: PortabilityIssueKind::Ffi,
+                    usage: format!(
+                        "unsafe call to {}",
+                        segments
+                            .iter()
+                            .map(|segment| segment.ident.to_string())
+                            .collect::<Vec<String>>()
+                            .join("::")
+                    ),
+                    function_name: self.current_function.clone(),
+                });
+            }
+        }
+        visit_expr_call(self, node);
+    }
+
+    fn visit_path(&mut self, node: &'ast Path) {
+        let path = node
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<String>>()
+            .join("::");
+        self.check_path(&path);
+        visit_path(self, node);
+    }
+
+    fn visit_item_use(&mut self, node: &'ast ItemUse) {
+        let mut paths = Vec::new();
+        flatten_use_tree(&node.tree, String::new(), &mut paths);
+        for path in paths {
+            self.check_path(&path);
+        }
+    }
+
+    fn visit_item_foreign_mod(&mut self, node: &'ast ItemForeignMod) {
+        let abi = node
+            .abi
+            .name
+            .as_ref()
+            .map(|name| name.value())
+            .unwrap_or_else(|| "C".to_string());
+        let native_libraries = node
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("link"))
+            .filter_map(get_linked_library)
+            .collect::<Vec<String>>();
+        let usage = match native_libraries.is_empty() {
+            true => format!("extern \"{}\" block", abi),
+            false => format!(
+                "extern \"{}\" block linking {}",
+                abi,
+                native_libraries.join(", ")
+            ),
+        };
+        self.issues.push(PortabilityIssue {
+            kind: PortabilityIssueKind::Ffi,
+            usage,
+            function_name: self.current_function.clone(),
+        });
+    }
+}
+
+/// Returns the name of the native library linked by a `#[link(name = "...")]` attribute.
+fn get_linked_library(attr: &Attribute) -> Option<String> {
+    let mut library = None;
+    attr.parse_nested_meta(|meta| {
+        match meta.path.is_ident("name") {
+            true => library = Some(meta.value()?.parse::<LitStr>()?.value()),
+            false => {
+                // Skip the values of the other keys, e.g. `kind = "static"`
+                if meta.input.peek(Token![=]) {
+                    meta.value()?.parse::<Expr>()?;
+                }
+            }
+        }
+        Ok(())
+    })
+    .ok()?;
+    library
+}
+
+/// Expands a use tree into the full paths it imports, e.g. `std::{fs, thread::sleep}` into `std::fs` and `std::thread::sleep`.
+fn flatten_use_tree(tree: &UseTree, prefix: String, paths: &mut Vec<String>) {
+    let join = |ident: &syn::Ident| match prefix.is_empty() {
+        true => ident.to_string(),
+        false => format!("{}::{}", prefix, ident),
+    };
+    match tree {
+        UseTree::Path(use_path) => flatten_use_tree(&use_path.tree, join(&use_path.ident), paths),
+        UseTree::Name(use_name) => paths.push(join(&use_name.ident)),
+        UseTree::Rename(use_rename) => paths.push(join(&use_rename.ident)),
+        UseTree::Glob(_) => paths.push(prefix),
+        UseTree::Group(use_group) => {
+            for tree in &use_group.items {
+                flatten_use_tree(tree, prefix.clone(), paths);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::application::function_fragment::FunctionFragment;
+    use crate::modules::source_code_analyzer::types::rust_function::RustFunction;
+
+    #[test]
+    fn test_analyze() {
+        let code = r#"
+            use std::{fs::File, thread};
+            #[link(name = "checksum", kind = "static")]
+            extern "C" {
+                fn native_checksum(data: *const u8) -> u32;
+            }
+            pub fn check() -> bool {
+                thread::sleep(std::time::Duration::from_secs(1));
+                let _ = std::net::TcpStream::connect("127.0.0.1:80");
+                true
+            }
+        "#;
+        let issues = analyze(code);
+        let kinds = issues
+            .iter()
+            .map(|issue| issue.kind.clone())
+            .collect::<Vec<PortabilityIssueKind>>();
+        assert_eq!(
+            kinds,
+            vec![
+                PortabilityIssueKind::Filesystem,
+                PortabilityIssueKind::Threads,
+                PortabilityIssueKind::Ffi,
+                PortabilityIssueKind::Network,
+            ]
+        );
+        assert_eq!(issues[2].usage, "extern \"C\" block linking checksum");
+        assert_eq!(issues[3].function_name, Some("check".to_string()));
+    }
+
+    #[test]
+    fn test_analyze_unsafe_ffi_calls() {
+        let code = r#"
+            pub fn process_id() -> i32 {
+                let values = [1, 2];
+                let first = unsafe { *values.get_unchecked(0) };
+                unsafe { libc::getpid() + first }
+            }
+            pub unsafe fn page_size() -> i64 {
+                libc::sysconf(libc::_SC_PAGESIZE)
+            }
+            pub fn length() -> usize {
+                libc::strlen as usize
+            }
+        "#;
+        let usages = analyze(code)
+            .into_iter()
+            .map(|issue| (issue.kind, issue.usage, issue.function_name.unwrap()))
+            .collect::<Vec<(PortabilityIssueKind, String, String)>>();
+        assert_eq!(
+            usages,
+            vec![
+                (
+                    PortabilityIssueKind::Ffi,
+                    "unsafe call to libc::getpid".to_string(),
+                    "process_id".to_string()
+                ),
+                (
+                    PortabilityIssueKind::Ffi,
+                    "unsafe call to libc::sysconf".to_string(),
+                    "page_size".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_allowed_locations() {
+        let create_fragment = |code: &str, initial_execution_location| {
+            let item_fn: ItemFn = syn::parse_str(code).unwrap();
+            let mut rust_function: RustFunction = item_fn.into();
+            rust_function.properties.code = code.to_string();
+            FunctionFragment {
+                id: "read_config".to_string(),
+                cargo_toml_content: Default::default(),
+                initial_execution_location,
+                crates: Vec::new(),
+                dependencies: Vec::new(),
+                fragment_type: Default::default(),
+                rust_function,
+                module_hierarchy: Vec::new(),
+                mutable_global_state: Vec::new(),
+                bundle: None,
+                tests: Vec::new(),
+            }
+        };
+        let reads_file =
+            "pub fn read_config() -> String { std::fs::read_to_string(\"config.toml\").unwrap() }";
+        let spawns_thread = "pub fn read_config() { std::thread::spawn(|| {}); }";
+        let adds = "pub fn read_config(a: u32, b: u32) -> u32 { a + b }";
+        assert_eq!(
+            get_allowed_locations(&create_fragment(adds, ExecutionLocation::Client)),
+            vec![ExecutionLocation::Client, ExecutionLocation::Server]
+        );
+        assert_eq!(
+            get_allowed_locations(&create_fragment(reads_file, ExecutionLocation::Client)),
+            vec![ExecutionLocation::Client]
+        );
+        assert_eq!(
+            get_allowed_locations(&create_fragment(reads_file, ExecutionLocation::Server)),
+            vec![ExecutionLocation::Server]
+        );
+        assert_eq!(
+            get_allowed_locations(&create_fragment(spawns_thread, ExecutionLocation::Server)),
+            vec![ExecutionLocation::Server]
+        );
+    }
+}
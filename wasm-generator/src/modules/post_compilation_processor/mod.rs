@@ -2,9 +2,13 @@ use crate::modules::application::function_fragment::ExecutionLocation;
 use crate::modules::application::FinalFragmentContext;
 use crate::modules::configuration::Configuration;
 use crate::modules::constants::TEMP_PATH;
+use crate::modules::fragment_compiler::source_map::{
+    get_source_map_identifier, get_source_map_path,
+};
+use crate::modules::fragment_compiler::{get_bindgen_output_directory, get_wasm_target_directory};
 use crate::modules::util::file_handler;
 use log::info;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 pub fn run(fragments: &Vec<FinalFragmentContext>, config: &Configuration) {
     move_fragments(fragments, ExecutionLocation::Client, config);
@@ -14,19 +18,7 @@ pub fn run(fragments: &Vec<FinalFragmentContext>, config: &Configuration) {
     move_fragments_data(ExecutionLocation::Server, &config);
 
     move_js_wrappers(config);
-}
-
-fn get_wasm_module_source_path(
-    base_path: &Path,
-    fragment_identifier: &String,
-    config: &Configuration,
-) -> PathBuf {
-    let mut path = base_path.join("target").join("wasm32-unknown-unknown");
-    match config.release_mode.unwrap_or(false) {
-        true => path.push("release"),
-        false => path.push("debug"),
-    };
-    path.join(fragment_identifier)
+    move_bindgen_modules(fragments, config);
 }
 
 fn get_wasm_module_destination_path(
@@ -49,11 +41,7 @@ pub fn move_fragments(
     config: &Configuration,
 ) {
     for final_fragment in final_fragments {
-        let source = get_wasm_module_source_path(
-            &final_fragment.directory.base_path,
-            &final_fragment.fragment_identifier,
-            config,
-        );
+        let source = get_wasm_target_directory(config).join(&final_fragment.fragment_identifier);
         let destination = get_wasm_module_destination_path(
             &execution_location,
             &final_fragment.fragment_identifier,
@@ -64,6 +52,32 @@ pub fn move_fragments(
             &source, &destination
         );
         file_handler::copy_file(&source, &destination).expect("Failed to move wasm module");
+
+        // The source map lets the hosts resolve traps of the module to the project sources
+        if config.debug_info.unwrap_or(false) {
+            let source = get_source_map_path(config, &final_fragment.fragment_identifier);
+            let source_map_identifier =
+                get_source_map_identifier(&final_fragment.fragment_identifier);
+            let destination = get_wasm_module_destination_path(
+                &execution_location,
+                &source_map_identifier,
+                config,
+            );
+            info!("Moving source map from {:?} to {:?}", &source, &destination);
+            file_handler::copy_file(&source, &destination).expect("Failed to move source map");
+        }
+
+        // Components are deployed next to the core modules, for hosts implementing the component model
+        if let Some(component) = &final_fragment.component {
+            let source = get_wasm_target_directory(config).join(&component.component_identifier);
+            let destination = get_wasm_module_destination_path(
+                &execution_location,
+                &component.component_identifier,
+                config,
+            );
+            info!("Moving component from {:?} to {:?}", &source, &destination);
+            file_handler::copy_file(&source, &destination).expect("Failed to move component");
+        }
     }
 }
 
@@ -95,4 +109,35 @@ fn move_js_wrappers(config: &Configuration) {
     let destination = PathBuf::from(&config.client_code_distributor_dir).join("exports.js");
     info!("Moving js_glue.js from {:?} to {:?}", &source, &destination);
     file_handler::copy_file(&source, &destination).expect("Failed to move js_wrappers.js");
+
+    // Move the TypeScript declarations next to the JS wrappers they describe
+    let source = PathBuf::from(&config.project)
+        .join(TEMP_PATH)
+        .join("js_wrappers.d.ts");
+    let destination = PathBuf::from(&config.client_code_distributor_dir).join("exports.d.ts");
+    info!(
+        "Moving js_wrappers.d.ts from {:?} to {:?}",
+        &source, &destination
+    );
+    file_handler::copy_file(&source, &destination).expect("Failed to move js_wrappers.d.ts");
+}
+
+fn move_bindgen_modules(fragments: &[FinalFragmentContext], config: &Configuration) {
+    // The modules generated by wasm-bindgen are only deployed to the client, server fragments keep the pointer protocol
+    let destination_directory = PathBuf::from(&config.client_code_distributor_dir)
+        .join("fragments")
+        .join("bindgen");
+    for bindgen in fragments.iter().filter_map(|f| f.bindgen.as_ref()) {
+        for file_name in [
+            format!("{}.js", bindgen.out_name),
+            format!("{}.d.ts", bindgen.out_name),
+            format!("{}_bg.wasm", bindgen.out_name),
+        ] {
+            let source = get_bindgen_output_directory(config).join(&file_name);
+            let destination = destination_directory.join(&file_name);
+            info!("Moving {:?} to {:?}", &source, &destination);
+            file_handler::copy_file(&source, &destination)
+                .expect("Failed to move wasm-bindgen output");
+        }
+    }
 }
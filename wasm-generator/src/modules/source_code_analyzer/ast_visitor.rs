@@ -1,17 +1,19 @@
 use crate::modules::application::fragment_type::RustItemType;
 use crate::modules::application::function_fragment::FunctionFragment;
+use crate::modules::application::mobile_module::MobileModule;
 use crate::modules::application::object_fragment::ObjectFragment;
 use crate::modules::application::MobileFragments;
 use crate::modules::source_code_analyzer::rust_file::RustFile;
 use crate::modules::source_code_analyzer::traits::rust_item::RustItem;
 use crate::modules::source_code_analyzer::types::rust_const::RustConst;
+use crate::modules::source_code_analyzer::types::rust_enum::RustEnum;
 use crate::modules::source_code_analyzer::types::rust_function::RustFunction;
 use crate::modules::source_code_analyzer::types::rust_impl::RustImpl;
 use crate::modules::source_code_analyzer::types::rust_static::RustStatic;
 use crate::modules::source_code_analyzer::types::rust_struct::RustStruct;
 use crate::modules::source_code_analyzer::types::rust_type_definition::RustTypeDefinition;
 use crate::modules::source_code_analyzer::types::rust_use::RustUse;
-use crate::modules::source_code_analyzer::FilePath;
+use crate::modules::source_code_analyzer::{get_module_path_of_file, FilePath};
 use syn::visit::Visit;
 use syn::{ItemConst, ItemImpl, ItemMod, ItemUse};
 
@@ -20,6 +22,8 @@ pub struct AstVisitor<'a> {
     source_code: String,
     module_hierarchy: Vec<String>,
     mobile_fragments: &'a mut MobileFragments,
+    /// The modules annotated as mobile, which are expanded once all files are analyzed
+    pub mobile_modules: Vec<MobileModule>,
 }
 
 impl<'a> AstVisitor<'a> {
@@ -28,14 +32,26 @@ impl<'a> AstVisitor<'a> {
         source_code: String,
         mobile_fragments: &'a mut MobileFragments,
     ) -> Self {
+        // Items are assigned their module path relative to the crate root, starting with the module of the file
+        let module_hierarchy = get_module_path_of_file(&file_path.relative);
+        let mut rust_file: RustFile = file_path.into();
+        rust_file.module_path = module_hierarchy.clone();
         Self {
-            rust_file: file_path.into(),
+            rust_file,
             source_code,
-            module_hierarchy: Vec::new(),
+            module_hierarchy,
             mobile_fragments,
+            mobile_modules: Vec::new(),
         }
     }
 
+    /// Places the items of the file in the given module, for files whose module path does not follow from their location.
+    pub fn with_module_hierarchy(mut self, module_hierarchy: Vec<String>) -> Self {
+        self.rust_file.module_path = module_hierarchy.clone();
+        self.module_hierarchy = module_hierarchy;
+        self
+    }
+
     pub fn fill_common_properties(&self, rust_item: &mut impl RustItem) {
         let common_properties = rust_item.get_common_properties_mut();
         common_properties.set_module_hierarchy(self.module_hierarchy.clone());
@@ -96,6 +112,13 @@ impl<'ast, 'a> Visit<'ast> for AstVisitor<'a> {
 
     fn visit_item_mod(&mut self, node: &'ast ItemMod) {
         self.module_hierarchy.push(node.ident.to_string());
+        // The items of a file module are located in another file, so modules are expanded after all files are analyzed
+        if let Some(mobile_module) = MobileModule::try_create_from_attributes(
+            node.attrs.clone(),
+            self.module_hierarchy.clone(),
+        ) {
+            self.mobile_modules.push(mobile_module);
+        }
         if let Some(item) = node.content.as_ref() {
             for item in item.1.iter() {
                 self.visit_item(item);
@@ -120,6 +143,14 @@ impl<'ast, 'a> Visit<'ast> for AstVisitor<'a> {
         syn::visit::visit_item_struct(self, node);
     }
 
+    fn visit_item_enum(&mut self, node: &'ast syn::ItemEnum) {
+        let mut rust_item: RustEnum = node.clone().into();
+        self.fill_common_properties(&mut rust_item);
+        rust_item.properties.item_type = RustItemType::Enum;
+        self.rust_file.enums.push(rust_item.clone());
+        syn::visit::visit_item_enum(self, node);
+    }
+
     fn visit_item_type(&mut self, node: &'ast syn::ItemType) {
         let mut rust_item: RustTypeDefinition = node.clone().into();
         self.fill_common_properties(&mut rust_item);
@@ -9,6 +9,7 @@ pub struct ProjectCargoToml {
     pub package: CargoPackageInformation,
     pub dependencies: Option<Table>,
     pub lib: Option<Table>,
+    pub features: Option<Table>,
 }
 
 impl From<CargoPath> for ProjectCargoToml {
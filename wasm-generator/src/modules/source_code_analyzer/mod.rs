@@ -11,13 +11,14 @@ use log::info;
 use crate::modules::application::MobileFragments;
 use crate::modules::language_server_protocol::traits::lsp_client::LspFilePath;
 use serde_derive::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use walkdir::WalkDir;
 
 pub mod ast_visitor;
 pub mod attribute_parser;
 pub mod cargo_toml;
+pub mod module_tree;
 pub mod rust_file;
 
 pub mod traits;
@@ -28,10 +29,12 @@ pub fn run(
     config: Arc<Configuration>,
 ) -> (Vec<RustFile>, ProjectCargoToml) {
     let mut rust_files: Vec<RustFile> = Vec::new();
+    let mut mobile_modules = Vec::new();
 
     let project_root = PathBuf::from(&config.project.clone());
     let source_code_dir = &project_root.join(SOURCE_CODE_DIR);
 
+    let module_tree = module_tree::build_module_tree(source_code_dir);
     let entries = WalkDir::new(source_code_dir)
         .into_iter()
         .filter_ok(is_rust_file)
@@ -51,12 +54,28 @@ pub fn run(
         };
         let source_code = file_handler::read(&file_path.absolute).expect("Failed to read file");
         let syntax_tree = syn::parse_file(&source_code).expect("Failed to parse file");
+        // Files that are not reachable from the crate roots keep the module path derived from their location
+        let module_path = module_tree
+            .get(&module_tree::normalize_path(&absolute_path))
+            .cloned()
+            .unwrap_or_else(|| get_module_path_of_file(&relative_path));
         let mut ast_visitor =
-            ast_visitor::AstVisitor::new(file_path, source_code, mobile_fragments);
+            ast_visitor::AstVisitor::new(file_path, source_code, mobile_fragments)
+                .with_module_hierarchy(module_path);
         syn::visit::visit_file(&mut ast_visitor, &syntax_tree);
+        mobile_modules.append(&mut ast_visitor.mobile_modules);
         rust_files.push(ast_visitor.rust_file);
     }
 
+    for mobile_module in mobile_modules {
+        let fragment_count = mobile_module.expand(&rust_files, mobile_fragments);
+        info!(
+            "Mobile module {} contains {} fragments",
+            mobile_module.module_path.join("::"),
+            fragment_count
+        );
+    }
+
     // Parse Cargo.toml and derive CargoToml struct
     let cargo_toml: ProjectCargoToml = CargoPath::new(project_root.join("Cargo.toml")).into();
 
@@ -67,7 +86,31 @@ pub fn run(
     //     .expect("Failed to write to rust_files.json");
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
+/// Derives the module path of a source file from its path relative to the project,
+/// e.g. `src/shared/webshop/shopping.rs` is the module `shared::webshop::shopping` and `src/shared/mod.rs` is the module `shared`.
+/// This ignores `#[path]` attributes, files reachable from the crate roots take their module path from `module_tree`.
+pub fn get_module_path_of_file(relative_path: &Path) -> Vec<String> {
+    let path = relative_path
+        .strip_prefix(SOURCE_CODE_DIR)
+        .unwrap_or(relative_path)
+        .with_extension("");
+    let mut module_path: Vec<String> = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+    match module_path.last().map(String::as_str) {
+        Some("mod") => {
+            module_path.pop();
+        }
+        Some("lib") | Some("main") if module_path.len() == 1 => {
+            module_path.pop();
+        }
+        _ => {}
+    }
+    module_path
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FilePath {
     absolute: PathBuf,
     relative: PathBuf,
@@ -105,3 +148,22 @@ impl From<LspFilePath> for FilePath {
 pub struct CargoPath {
     value: PathBuf,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_module_path_of_file() {
+        assert!(get_module_path_of_file(Path::new("src/lib.rs")).is_empty());
+        assert!(get_module_path_of_file(Path::new("src/main.rs")).is_empty());
+        assert_eq!(
+            get_module_path_of_file(Path::new("src/shared/mod.rs")),
+            vec!["shared"]
+        );
+        assert_eq!(
+            get_module_path_of_file(Path::new("src/shared/webshop/order.rs")),
+            vec!["shared", "webshop", "order"]
+        );
+    }
+}
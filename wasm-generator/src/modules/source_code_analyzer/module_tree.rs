@@ -0,0 +1,187 @@
+use crate::modules::util::file_handler;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use syn::{Attribute, Expr, ExprLit, Item, Lit, Meta};
+
+/// The files the compiler starts loading the modules of a crate from.
+const CRATE_ROOTS: [&str; 2] = ["lib.rs", "main.rs"];
+
+/// Follows the `mod` declarations from the crate roots in the source directory and returns the module path of every file
+/// they load, keyed by the normalized path of the file. Declarations with a `#[path]` attribute are resolved as the
+/// compiler does, so files in non-standard locations get the module path they are declared with.
+pub fn build_module_tree(source_code_dir: &Path) -> HashMap<PathBuf, Vec<String>> {
+    let mut module_paths = HashMap::new();
+    for crate_root in CRATE_ROOTS {
+        let root_path = source_code_dir.join(crate_root);
+        if root_path.exists() {
+            visit_module_file(&root_path, Vec::new(), true, &mut module_paths);
+        }
+    }
+    module_paths
+}
+
+/// Removes the `.` and `..` components of a path, so that paths of `#[path]` attributes can be compared with walked paths.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Files named `mod.rs`, the crate roots and files loaded through a `#[path]` attribute look up the files of their child
+/// modules next to them, other files in a directory named after themselves.
+fn visit_module_file(
+    file_path: &Path,
+    module_path: Vec<String>,
+    is_mod_rs: bool,
+    module_paths: &mut HashMap<PathBuf, Vec<String>>,
+) {
+    let file_path = normalize_path(file_path);
+    if module_paths.contains_key(&file_path) {
+        return;
+    }
+    module_paths.insert(file_path.clone(), module_path.clone());
+    let Ok(source_code) = file_handler::read(&file_path) else {
+        return;
+    };
+    let Ok(syntax_tree) = syn::parse_file(&source_code) else {
+        return;
+    };
+    let directory = file_path.parent().unwrap_or(Path::new("")).to_path_buf();
+    let child_directory = match is_mod_rs {
+        true => directory.clone(),
+        false => directory.join(file_path.file_stem().unwrap_or_default()),
+    };
+    visit_items(
+        &syntax_tree.items,
+        &directory,
+        &child_directory,
+        &module_path,
+        module_paths,
+    );
+}
+
+/// `#[path]` attributes are relative to `path_directory`, which is the directory of the file for the items of the file,
+/// and the directory of the child modules for the items of inline modules.
+fn visit_items(
+    items: &[Item],
+    path_directory: &Path,
+    child_directory: &Path,
+    module_path: &[String],
+    module_paths: &mut HashMap<PathBuf, Vec<String>>,
+) {
+    for item in items {
+        let Item::Mod(item_mod) = item else {
+            continue;
+        };
+        let name = item_mod.ident.to_string();
+        let mut child_module_path = module_path.to_vec();
+        child_module_path.push(name.clone());
+        let path_attribute = get_path_attribute(&item_mod.attrs);
+        match &item_mod.content {
+            Some((_, items)) => {
+                let directory = match path_attribute {
+                    Some(path) => path_directory.join(path),
+                    None => child_directory.join(&name),
+                };
+                visit_items(
+                    items,
+                    &directory,
+                    &directory,
+                    &child_module_path,
+                    module_paths,
+                );
+            }
+            None => {
+                let (file_path, is_mod_rs) = match path_attribute {
+                    Some(path) => (path_directory.join(path), true),
+                    None => {
+                        let file_path = child_directory.join(format!("{}.rs", name));
+                        match file_path.exists() {
+                            true => (file_path, false),
+                            false => (child_directory.join(&name).join("mod.rs"), true),
+                        }
+                    }
+                };
+                visit_module_file(&file_path, child_module_path, is_mod_rs, module_paths);
+            }
+        }
+    }
+}
+
+fn get_path_attribute(attrs: &[Attribute]) -> Option<String> {
+    attrs.iter().find_map(|attr| match &attr.meta {
+        Meta::NameValue(name_value) if name_value.path.is_ident("path") => {
+            match &name_value.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(path),
+                    ..
+                }) => Some(path.value()),
+                _ => None,
+            }
+        }
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_build_module_tree() {
+        let source_code_dir =
+            std::env::temp_dir().join(format!("dcm-module-tree-{}", std::process::id()));
+        let files = [
+            (
+                "lib.rs",
+                "pub mod shared;\n#[path = \"platform/web.rs\"]\npub mod platform;\nmod inline { pub mod nested; }",
+            ),
+            ("shared/mod.rs", "pub mod webshop;"),
+            ("shared/webshop.rs", "pub mod order;"),
+            ("shared/webshop/order.rs", ""),
+            ("platform/web.rs", "pub mod storage;"),
+            ("platform/storage.rs", ""),
+            ("inline/nested.rs", ""),
+            ("unused.rs", ""),
+        ];
+        for (file, code) in files {
+            let file_path = source_code_dir.join(file);
+            fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+            fs::write(file_path, code).unwrap();
+        }
+
+        let module_tree = build_module_tree(&source_code_dir);
+        fs::remove_dir_all(&source_code_dir).unwrap();
+
+        let module_path = |file: &str| {
+            module_tree
+                .get(&source_code_dir.join(file))
+                .map(|module_path| module_path.join("::"))
+        };
+        assert_eq!(module_path("lib.rs"), Some(String::new()));
+        assert_eq!(module_path("shared/mod.rs"), Some("shared".to_string()));
+        assert_eq!(
+            module_path("shared/webshop/order.rs"),
+            Some("shared::webshop::order".to_string())
+        );
+        assert_eq!(module_path("platform/web.rs"), Some("platform".to_string()));
+        assert_eq!(
+            module_path("platform/storage.rs"),
+            Some("platform::storage".to_string())
+        );
+        assert_eq!(
+            module_path("inline/nested.rs"),
+            Some("inline::nested".to_string())
+        );
+        assert_eq!(module_path("unused.rs"), None);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::modules::source_code_analyzer::types::rust_const::RustConst;
+use crate::modules::source_code_analyzer::types::rust_enum::RustEnum;
 use crate::modules::source_code_analyzer::types::rust_function::RustFunction;
 use crate::modules::source_code_analyzer::types::rust_impl::RustImpl;
 use crate::modules::source_code_analyzer::types::rust_static::RustStatic;
@@ -14,12 +15,15 @@ use std::path::PathBuf;
 pub struct RustFile {
     pub absolute_filepath: PathBuf,
     pub relative_filepath: PathBuf,
+    /// The module the file is loaded as, following the `mod` declarations and their `#[path]` attributes
+    pub module_path: Vec<String>,
     pub uses: Vec<RustUse>,
     pub functions: Vec<RustFunction>,
     pub consts: Vec<RustConst>,
     pub statics: Vec<RustStatic>,
     pub type_definitions: Vec<RustTypeDefinition>,
     pub structs: Vec<RustStruct>,
+    pub enums: Vec<RustEnum>,
     pub impls: Vec<RustImpl>,
 
     // This property is here to keep track of the index of this Rust file in the vector.
@@ -1,2 +1 @@
 pub mod rust_item;
-
@@ -5,6 +5,8 @@ use crate::modules::util;
 use proc_macro2::Span;
 use serde_derive::{Deserialize, Serialize};
 
+pub mod rust_enum;
+
 pub mod rust_function;
 
 pub mod rust_impl;
@@ -0,0 +1,137 @@
+use crate::modules::source_code_analyzer::traits::rust_item::RustItem;
+use crate::modules::source_code_analyzer::types::rust_struct::RustStructField;
+use crate::modules::source_code_analyzer::types::RustItemCommonProperties;
+use crate::modules::util;
+use crate::modules::util::serde_attributes::{get_serialized_name, SerdeAttributes};
+use serde_derive::Serialize;
+use syn::spanned::Spanned;
+use syn::{Fields, ItemEnum};
+
+/// This struct represents a Rust enum in Rust syntax tree
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct RustEnum {
+    pub properties: RustItemCommonProperties,
+    pub variants: Vec<RustEnumVariant>,
+    pub representation: EnumRepresentation,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct RustEnumVariant {
+    pub name: String,
+    /// The name of the variant in the serialized enum, following its `#[serde(rename)]` and `#[serde(rename_all)]`
+    pub serialized_name: String,
+    pub fields: RustEnumVariantFields,
+}
+
+/// How serde tags the variants of an enum, set with the `#[serde(tag, content, untagged)]` attributes.
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub enum EnumRepresentation {
+    #[default]
+    External,
+    Internal {
+        tag: String,
+    },
+    Adjacent {
+        tag: String,
+        content: String,
+    },
+    Untagged,
+}
+
+/// The fields of an enum variant, which determine how serde represents the variant.
+#[derive(Debug, Serialize, Clone, Default)]
+pub enum RustEnumVariantFields {
+    #[default]
+    Unit,
+    Unnamed(Vec<String>),
+    Named(Vec<RustStructField>),
+}
+
+impl From<ItemEnum> for RustEnum {
+    fn from(item_enum: ItemEnum) -> Self {
+        let location = item_enum.span().into();
+        let serde_attributes = SerdeAttributes::parse(&item_enum.attrs);
+        let representation = match (
+            serde_attributes.untagged,
+            &serde_attributes.tag,
+            &serde_attributes.content,
+        ) {
+            (true, _, _) => EnumRepresentation::Untagged,
+            (false, Some(tag), Some(content)) => EnumRepresentation::Adjacent {
+                tag: tag.clone(),
+                content: content.clone(),
+            },
+            (false, Some(tag), None) => EnumRepresentation::Internal { tag: tag.clone() },
+            (false, None, _) => EnumRepresentation::External,
+        };
+        let variants = item_enum
+            .variants
+            .iter()
+            .map(|variant| {
+                let variant_attributes = SerdeAttributes::parse(&variant.attrs);
+                let field_rename_rule = variant_attributes
+                    .rename_all
+                    .or(serde_attributes.rename_all_fields);
+                RustEnumVariant {
+                    name: variant.ident.to_string(),
+                    serialized_name: get_serialized_name(
+                        &variant.ident.to_string(),
+                        &variant_attributes,
+                        serde_attributes.rename_all,
+                        true,
+                    ),
+                    fields: match &variant.fields {
+                        Fields::Unit => RustEnumVariantFields::Unit,
+                        Fields::Unnamed(fields) => RustEnumVariantFields::Unnamed(
+                            fields
+                                .unnamed
+                                .iter()
+                                .map(|field| util::get_type_string(&field.ty))
+                                .collect(),
+                        ),
+                        Fields::Named(fields) => RustEnumVariantFields::Named(
+                            fields
+                                .named
+                                .iter()
+                                .filter_map(|field| {
+                                    field.ident.as_ref().map(|ident| RustStructField {
+                                        name: ident.to_string(),
+                                        serialized_name: get_serialized_name(
+                                            &ident.to_string(),
+                                            &SerdeAttributes::parse(&field.attrs),
+                                            field_rename_rule,
+                                            false,
+                                        ),
+                                        rust_type: util::get_type_string(&field.ty),
+                                    })
+                                })
+                                .collect(),
+                        ),
+                    },
+                }
+            })
+            .collect();
+
+        let properties = RustItemCommonProperties {
+            name: item_enum.ident.to_string(),
+            position: location,
+            ..Default::default()
+        };
+
+        Self {
+            properties,
+            variants,
+            representation,
+        }
+    }
+}
+
+impl RustItem for RustEnum {
+    fn get_common_properties(&self) -> RustItemCommonProperties {
+        self.properties.clone()
+    }
+
+    fn get_common_properties_mut(&mut self) -> &mut RustItemCommonProperties {
+        &mut self.properties
+    }
+}
@@ -1,9 +1,12 @@
 use crate::modules::source_code_analyzer::traits::rust_item::RustItem;
 use crate::modules::source_code_analyzer::types::RustItemCommonProperties;
 use crate::modules::util;
+use crate::modules::util::type_mapper::get_generic_types;
 use quote::ToTokens;
 use serde_derive::Serialize;
-use syn::{spanned::Spanned, FnArg, ImplItemFn, ItemFn, Pat, Signature, Type, Visibility};
+use syn::{
+    spanned::Spanned, FnArg, ImplItemFn, ItemFn, Pat, Receiver, Signature, Type, Visibility,
+};
 
 /// This struct represents a Rust function in Rust syntax tree
 #[derive(Debug, Serialize, Clone, Default)]
@@ -14,13 +17,14 @@ pub struct RustFunction {
     pub is_public: bool,
     pub struct_name: Option<String>,
     pub function_type: RustFunctionType,
+    /// How a method receives its instance, `None` for free and associated functions
+    pub receiver: Option<ReceiverKind>,
 }
 
 #[derive(Debug, Serialize, Clone, Default)]
 pub struct ParameterInfo {
     pub name: String,
     pub rust_type: String,
-    pub js_type: String,
     pub is_reference: bool,
     pub is_mutable: bool,
 }
@@ -33,6 +37,94 @@ pub enum RustFunctionType {
     AssociatedFunction,
 }
 
+/// The receiver of a method, which determines whether the instance is changed or consumed by the call.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub enum ReceiverKind {
+    /// `self`, the instance is consumed by the call
+    Owned,
+    /// `&self`, the instance is left unchanged
+    Shared,
+    /// `&mut self`, the instance may be changed by the call
+    Mutable,
+    /// `self: Box<Self>`, `self: Rc<Self>` or `self: Arc<Self>`, the instance is wrapped into the pointer and consumed
+    Pointer(String),
+    /// Receivers the wrappers cannot pass the instance to, e.g. `self: Pin<&mut Self>`, holding the receiver type.
+    /// Methods with these receivers are not exported.
+    Unsupported(String),
+}
+
+impl ReceiverKind {
+    /// Returns whether the instance cannot be used after the call
+    pub fn consumes_instance(&self) -> bool {
+        matches!(self, ReceiverKind::Owned | ReceiverKind::Pointer(_))
+    }
+
+    /// Returns the expression passing the deserialized `instance` to the method.
+    pub fn wrap_instance(&self, instance: &str) -> String {
+        match self {
+            ReceiverKind::Pointer(pointer) if pointer == "Rc" => {
+                format!("std::rc::Rc::new({})", instance)
+            }
+            ReceiverKind::Pointer(pointer) if pointer == "Arc" => {
+                format!("std::sync::Arc::new({})", instance)
+            }
+            ReceiverKind::Pointer(pointer) => format!("{}::new({})", pointer, instance),
+            _ => instance.to_string(),
+        }
+    }
+}
+
+impl From<&Receiver> for ReceiverKind {
+    // The type is set for the shorthand receivers as well, while `reference` is only set for `&self` and `&mut self`
+    fn from(receiver: &Receiver) -> Self {
+        match &*receiver.ty {
+            Type::Reference(type_reference) if type_reference.mutability.is_some() => {
+                ReceiverKind::Mutable
+            }
+            Type::Reference(_) => ReceiverKind::Shared,
+            ty if is_instance_type(ty) => ReceiverKind::Owned,
+            ty => match get_pointer_name(ty) {
+                Some(pointer) => ReceiverKind::Pointer(pointer),
+                None => ReceiverKind::Unsupported(ty.to_token_stream().to_string()),
+            },
+        }
+    }
+}
+
+// Returns the name of the smart pointer of a `Box<Self>`, `Rc<Self>` or `Arc<Self>` receiver
+fn get_pointer_name(ty: &Type) -> Option<String> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let last_segment = type_path.path.segments.last()?;
+    let pointer = last_segment.ident.to_string();
+    match get_generic_types(&last_segment.arguments).as_slice() {
+        [inner] if ["Box", "Rc", "Arc"].contains(&pointer.as_str()) && is_instance_type(inner) => {
+            Some(pointer)
+        }
+        _ => None,
+    }
+}
+
+// `Self` or the name of the struct, which is a path without generic arguments
+fn is_instance_type(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => {
+            type_path.qself.is_none()
+                && type_path.path.segments.len() == 1
+                && type_path.path.segments[0].arguments.is_empty()
+        }
+        _ => false,
+    }
+}
+
+fn get_receiver(signature: &Signature) -> Option<ReceiverKind> {
+    signature.inputs.iter().find_map(|param| match param {
+        FnArg::Receiver(receiver) => Some(receiver.into()),
+        FnArg::Typed(_) => None,
+    })
+}
+
 fn get_function_parameters(signature: &Signature, is_method: &mut bool) -> Vec<ParameterInfo> {
     let mut params = Vec::new();
     let mut index = 0;
@@ -54,17 +146,10 @@ fn get_function_parameters(signature: &Signature, is_method: &mut bool) -> Vec<P
         };
 
         if let FnArg::Typed(typed) = param {
-            let rust_type = typed
-                .ty
-                .clone()
-                .into_token_stream()
-                .to_string()
-                .replace(' ', "");
-            let json_type = util::get_js_type_from_rust_type(&rust_type);
+            let rust_type = util::get_type_string(&typed.ty);
             params.push(ParameterInfo {
                 name,
                 rust_type,
-                js_type: json_type,
                 is_reference: matches!(&*typed.ty, Type::Reference(_)),
                 is_mutable: matches!(&*typed.ty, Type::Reference(type_reference) if type_reference.mutability.is_some()),
             });
@@ -78,29 +163,18 @@ fn get_return_type(signature: &Signature) -> ParameterInfo {
     let (rust_type, is_reference, is_mutable) = match &signature.output {
         syn::ReturnType::Default => ("()".to_string(), false, false),
         syn::ReturnType::Type(_, ty) => match &**ty {
-            Type::Reference(type_reference) => {
-                let rust_type = type_reference
-                    .clone()
-                    .elem
-                    .into_token_stream()
-                    .to_string()
-                    .replace(' ', "");
-                (rust_type, true, type_reference.mutability.is_some())
-            }
-            _ => (
-                ty.into_token_stream().to_string().replace(' ', ""),
-                false,
-                false,
+            Type::Reference(type_reference) => (
+                util::get_type_string(&type_reference.elem),
+                true,
+                type_reference.mutability.is_some(),
             ),
+            _ => (util::get_type_string(ty), false, false),
         },
     };
 
-    let json_return_type = util::get_js_type_from_rust_type(&rust_type);
-
     ParameterInfo {
         name: "return".to_string(),
         rust_type,
-        js_type: json_return_type,
         is_reference,
         is_mutable,
     }
@@ -129,6 +203,7 @@ impl From<ItemFn> for RustFunction {
             return_type,
             struct_name: None,
             function_type: RustFunctionType::FreeFunction,
+            receiver: None,
         }
     }
 }
@@ -141,6 +216,7 @@ impl From<ImplItemFn> for RustFunction {
         let parameters = get_function_parameters(&item_fn.sig, &mut is_method);
         let return_type = get_return_type(&item_fn.sig);
         let is_public = matches!(item_fn.vis, Visibility::Public(_));
+        let receiver = get_receiver(&item_fn.sig);
 
         let properties = RustItemCommonProperties {
             name,
@@ -159,6 +235,7 @@ impl From<ImplItemFn> for RustFunction {
             } else {
                 RustFunctionType::AssociatedFunction
             },
+            receiver,
             ..Default::default()
         }
     }
@@ -173,3 +250,49 @@ impl RustItem for RustFunction {
         &mut self.properties
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_receiver_kind() {
+        let get_receiver_kind = |code: &str| {
+            let impl_item_fn: ImplItemFn = syn::parse_str(code).unwrap();
+            RustFunction::from(impl_item_fn).receiver
+        };
+        assert_eq!(
+            get_receiver_kind("fn total(&self) -> u32 { 0 }"),
+            Some(ReceiverKind::Shared)
+        );
+        assert_eq!(
+            get_receiver_kind("fn clear(&mut self) {}"),
+            Some(ReceiverKind::Mutable)
+        );
+        assert_eq!(
+            get_receiver_kind("fn checkout(mut self) {}"),
+            Some(ReceiverKind::Owned)
+        );
+        assert_eq!(
+            get_receiver_kind("fn total(self: &Self) -> u32 { 0 }"),
+            Some(ReceiverKind::Shared)
+        );
+        assert_eq!(
+            get_receiver_kind("fn clear(self: &mut Self) {}"),
+            Some(ReceiverKind::Mutable)
+        );
+        assert_eq!(
+            get_receiver_kind("fn checkout(self: Box<Self>) {}"),
+            Some(ReceiverKind::Pointer("Box".to_string()))
+        );
+        assert_eq!(
+            get_receiver_kind("fn share(self: std::rc::Rc<Self>) {}"),
+            Some(ReceiverKind::Pointer("Rc".to_string()))
+        );
+        assert_eq!(
+            get_receiver_kind("fn poll(self: Pin<&mut Self>) {}"),
+            Some(ReceiverKind::Unsupported("Pin < & mut Self >".to_string()))
+        );
+        assert_eq!(get_receiver_kind("fn new() -> Self { todo!() }"), None);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::modules::source_code_analyzer::traits::rust_item::RustItem;
 use crate::modules::source_code_analyzer::types::RustItemCommonProperties;
 use crate::modules::util;
-use quote::ToTokens;
+use crate::modules::util::serde_attributes::{get_serialized_name, SerdeAttributes};
 use serde_derive::Serialize;
 use syn::spanned::Spanned;
 use syn::ItemStruct;
@@ -16,8 +16,9 @@ pub struct RustStruct {
 #[derive(Debug, Serialize, Clone, Default)]
 pub struct RustStructField {
     pub name: String,
+    /// The key of the field in the serialized struct, following its `#[serde(rename)]` and `#[serde(rename_all)]`
+    pub serialized_name: String,
     pub rust_type: String,
-    pub js_type: String,
 }
 
 impl From<ItemStruct> for RustStruct {
@@ -25,17 +26,24 @@ impl From<ItemStruct> for RustStruct {
         let struct_name = item_struct.ident.to_string();
         let location = item_struct.span().into();
 
+        let rename_rule = SerdeAttributes::parse(&item_struct.attrs).rename_all;
+
         let mut fields = Vec::new();
 
         for field in item_struct.fields.iter() {
             if let Some(ident) = &field.ident {
                 let name = ident.to_string();
-                let rust_type = field.ty.to_token_stream().to_string().replace(' ', "");
-                let js_type = util::get_js_type_from_rust_type(&rust_type);
+                let serialized_name = get_serialized_name(
+                    &name,
+                    &SerdeAttributes::parse(&field.attrs),
+                    rename_rule,
+                    false,
+                );
+                let rust_type = util::get_type_string(&field.ty);
                 fields.push(RustStructField {
                     name,
+                    serialized_name,
                     rust_type,
-                    js_type,
                 });
             }
         }
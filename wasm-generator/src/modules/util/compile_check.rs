@@ -0,0 +1,47 @@
+use crate::modules::dependency_resolver::crates_resolver::DEFAULT_CRATES;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Builds the given code as the `lib.rs` of a crate depending on the default fragment crates and runs its tests natively.
+/// The crate is locked to the versions of the generator's own lockfile and built offline, so that the tests can check
+/// that the generated code compiles without network access. Returns the compiler or test output on failure.
+pub fn test_generated_crate(name: &str, code: &str) -> Result<(), String> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let base_path = manifest_dir.join("target").join("compile_check");
+    let crate_path = base_path.join(name);
+    fs::create_dir_all(crate_path.join("src")).map_err(|e| e.to_string())?;
+
+    let dependencies = DEFAULT_CRATES
+        .iter()
+        .map(|(name, version)| format!("{} = \"{}\"", name, version))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let cargo_toml = format!(
+        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n{}\n\n[workspace]\n",
+        name, dependencies
+    );
+    fs::write(crate_path.join("Cargo.toml"), cargo_toml).map_err(|e| e.to_string())?;
+    fs::write(crate_path.join("src").join("lib.rs"), code).map_err(|e| e.to_string())?;
+    fs::copy(
+        manifest_dir.join("Cargo.lock"),
+        crate_path.join("Cargo.lock"),
+    )
+    .map_err(|e| e.to_string())?;
+
+    // The crates share a target directory, so the dependencies are only built once
+    let output = Command::new(env!("CARGO"))
+        .args(["test", "--offline", "--quiet"])
+        .env("CARGO_TARGET_DIR", base_path.join("target"))
+        .current_dir(&crate_path)
+        .output()
+        .map_err(|e| e.to_string())?;
+    match output.status.success() {
+        true => Ok(()),
+        false => Err(format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )),
+    }
+}
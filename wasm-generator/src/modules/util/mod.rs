@@ -1,12 +1,18 @@
 use crate::modules::error::ApplicationError;
+use quote::ToTokens;
 use serde::de::DeserializeOwned;
 use std::path::PathBuf;
-use syn::File;
+use syn::{File, Type};
 use walkdir::DirEntry;
 
 pub mod file_handler;
 pub mod id_generator;
+pub mod serde_attributes;
 pub mod thread_manager;
+pub mod type_mapper;
+
+#[cfg(test)]
+pub(crate) mod compile_check;
 
 pub fn parse_cfd<T: DeserializeOwned>(file_path: PathBuf) -> Result<T, ApplicationError> {
     let file_content = file_handler::read(&file_path)?;
@@ -37,46 +43,24 @@ pub fn line_col_to_char_pos(source: &str, line: usize, column: usize) -> usize {
         + column
 }
 
-use regex::Regex;
-
-pub fn get_js_type_from_rust_type(rust_type: &str) -> String {
-    let re_collection = Regex::new(r"(Vec|HashSet|BTreeSet)\s*<\s*([^>]+)\s*>").unwrap();
-    let re_option = Regex::new(r"Option\s*<\s*([^>]+)\s*>").unwrap();
-
-    match rust_type {
-        "()" => "void".to_string(),
-        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128"
-        | "usize" | "f32" | "f64" => "Number".to_string(),
-        "bool" => "Boolean".to_string(),
-        "String" | "&str" => "String".to_string(),
-        parameter if re_collection.is_match(parameter) => {
-            let captures = re_collection.captures(parameter).unwrap();
-            let inner_type = captures.get(2).unwrap().as_str();
-            format!("Array<{}>", get_js_type_from_rust_type(inner_type))
-        }
-        parameter if re_option.is_match(parameter) => {
-            let captures = re_option.captures(parameter).unwrap();
-            let inner_type = captures.get(1).unwrap().as_str();
-            format!("{} | null", get_js_type_from_rust_type(inner_type))
-        }
-        _ => "Object".to_string(),
-    }
-}
-
-pub fn get_default_value_for_js_type(js_type: &str) -> String {
-    if js_type.starts_with("Array<") {
-        "[]".to_string() // Default for any Array<T> type
-    } else if js_type.ends_with(" | null") {
-        "null".to_string() // Default for optional types T | null
-    } else {
-        match js_type {
-            "Number" => "0".to_string(),      // Default number
-            "Boolean" => "false".to_string(), // Default boolean
-            "String" => "\"\"".to_string(),   // Default string (empty string)
-            "Object" => "{}".to_string(),     // Default object (empty object)
-            _ => "null".to_string(),          // Default for unrecognized types
-        }
-    }
+/// Renders a type as a compact string, e.g. `HashMap<String,Vec<u8>>`.
+/// Spaces are only removed around punctuation, so `&mut T` and `dyn Trait` keep their meaning.
+pub fn get_type_string(ty: &Type) -> String {
+    let type_string = ty.to_token_stream().to_string();
+    let is_punctuation = |c: char| "<>,&()[];:".contains(c);
+    let chars = type_string.chars().collect::<Vec<char>>();
+    chars
+        .iter()
+        .enumerate()
+        .filter(|(index, c)| {
+            **c != ' '
+                || !(index
+                    .checked_sub(1)
+                    .is_some_and(|i| is_punctuation(chars[i]))
+                    || chars.get(index + 1).is_some_and(|c| is_punctuation(*c)))
+        })
+        .map(|(_, c)| *c)
+        .collect()
 }
 
 pub fn is_primitive(type_name: &str) -> bool {
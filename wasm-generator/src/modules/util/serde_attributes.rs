@@ -0,0 +1,225 @@
+use syn::meta::ParseNestedMeta;
+use syn::{Attribute, LitStr, Token};
+
+/// The `#[serde(...)]` attributes of a struct, enum, variant or field that change the names and the shape of its
+/// serialized form. Attributes serde would reject are ignored, the project does not compile with them anyway.
+#[derive(Debug, Default)]
+pub struct SerdeAttributes {
+    pub rename: Option<String>,
+    pub rename_all: Option<RenameRule>,
+    pub rename_all_fields: Option<RenameRule>,
+    pub tag: Option<String>,
+    pub content: Option<String>,
+    pub untagged: bool,
+}
+
+impl SerdeAttributes {
+    pub fn parse(attrs: &[Attribute]) -> Self {
+        let mut serde_attributes = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            let _ = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    serde_attributes.rename = get_serialize_value(&meta)?;
+                } else if meta.path.is_ident("rename_all") {
+                    serde_attributes.rename_all =
+                        get_serialize_value(&meta)?.and_then(|rule| RenameRule::parse(&rule));
+                } else if meta.path.is_ident("rename_all_fields") {
+                    serde_attributes.rename_all_fields =
+                        get_serialize_value(&meta)?.and_then(|rule| RenameRule::parse(&rule));
+                } else if meta.path.is_ident("tag") {
+                    serde_attributes.tag = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("content") {
+                    serde_attributes.content = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("untagged") {
+                    serde_attributes.untagged = true;
+                } else {
+                    skip_value(&meta)?;
+                }
+                Ok(())
+            });
+        }
+        serde_attributes
+    }
+}
+
+/// The case conventions of `#[serde(rename_all = "...")]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenameRule {
+    LowerCase,
+    UpperCase,
+    PascalCase,
+    CamelCase,
+    SnakeCase,
+    ScreamingSnakeCase,
+    KebabCase,
+    ScreamingKebabCase,
+}
+
+impl RenameRule {
+    pub fn parse(rule: &str) -> Option<Self> {
+        match rule {
+            "lowercase" => Some(RenameRule::LowerCase),
+            "UPPERCASE" => Some(RenameRule::UpperCase),
+            "PascalCase" => Some(RenameRule::PascalCase),
+            "camelCase" => Some(RenameRule::CamelCase),
+            "snake_case" => Some(RenameRule::SnakeCase),
+            "SCREAMING_SNAKE_CASE" => Some(RenameRule::ScreamingSnakeCase),
+            "kebab-case" => Some(RenameRule::KebabCase),
+            "SCREAMING-KEBAB-CASE" => Some(RenameRule::ScreamingKebabCase),
+            _ => None,
+        }
+    }
+
+    /// Renames a variant, whose name is expected in PascalCase, as serde does.
+    pub fn apply_to_variant(&self, variant: &str) -> String {
+        match self {
+            RenameRule::PascalCase => variant.to_string(),
+            RenameRule::LowerCase => variant.to_ascii_lowercase(),
+            RenameRule::UpperCase => variant.to_ascii_uppercase(),
+            RenameRule::CamelCase => {
+                let mut chars = variant.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                    None => String::new(),
+                }
+            }
+            RenameRule::SnakeCase => {
+                let mut snake = String::new();
+                for (i, ch) in variant.char_indices() {
+                    if i > 0 && ch.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(ch.to_ascii_lowercase());
+                }
+                snake
+            }
+            RenameRule::ScreamingSnakeCase => RenameRule::SnakeCase
+                .apply_to_variant(variant)
+                .to_ascii_uppercase(),
+            RenameRule::KebabCase => RenameRule::SnakeCase
+                .apply_to_variant(variant)
+                .replace('_', "-"),
+            RenameRule::ScreamingKebabCase => RenameRule::ScreamingSnakeCase
+                .apply_to_variant(variant)
+                .replace('_', "-"),
+        }
+    }
+
+    /// Renames a field, whose name is expected in snake_case, as serde does.
+    pub fn apply_to_field(&self, field: &str) -> String {
+        match self {
+            RenameRule::LowerCase | RenameRule::SnakeCase => field.to_string(),
+            RenameRule::UpperCase | RenameRule::ScreamingSnakeCase => field.to_ascii_uppercase(),
+            RenameRule::PascalCase => {
+                let mut pascal = String::new();
+                let mut capitalize = true;
+                for ch in field.chars() {
+                    if ch == '_' {
+                        capitalize = true;
+                    } else if capitalize {
+                        pascal.push(ch.to_ascii_uppercase());
+                        capitalize = false;
+                    } else {
+                        pascal.push(ch);
+                    }
+                }
+                pascal
+            }
+            RenameRule::CamelCase => {
+                let pascal = RenameRule::PascalCase.apply_to_field(field);
+                let mut chars = pascal.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                    None => String::new(),
+                }
+            }
+            RenameRule::KebabCase => field.replace('_', "-"),
+            RenameRule::ScreamingKebabCase => field.to_ascii_uppercase().replace('_', "-"),
+        }
+    }
+}
+
+/// Returns the serialized name of a struct field or enum variant, given the rule of its container.
+/// Raw identifiers are serialized without their `r#` prefix.
+pub fn get_serialized_name(
+    name: &str,
+    attrs: &SerdeAttributes,
+    rename_rule: Option<RenameRule>,
+    is_variant: bool,
+) -> String {
+    let name = name.strip_prefix("r#").unwrap_or(name);
+    match (&attrs.rename, rename_rule) {
+        (Some(rename), _) => rename.clone(),
+        (None, Some(rule)) if is_variant => rule.apply_to_variant(name),
+        (None, Some(rule)) => rule.apply_to_field(name),
+        (None, None) => name.to_string(),
+    }
+}
+
+/// Reads `key = "value"` or `key(serialize = "value", deserialize = "...")`, the values sent to the JS glue are the
+/// serialized ones.
+fn get_serialize_value(meta: &ParseNestedMeta) -> syn::Result<Option<String>> {
+    if meta.input.peek(Token![=]) {
+        return Ok(Some(meta.value()?.parse::<LitStr>()?.value()));
+    }
+    let mut value = None;
+    meta.parse_nested_meta(|nested| {
+        let nested_value = nested.value()?.parse::<LitStr>()?.value();
+        if nested.path.is_ident("serialize") {
+            value = Some(nested_value);
+        }
+        Ok(())
+    })?;
+    Ok(value)
+}
+
+/// Skips the value of a key that does not change the names or the shape, e.g. `default = "..."` or
+/// `bound(serialize = "...")`.
+fn skip_value(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.input.parse::<proc_macro2::TokenTree>()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_parse() {
+        let attrs: Vec<Attribute> = vec![
+            parse_quote!(#[derive(Serialize)]),
+            parse_quote!(#[serde(default, bound(serialize = "T: Serialize"), tag = "type")]),
+            parse_quote!(#[serde(rename_all(serialize = "camelCase", deserialize = "snake_case"))]),
+            parse_quote!(#[serde(with = "chrono", rename = "order")]),
+        ];
+        let serde_attributes = SerdeAttributes::parse(&attrs);
+        assert_eq!(serde_attributes.tag, Some("type".to_string()));
+        assert_eq!(serde_attributes.rename_all, Some(RenameRule::CamelCase));
+        assert_eq!(serde_attributes.rename, Some("order".to_string()));
+        assert!(!serde_attributes.untagged);
+    }
+
+    #[test]
+    fn test_rename_rules() {
+        let rules = [
+            ("lowercase", "outofstock", "unit_price"),
+            ("UPPERCASE", "OUTOFSTOCK", "UNIT_PRICE"),
+            ("PascalCase", "OutOfStock", "UnitPrice"),
+            ("camelCase", "outOfStock", "unitPrice"),
+            ("snake_case", "out_of_stock", "unit_price"),
+            ("SCREAMING_SNAKE_CASE", "OUT_OF_STOCK", "UNIT_PRICE"),
+            ("kebab-case", "out-of-stock", "unit-price"),
+            ("SCREAMING-KEBAB-CASE", "OUT-OF-STOCK", "UNIT-PRICE"),
+        ];
+        for (rule, variant, field) in rules {
+            let rule = RenameRule::parse(rule).unwrap();
+            assert_eq!(rule.apply_to_variant("OutOfStock"), variant);
+            assert_eq!(rule.apply_to_field("unit_price"), field);
+        }
+    }
+}
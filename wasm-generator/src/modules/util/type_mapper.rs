@@ -0,0 +1,781 @@
+use crate::modules::source_code_analyzer::rust_file::RustFile;
+use crate::modules::source_code_analyzer::types::rust_enum::{
+    EnumRepresentation, RustEnum, RustEnumVariantFields,
+};
+use crate::modules::source_code_analyzer::types::rust_struct::RustStruct;
+use crate::modules::source_code_analyzer::types::rust_struct::RustStructField;
+use std::collections::{BTreeMap, BTreeSet};
+use syn::{GenericArgument, ItemType, PathArguments, Type};
+
+/// A struct or enum of the project which is declared in the generated JS glue.
+pub enum NamedType<'a> {
+    Struct(&'a RustStruct),
+    Enum(&'a RustEnum),
+}
+
+impl NamedType<'_> {
+    pub fn get_name(&self) -> &str {
+        match self {
+            NamedType::Struct(rust_struct) => &rust_struct.properties.name,
+            NamedType::Enum(rust_enum) => &rust_enum.properties.name,
+        }
+    }
+}
+
+/// Maps Rust types, as they are serialized to MessagePack and decoded by the JS glue, to JS types.
+/// The produced types use the TypeScript syntax, which is understood by JSDoc as well.
+pub struct TypeMapper {
+    structs: BTreeMap<String, RustStruct>,
+    enums: BTreeMap<String, RustEnum>,
+    type_aliases: BTreeMap<String, Type>,
+    use_bigint: bool,
+    /// The structs and enums holding `bigint` values, which the glue converts after decoding
+    bigint_types: BTreeSet<String>,
+}
+
+impl TypeMapper {
+    /// Creates a mapper resolving the structs, enums and type aliases declared in the given files.
+    /// When `use_bigint` is set, 64 and 128 bit integers are mapped to `bigint` instead of `number`.
+    pub fn new(rust_files: &[RustFile], use_bigint: bool) -> Self {
+        let mut structs = BTreeMap::new();
+        let mut enums = BTreeMap::new();
+        let mut type_aliases = BTreeMap::new();
+        for rust_file in rust_files {
+            for rust_struct in &rust_file.structs {
+                structs
+                    .entry(rust_struct.properties.name.clone())
+                    .or_insert_with(|| rust_struct.clone());
+            }
+            for rust_enum in &rust_file.enums {
+                enums
+                    .entry(rust_enum.properties.name.clone())
+                    .or_insert_with(|| rust_enum.clone());
+            }
+            for type_definition in &rust_file.type_definitions {
+                if let Ok(item_type) = syn::parse_str::<ItemType>(&type_definition.properties.code)
+                {
+                    type_aliases
+                        .entry(item_type.ident.to_string())
+                        .or_insert(*item_type.ty);
+                }
+            }
+        }
+        let mut type_mapper = Self {
+            structs,
+            enums,
+            type_aliases,
+            use_bigint,
+            bigint_types: BTreeSet::new(),
+        };
+        type_mapper.bigint_types = type_mapper.collect_bigint_types();
+        type_mapper
+    }
+
+    pub fn uses_bigint(&self) -> bool {
+        self.use_bigint
+    }
+
+    pub fn get_struct(&self, name: &str) -> Option<&RustStruct> {
+        self.structs.get(name)
+    }
+
+    pub fn get_enum(&self, name: &str) -> Option<&RustEnum> {
+        self.enums.get(name)
+    }
+
+    /// Returns the type a type alias stands for
+    pub fn get_aliased_type(&self, name: &str) -> Option<&Type> {
+        self.type_aliases.get(name)
+    }
+
+    /// Returns the JS type of a Rust type, `self_type` is the type `Self` refers to.
+    /// Types that cannot be mapped are `unknown`.
+    pub fn get_js_type(&self, rust_type: &str, self_type: Option<&str>) -> String {
+        match syn::parse_str::<Type>(rust_type) {
+            Ok(ty) => self.map_type(&ty, self_type, &mut BTreeSet::new()),
+            Err(_) => "unknown".to_string(),
+        }
+    }
+
+    /// Returns a JS expression holding the default value of a Rust type, used to initialize class fields.
+    pub fn get_default_value(&self, rust_type: &str) -> String {
+        match syn::parse_str::<Type>(rust_type) {
+            Ok(ty) => self.default_value(&ty, &mut BTreeSet::new()),
+            Err(_) => "null".to_string(),
+        }
+    }
+
+    /// Returns the union of the serde representations of an enum's variants, following its `#[serde]` attributes.
+    /// Externally tagged unit variants are strings, the other variants objects keyed by the variant name.
+    pub fn get_enum_union(&self, rust_enum: &RustEnum) -> String {
+        if rust_enum.variants.is_empty() {
+            return "never".to_string();
+        }
+        rust_enum
+            .variants
+            .iter()
+            .map(|variant| {
+                let variant_name = format!("\"{}\"", variant.serialized_name);
+                let content = match &variant.fields {
+                    RustEnumVariantFields::Unit => None,
+                    RustEnumVariantFields::Unnamed(types) if types.len() == 1 => {
+                        Some(self.get_js_type(&types[0], None))
+                    }
+                    RustEnumVariantFields::Unnamed(types) => Some(format!(
+                        "[{}]",
+                        types
+                            .iter()
+                            .map(|ty| self.get_js_type(ty, None))
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    )),
+                    RustEnumVariantFields::Named(fields) => {
+                        Some(format!("{{ {} }}", self.get_object_members(fields)))
+                    }
+                };
+                match (&rust_enum.representation, content) {
+                    (EnumRepresentation::External, None) => variant_name,
+                    (EnumRepresentation::External, Some(content)) => format!(
+                        "{{ {}: {} }}",
+                        get_property_name(&variant.serialized_name),
+                        content
+                    ),
+                    (EnumRepresentation::Internal { tag }, _) => {
+                        let tag_member = format!("{}: {}", get_property_name(tag), variant_name);
+                        match &variant.fields {
+                            RustEnumVariantFields::Unit => format!("{{ {} }}", tag_member),
+                            // The fields of a struct variant are serialized next to the tag
+                            RustEnumVariantFields::Named(fields) if !fields.is_empty() => {
+                                format!("{{ {}; {} }}", tag_member, self.get_object_members(fields))
+                            }
+                            RustEnumVariantFields::Named(_) => format!("{{ {} }}", tag_member),
+                            RustEnumVariantFields::Unnamed(types) if types.len() == 1 => format!(
+                                "{{ {} }} & {}",
+                                tag_member,
+                                self.get_js_type(&types[0], None)
+                            ),
+                            // Serde rejects tuple variants in internally tagged enums
+                            RustEnumVariantFields::Unnamed(_) => "never".to_string(),
+                        }
+                    }
+                    (EnumRepresentation::Adjacent { tag, .. }, None) => {
+                        format!("{{ {}: {} }}", get_property_name(tag), variant_name)
+                    }
+                    (EnumRepresentation::Adjacent { tag, content: key }, Some(content)) => {
+                        format!(
+                            "{{ {}: {}; {}: {} }}",
+                            get_property_name(tag),
+                            variant_name,
+                            get_property_name(key),
+                            content
+                        )
+                    }
+                    (EnumRepresentation::Untagged, None) => "null".to_string(),
+                    (EnumRepresentation::Untagged, Some(content)) => content,
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" | ")
+    }
+
+    fn get_object_members(&self, fields: &[RustStructField]) -> String {
+        fields
+            .iter()
+            .map(|field| {
+                format!(
+                    "{}: {}",
+                    get_property_name(&field.serialized_name),
+                    self.get_js_type(&field.rust_type, None)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("; ")
+    }
+
+    /// Returns the structs and enums used by the given types and, transitively, by the fields of those types.
+    /// `excluded_names` are not returned, nor followed, e.g. the structs of mobile impls declared as classes.
+    pub fn get_reachable_types(
+        &self,
+        rust_types: Vec<String>,
+        excluded_names: &BTreeSet<String>,
+    ) -> Vec<NamedType<'_>> {
+        let mut rust_types = rust_types;
+        let mut reachable_names: BTreeSet<String> = BTreeSet::new();
+        while let Some(rust_type) = rust_types.pop() {
+            for type_name in self.get_type_names(&rust_type) {
+                if excluded_names.contains(&type_name) || reachable_names.contains(&type_name) {
+                    continue;
+                }
+                if let Some(rust_struct) = self.structs.get(&type_name) {
+                    rust_types.extend(rust_struct.fields.iter().map(|f| f.rust_type.clone()));
+                } else if let Some(rust_enum) = self.enums.get(&type_name) {
+                    for variant in &rust_enum.variants {
+                        match &variant.fields {
+                            RustEnumVariantFields::Unit => {}
+                            RustEnumVariantFields::Unnamed(types) => {
+                                rust_types.extend(types.iter().cloned())
+                            }
+                            RustEnumVariantFields::Named(fields) => {
+                                rust_types.extend(fields.iter().map(|f| f.rust_type.clone()))
+                            }
+                        }
+                    }
+                } else {
+                    continue;
+                }
+                reachable_names.insert(type_name);
+            }
+        }
+        reachable_names
+            .iter()
+            .filter_map(|name| match self.structs.get(name) {
+                Some(rust_struct) => Some(NamedType::Struct(rust_struct)),
+                None => self.enums.get(name).map(NamedType::Enum),
+            })
+            .collect()
+    }
+
+    /// Returns the last segment of every type path in the given type, including the ones nested in generic arguments
+    /// and the ones behind type aliases.
+    fn get_type_names(&self, rust_type: &str) -> Vec<String> {
+        let mut type_names = Vec::new();
+        if let Ok(ty) = syn::parse_str::<Type>(rust_type) {
+            self.collect_type_names(&ty, &mut type_names, &mut BTreeSet::new());
+        }
+        type_names
+    }
+
+    fn collect_type_names(
+        &self,
+        ty: &Type,
+        type_names: &mut Vec<String>,
+        visited_aliases: &mut BTreeSet<String>,
+    ) {
+        match ty {
+            Type::Path(type_path) => {
+                if let Some(last_segment) = type_path.path.segments.last() {
+                    let type_name = last_segment.ident.to_string();
+                    if let Some(aliased_type) = self.type_aliases.get(&type_name) {
+                        if visited_aliases.insert(type_name) {
+                            self.collect_type_names(aliased_type, type_names, visited_aliases);
+                        }
+                    } else {
+                        type_names.push(type_name);
+                    }
+                    for inner_type in get_generic_types(&last_segment.arguments) {
+                        self.collect_type_names(inner_type, type_names, visited_aliases);
+                    }
+                }
+            }
+            Type::Reference(type_reference) => {
+                self.collect_type_names(&type_reference.elem, type_names, visited_aliases)
+            }
+            Type::Slice(type_slice) => {
+                self.collect_type_names(&type_slice.elem, type_names, visited_aliases)
+            }
+            Type::Array(type_array) => {
+                self.collect_type_names(&type_array.elem, type_names, visited_aliases)
+            }
+            Type::Tuple(type_tuple) => type_tuple
+                .elems
+                .iter()
+                .for_each(|elem| self.collect_type_names(elem, type_names, visited_aliases)),
+            Type::Paren(type_paren) => {
+                self.collect_type_names(&type_paren.elem, type_names, visited_aliases)
+            }
+            _ => {}
+        }
+    }
+
+    fn map_type(
+        &self,
+        ty: &Type,
+        self_type: Option<&str>,
+        visited_aliases: &mut BTreeSet<String>,
+    ) -> String {
+        let mut map = |ty: &Type| self.map_type(ty, self_type, visited_aliases);
+        match ty {
+            Type::Reference(type_reference) => map(&type_reference.elem),
+            Type::Paren(type_paren) => map(&type_paren.elem),
+            Type::Group(type_group) => map(&type_group.elem),
+            Type::Slice(type_slice) => format!("Array<{}>", map(&type_slice.elem)),
+            Type::Array(type_array) => format!("Array<{}>", map(&type_array.elem)),
+            Type::Tuple(type_tuple) if type_tuple.elems.is_empty() => "void".to_string(),
+            Type::Tuple(type_tuple) => format!(
+                "[{}]",
+                type_tuple
+                    .elems
+                    .iter()
+                    .map(map)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Type::Path(type_path) => {
+                let Some(last_segment) = type_path.path.segments.last() else {
+                    return "unknown".to_string();
+                };
+                let generic_types = get_generic_types(&last_segment.arguments);
+                let type_name = last_segment.ident.to_string();
+                match (type_name.as_str(), generic_types.as_slice()) {
+                    ("i64" | "i128" | "u64" | "u128", _) if self.use_bigint => "bigint".to_string(),
+                    (
+                        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32"
+                        | "u64" | "u128" | "usize" | "f32" | "f64",
+                        _,
+                    ) => "number".to_string(),
+                    ("bool", _) => "boolean".to_string(),
+                    ("String" | "str" | "char", _) => "string".to_string(),
+                    ("Vec" | "VecDeque" | "LinkedList" | "HashSet" | "BTreeSet", [inner]) => {
+                        format!("Array<{}>", map(inner))
+                    }
+                    ("HashMap" | "BTreeMap", [key, value]) => {
+                        // Object keys are strings, `bigint` is no valid key type
+                        let key = match map(key) {
+                            key if key == "bigint" => "string".to_string(),
+                            key => key,
+                        };
+                        format!("Record<{}, {}>", key, map(value))
+                    }
+                    ("Option", [inner]) => format!("{} | null", map(inner)),
+                    ("Box" | "Rc" | "Arc", [inner]) => map(inner),
+                    ("Result", [ok, err]) => {
+                        format!("{{ Ok: {} }} | {{ Err: {} }}", map(ok), map(err))
+                    }
+                    ("Self", _) => self_type.unwrap_or("unknown").to_string(),
+                    (name, _)
+                        if self.structs.contains_key(name) || self.enums.contains_key(name) =>
+                    {
+                        name.to_string()
+                    }
+                    (name, _) => match self.type_aliases.get(name) {
+                        Some(aliased_type) if visited_aliases.insert(name.to_string()) => {
+                            self.map_type(aliased_type, self_type, visited_aliases)
+                        }
+                        _ => "unknown".to_string(),
+                    },
+                }
+            }
+            _ => "unknown".to_string(),
+        }
+    }
+
+    /// Returns a JS literal locating the `bigint` values in a value of the given Rust type, which the JS glue uses to
+    /// convert the integers decoded as numbers, `self_type` is the type `Self` refers to.
+    /// `None` if the value holds no `bigint`, or if `js_bigint` is disabled.
+    pub fn get_bigint_schema(&self, rust_type: &str, self_type: Option<&str>) -> Option<String> {
+        let ty = syn::parse_str::<Type>(rust_type).ok()?;
+        self.bigint_schema(&ty, self_type, &self.bigint_types, &mut BTreeSet::new())
+    }
+
+    /// Returns the schemas of the structs and enums holding `bigint` values by name, the schemas returned by
+    /// `get_bigint_schema` refer to them as `{ type: "Name" }`.
+    pub fn get_bigint_type_schemas(&self) -> Vec<(String, String)> {
+        self.bigint_types
+            .iter()
+            .filter_map(|name| {
+                self.named_bigint_schema(name, &self.bigint_types)
+                    .map(|schema| (name.clone(), schema))
+            })
+            .collect()
+    }
+
+    /// Types referring to each other are resolved by adding the types holding `bigint` values until no type is added.
+    fn collect_bigint_types(&self) -> BTreeSet<String> {
+        let mut bigint_types = BTreeSet::new();
+        if !self.use_bigint {
+            return bigint_types;
+        }
+        loop {
+            let next_bigint_types: BTreeSet<String> = self
+                .structs
+                .keys()
+                .chain(self.enums.keys())
+                .filter(|name| self.named_bigint_schema(name, &bigint_types).is_some())
+                .cloned()
+                .collect();
+            if next_bigint_types.len() == bigint_types.len() {
+                return bigint_types;
+            }
+            bigint_types = next_bigint_types;
+        }
+    }
+
+    fn named_bigint_schema(&self, name: &str, bigint_types: &BTreeSet<String>) -> Option<String> {
+        if let Some(rust_struct) = self.structs.get(name) {
+            return self.fields_bigint_schema(&rust_struct.fields, bigint_types);
+        }
+        let rust_enum = self.enums.get(name)?;
+        let variants = rust_enum
+            .variants
+            .iter()
+            .filter_map(|variant| {
+                let schema = match &variant.fields {
+                    RustEnumVariantFields::Unit => None,
+                    RustEnumVariantFields::Unnamed(types) if types.len() == 1 => {
+                        self.parsed_bigint_schema(&types[0], bigint_types)
+                    }
+                    RustEnumVariantFields::Unnamed(types) => get_tuple_bigint_schema(
+                        types
+                            .iter()
+                            .map(|ty| self.parsed_bigint_schema(ty, bigint_types))
+                            .collect(),
+                    ),
+                    RustEnumVariantFields::Named(fields) => {
+                        self.fields_bigint_schema(fields, bigint_types)
+                    }
+                }?;
+                Some((variant.serialized_name.as_str(), schema))
+            })
+            .collect::<Vec<(&str, String)>>();
+        let variants = get_variants_bigint_schema(&variants)?;
+        match &rust_enum.representation {
+            EnumRepresentation::External => Some(format!("{{ {} }}", variants)),
+            EnumRepresentation::Internal { tag } => {
+                Some(format!("{{ tag: \"{}\", {} }}", tag, variants))
+            }
+            EnumRepresentation::Adjacent { tag, content } => Some(format!(
+                "{{ tag: \"{}\", content: \"{}\", {} }}",
+                tag, content, variants
+            )),
+            // The variant of an untagged value is not known, its integers are left as they were decoded
+            EnumRepresentation::Untagged => None,
+        }
+    }
+
+    fn fields_bigint_schema(
+        &self,
+        fields: &[RustStructField],
+        bigint_types: &BTreeSet<String>,
+    ) -> Option<String> {
+        let members = fields
+            .iter()
+            .filter_map(|field| {
+                self.parsed_bigint_schema(&field.rust_type, bigint_types)
+                    .map(|schema| {
+                        format!("{}: {}", get_property_name(&field.serialized_name), schema)
+                    })
+            })
+            .collect::<Vec<String>>();
+        match members.is_empty() {
+            true => None,
+            false => Some(format!("{{ fields: {{ {} }} }}", members.join(", "))),
+        }
+    }
+
+    fn parsed_bigint_schema(
+        &self,
+        rust_type: &str,
+        bigint_types: &BTreeSet<String>,
+    ) -> Option<String> {
+        let ty = syn::parse_str::<Type>(rust_type).ok()?;
+        self.bigint_schema(&ty, None, bigint_types, &mut BTreeSet::new())
+    }
+
+    fn bigint_schema(
+        &self,
+        ty: &Type,
+        self_type: Option<&str>,
+        bigint_types: &BTreeSet<String>,
+        visited_aliases: &mut BTreeSet<String>,
+    ) -> Option<String> {
+        if !self.use_bigint {
+            return None;
+        }
+        let mut schema =
+            |ty: &Type| self.bigint_schema(ty, self_type, bigint_types, visited_aliases);
+        match ty {
+            Type::Reference(type_reference) => schema(&type_reference.elem),
+            Type::Paren(type_paren) => schema(&type_paren.elem),
+            Type::Group(type_group) => schema(&type_group.elem),
+            Type::Slice(type_slice) => {
+                schema(&type_slice.elem).map(|inner| format!("{{ array: {} }}", inner))
+            }
+            Type::Array(type_array) => {
+                schema(&type_array.elem).map(|inner| format!("{{ array: {} }}", inner))
+            }
+            Type::Tuple(type_tuple) => {
+                get_tuple_bigint_schema(type_tuple.elems.iter().map(schema).collect())
+            }
+            Type::Path(type_path) => {
+                let last_segment = type_path.path.segments.last()?;
+                let generic_types = get_generic_types(&last_segment.arguments);
+                let type_name = last_segment.ident.to_string();
+                match (type_name.as_str(), generic_types.as_slice()) {
+                    ("i64" | "i128" | "u64" | "u128", _) => Some("\"bigint\"".to_string()),
+                    ("Vec" | "VecDeque" | "LinkedList" | "HashSet" | "BTreeSet", [inner]) => {
+                        schema(inner).map(|inner| format!("{{ array: {} }}", inner))
+                    }
+                    ("HashMap" | "BTreeMap", [_, value]) => {
+                        schema(value).map(|value| format!("{{ record: {} }}", value))
+                    }
+                    ("Option" | "Box" | "Rc" | "Arc", [inner]) => schema(inner),
+                    ("Result", [ok, err]) => {
+                        let variants = [("Ok", schema(ok)), ("Err", schema(err))]
+                            .into_iter()
+                            .filter_map(|(name, schema)| schema.map(|schema| (name, schema)))
+                            .collect::<Vec<(&str, String)>>();
+                        get_variants_bigint_schema(&variants)
+                            .map(|variants| format!("{{ {} }}", variants))
+                    }
+                    ("Self", _) => self_type
+                        .filter(|name| bigint_types.contains(*name))
+                        .map(|name| format!("{{ type: \"{}\" }}", name)),
+                    (name, _) if bigint_types.contains(name) => {
+                        Some(format!("{{ type: \"{}\" }}", name))
+                    }
+                    (name, _) => match self.type_aliases.get(name) {
+                        Some(aliased_type) if visited_aliases.insert(name.to_string()) => self
+                            .bigint_schema(aliased_type, self_type, bigint_types, visited_aliases),
+                        _ => None,
+                    },
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn default_value(&self, ty: &Type, visited_aliases: &mut BTreeSet<String>) -> String {
+        match ty {
+            Type::Reference(type_reference) => {
+                self.default_value(&type_reference.elem, visited_aliases)
+            }
+            Type::Paren(type_paren) => self.default_value(&type_paren.elem, visited_aliases),
+            Type::Slice(_) | Type::Array(_) => "[]".to_string(),
+            Type::Tuple(type_tuple) if type_tuple.elems.is_empty() => "null".to_string(),
+            Type::Tuple(type_tuple) => format!(
+                "[{}]",
+                type_tuple
+                    .elems
+                    .iter()
+                    .map(|elem| self.default_value(elem, visited_aliases))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Type::Path(type_path) => {
+                let Some(last_segment) = type_path.path.segments.last() else {
+                    return "null".to_string();
+                };
+                match self.map_type(ty, None, &mut BTreeSet::new()).as_str() {
+                    "number" => "0".to_string(),
+                    "bigint" => "0n".to_string(),
+                    "boolean" => "false".to_string(),
+                    "string" => "\"\"".to_string(),
+                    js_type if js_type.starts_with("Array<") => "[]".to_string(),
+                    js_type if js_type.starts_with("Record<") => "{}".to_string(),
+                    _ => {
+                        // Aliases of tuples are expanded here, all other types are initialized once received
+                        let type_name = last_segment.ident.to_string();
+                        match self.type_aliases.get(&type_name) {
+                            Some(aliased_type) if visited_aliases.insert(type_name) => {
+                                self.default_value(aliased_type, visited_aliases)
+                            }
+                            _ => "null".to_string(),
+                        }
+                    }
+                }
+            }
+            _ => "null".to_string(),
+        }
+    }
+}
+
+/// Returns the name of an object property as it is written in JS, quoted when it is not an identifier,
+/// e.g. for fields renamed to kebab case.
+pub fn get_property_name(name: &str) -> String {
+    let mut chars = name.chars();
+    let is_identifier = chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_' || first == '$')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '$');
+    match is_identifier {
+        true => name.to_string(),
+        false => format!("\"{}\"", name),
+    }
+}
+
+/// Returns the schema of a tuple, `null` marks the elements without `bigint` values.
+fn get_tuple_bigint_schema(elements: Vec<Option<String>>) -> Option<String> {
+    if elements.iter().all(Option::is_none) {
+        return None;
+    }
+    let elements = elements
+        .into_iter()
+        .map(|element| element.unwrap_or_else(|| "null".to_string()))
+        .collect::<Vec<String>>();
+    Some(format!("{{ tuple: [{}] }}", elements.join(", ")))
+}
+
+/// Returns the `variants` member of an enum schema, mapping the serialized variant names to the schemas of their content.
+fn get_variants_bigint_schema(variants: &[(&str, String)]) -> Option<String> {
+    if variants.is_empty() {
+        return None;
+    }
+    let variants = variants
+        .iter()
+        .map(|(name, schema)| format!("{}: {}", get_property_name(name), schema))
+        .collect::<Vec<String>>();
+    Some(format!("variants: {{ {} }}", variants.join(", ")))
+}
+
+pub(crate) fn get_generic_types(arguments: &PathArguments) -> Vec<&Type> {
+    match arguments {
+        PathArguments::AngleBracketed(arguments) => arguments
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_type_mapper(use_bigint: bool) -> TypeMapper {
+        let source_code = r#"
+            struct Order { id: u64, items: Vec<Item> }
+            struct Item { name: String, quantity: u32 }
+            struct Cache { entries: HashMap<String, u8> }
+            enum Status { Open, Shipped(u64), Moved(i32, i32), Cancelled { reason: String } }
+            #[serde(tag = "type", rename_all = "snake_case", rename_all_fields = "camelCase")]
+            enum Event { Opened, Paid { total_price: f64 }, Updated(Item) }
+            #[serde(tag = "t", content = "c")]
+            enum Change { Reset, Set(u8), #[serde(rename = "move")] Moved(i32, i32) }
+            #[serde(untagged)]
+            enum Amount { Empty, Exact(u32), Range { min: u32, #[serde(rename = "max-value")] max: u32 } }
+            type Orders = Vec<Order>;
+        "#;
+        let syntax_tree = syn::parse_file(source_code).unwrap();
+        let mut rust_file = RustFile::default();
+        for item in syntax_tree.items {
+            match item {
+                syn::Item::Struct(item_struct) => rust_file.structs.push(item_struct.into()),
+                syn::Item::Enum(item_enum) => rust_file.enums.push(item_enum.into()),
+                syn::Item::Type(item_type) => {
+                    let mut type_definition: crate::modules::source_code_analyzer::types::rust_type_definition::RustTypeDefinition = item_type.clone().into();
+                    type_definition.properties.code = quote::quote!(#item_type).to_string();
+                    rust_file.type_definitions.push(type_definition);
+                }
+                _ => {}
+            }
+        }
+        TypeMapper::new(&[rust_file], use_bigint)
+    }
+
+    #[test]
+    fn test_get_js_type() {
+        let type_mapper = get_type_mapper(false);
+        assert_eq!(type_mapper.get_js_type("Vec<Order>", None), "Array<Order>");
+        assert_eq!(
+            type_mapper.get_js_type("HashMap<String,Vec<u64>>", None),
+            "Record<string, Array<number>>"
+        );
+        assert_eq!(
+            type_mapper.get_js_type("(char,bool,[f32;3])", None),
+            "[string, boolean, Array<number>]"
+        );
+        assert_eq!(
+            type_mapper.get_js_type("Option<&str>", None),
+            "string | null"
+        );
+        assert_eq!(type_mapper.get_js_type("Orders", None), "Array<Order>");
+        assert_eq!(
+            type_mapper.get_js_type("Option<Self>", Some("OrderManager")),
+            "OrderManager | null"
+        );
+        assert_eq!(type_mapper.get_js_type("Mutex<u8>", None), "unknown");
+        assert_eq!(type_mapper.get_js_type("()", None), "void");
+    }
+
+    #[test]
+    fn test_get_enum_union() {
+        let type_mapper = get_type_mapper(false);
+        let status = &type_mapper.enums["Status"];
+        assert_eq!(
+            type_mapper.get_enum_union(status),
+            "\"Open\" | { Shipped: number } | { Moved: [number, number] } | { Cancelled: { reason: string } }"
+        );
+        assert_eq!(
+            type_mapper.get_enum_union(&type_mapper.enums["Event"]),
+            "{ type: \"opened\" } | { type: \"paid\"; totalPrice: number } | { type: \"updated\" } & Item"
+        );
+        assert_eq!(
+            type_mapper.get_enum_union(&type_mapper.enums["Change"]),
+            "{ t: \"Reset\" } | { t: \"Set\"; c: number } | { t: \"move\"; c: [number, number] }"
+        );
+        assert_eq!(
+            type_mapper.get_enum_union(&type_mapper.enums["Amount"]),
+            "null | number | { min: number; \"max-value\": number }"
+        );
+    }
+
+    #[test]
+    fn test_get_reachable_types() {
+        let type_mapper = get_type_mapper(false);
+        let reachable_types = type_mapper.get_reachable_types(
+            vec!["Option<Orders>".to_string(), "Status".to_string()],
+            &BTreeSet::new(),
+        );
+        let names = reachable_types
+            .iter()
+            .map(|named_type| named_type.get_name())
+            .collect::<Vec<&str>>();
+        assert_eq!(names, vec!["Item", "Order", "Status"]);
+    }
+
+    #[test]
+    fn test_get_default_value() {
+        let type_mapper = get_type_mapper(false);
+        assert_eq!(type_mapper.get_default_value("Vec<Item>"), "[]");
+        assert_eq!(type_mapper.get_default_value("BTreeMap<u8,u8>"), "{}");
+        assert_eq!(
+            type_mapper.get_default_value("(String,bool)"),
+            "[\"\", false]"
+        );
+        assert_eq!(type_mapper.get_default_value("Option<u8>"), "null");
+        assert_eq!(type_mapper.get_default_value("Item"), "null");
+    }
+
+    #[test]
+    fn test_bigint_mapping() {
+        let type_mapper = get_type_mapper(true);
+        assert_eq!(type_mapper.get_js_type("Vec<u64>", None), "Array<bigint>");
+        assert_eq!(type_mapper.get_js_type("i32", None), "number");
+        assert_eq!(type_mapper.get_default_value("u64"), "0n");
+        assert_eq!(get_type_mapper(false).get_default_value("u64"), "0");
+    }
+
+    #[test]
+    fn test_get_bigint_schema() {
+        let type_mapper = get_type_mapper(true);
+        assert_eq!(
+            type_mapper
+                .get_bigint_schema("Option<Orders>", None)
+                .unwrap(),
+            "{ array: { type: \"Order\" } }"
+        );
+        assert_eq!(
+            type_mapper.get_bigint_schema("(u8,&i64)", None).unwrap(),
+            "{ tuple: [null, \"bigint\"] }"
+        );
+        assert_eq!(type_mapper.get_bigint_schema("Vec<Item>", None), None);
+        assert_eq!(
+            type_mapper.get_bigint_type_schemas(),
+            vec![
+                (
+                    "Order".to_string(),
+                    "{ fields: { id: \"bigint\" } }".to_string()
+                ),
+                (
+                    "Status".to_string(),
+                    "{ variants: { Shipped: \"bigint\" } }".to_string()
+                ),
+            ]
+        );
+        assert_eq!(get_type_mapper(false).get_bigint_schema("u64", None), None);
+    }
+}
@@ -0,0 +1,470 @@
+use crate::modules::source_code_analyzer::types::rust_enum::{
+    EnumRepresentation, RustEnumVariantFields,
+};
+use crate::modules::source_code_analyzer::types::rust_function::ParameterInfo;
+use crate::modules::util::type_mapper::{get_generic_types, TypeMapper};
+use serde_json::{json, Map, Value};
+use syn::Type;
+
+/// Types nested deeper than this are cut short, which ends recursive types at their `Option`s and collections.
+const MAX_DEPTH: usize = 4;
+const MAX_COLLECTION_LENGTH: u64 = 4;
+const STRING_CHARACTERS: [char; 12] = ['a', 'b', 'Z', '0', '9', ' ', '_', '-', 'ä', 'ß', '€', '🦀'];
+const EDGE_STRINGS: [&str; 4] = ["", "a", "héllo wörld", "🦀 crab"];
+const EDGE_CHARS: [char; 3] = ['a', 'ß', '🦀'];
+
+/// Generates the parameters of the verified functions from their types.
+/// The first cases of every type hold its edge values, such as zero, the limits of integers or the empty string,
+/// the remaining cases are random. The cases only depend on the seed, so every run with the same seed repeats them.
+pub struct InputGenerator<'a> {
+    type_mapper: &'a TypeMapper,
+    random: SplitMix64,
+}
+
+impl<'a> InputGenerator<'a> {
+    pub fn new(type_mapper: &'a TypeMapper, seed: u64) -> Self {
+        Self {
+            type_mapper,
+            random: SplitMix64(seed),
+        }
+    }
+
+    /// Returns the parameters of `case_count` calls, or the type of a parameter no values can be generated for.
+    pub fn generate_cases(
+        &mut self,
+        parameters: &[ParameterInfo],
+        case_count: usize,
+    ) -> Result<Vec<Vec<Value>>, String> {
+        let types = parameters
+            .iter()
+            .map(|parameter| {
+                syn::parse_str::<Type>(&parameter.rust_type)
+                    .map_err(|_| parameter.rust_type.clone())
+            })
+            .collect::<Result<Vec<Type>, String>>()?;
+        (0..case_count)
+            .map(|case_index| {
+                types
+                    .iter()
+                    .zip(parameters)
+                    .map(|(ty, parameter)| {
+                        self.generate(ty, case_index, 0)
+                            .ok_or_else(|| parameter.rust_type.clone())
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // `is_multiple_of` is not available on the toolchain of the Dockerfile
+    #[allow(clippy::manual_is_multiple_of)]
+    fn generate(&mut self, ty: &Type, case_index: usize, depth: usize) -> Option<Value> {
+        match ty {
+            Type::Reference(type_reference) => {
+                self.generate(&type_reference.elem, case_index, depth)
+            }
+            Type::Paren(type_paren) => self.generate(&type_paren.elem, case_index, depth),
+            Type::Group(type_group) => self.generate(&type_group.elem, case_index, depth),
+            Type::Slice(type_slice) => self.generate_sequence(&type_slice.elem, case_index, depth),
+            Type::Array(type_array) => {
+                let syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Int(length),
+                    ..
+                }) = &type_array.len
+                else {
+                    return None;
+                };
+                (0..length.base10_parse::<usize>().ok()?)
+                    .map(|_| self.generate(&type_array.elem, case_index, depth + 1))
+                    .collect::<Option<Vec<Value>>>()
+                    .map(Value::Array)
+            }
+            Type::Tuple(type_tuple) if type_tuple.elems.is_empty() => Some(Value::Null),
+            Type::Tuple(type_tuple) => type_tuple
+                .elems
+                .iter()
+                .map(|elem| self.generate(elem, case_index, depth + 1))
+                .collect::<Option<Vec<Value>>>()
+                .map(Value::Array),
+            Type::Path(type_path) => {
+                let last_segment = type_path.path.segments.last()?;
+                let generic_types = get_generic_types(&last_segment.arguments);
+                let type_name = last_segment.ident.to_string();
+                match (type_name.as_str(), generic_types.as_slice()) {
+                    ("bool", _) => Some(Value::Bool(match case_index {
+                        0 | 1 => case_index == 1,
+                        _ => self.random.next() % 2 == 1,
+                    })),
+                    ("f32", _) => Some(self.generate_float(case_index, f32::MAX as f64)),
+                    ("f64", _) => Some(self.generate_float(case_index, f64::MAX)),
+                    ("char", _) => Some(Value::String(
+                        match EDGE_CHARS.get(case_index) {
+                            Some(edge_char) => *edge_char,
+                            None => self.random_char(),
+                        }
+                        .to_string(),
+                    )),
+                    ("String" | "str", _) => Some(Value::String(self.generate_string(case_index))),
+                    ("Vec" | "VecDeque" | "LinkedList" | "HashSet" | "BTreeSet", [inner]) => {
+                        self.generate_sequence(inner, case_index, depth)
+                    }
+                    ("HashMap" | "BTreeMap", [key, value]) => {
+                        self.generate_map(key, value, case_index, depth)
+                    }
+                    ("Option", [inner]) => match case_index == 0
+                        || depth >= MAX_DEPTH
+                        || (case_index >= 4 && self.random.next() % 4 == 0)
+                    {
+                        true => Some(Value::Null),
+                        false => self.generate(inner, case_index, depth + 1),
+                    },
+                    ("Result", [ok, err]) => match case_index % 2 {
+                        0 => Some(json!({ "Ok": self.generate(ok, case_index, depth + 1)? })),
+                        _ => Some(json!({ "Err": self.generate(err, case_index, depth + 1)? })),
+                    },
+                    ("Box" | "Rc" | "Arc" | "Cow", [inner]) => {
+                        self.generate(inner, case_index, depth)
+                    }
+                    (name, []) => match get_integer_range(name) {
+                        Some((min, max)) => Some(self.generate_integer(case_index, min, max)),
+                        None => self.generate_named_type(name, case_index, depth),
+                    },
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::manual_is_multiple_of)]
+    fn generate_integer(&mut self, case_index: usize, min: i128, max: i128) -> Value {
+        let mut edge_values = vec![0, 1, max];
+        if min < 0 {
+            edge_values.extend([-1, min]);
+        }
+        let value = match edge_values.get(case_index) {
+            Some(edge_value) => *edge_value,
+            // Small values are more likely to pass the checks of the functions, so half of the values are below 100
+            None if self.random.next() % 2 == 0 => {
+                let value = (self.random.next() % 100) as i128;
+                match min < 0 && self.random.next() % 2 == 0 {
+                    true => -value,
+                    false => value,
+                }
+            }
+            None => {
+                let range = (max - min) as u128 + 1;
+                min + ((self.random.next() as u128 * self.random.next() as u128) % range) as i128
+            }
+        };
+        match value < 0 {
+            true => json!(value as i64),
+            false => json!(value as u64),
+        }
+    }
+
+    fn generate_float(&mut self, case_index: usize, max: f64) -> Value {
+        let edge_values = [0.0, 0.1, -1.5, 1e21, 1e-7, max];
+        let value = match edge_values.get(case_index) {
+            Some(edge_value) => *edge_value,
+            None => {
+                let exponent = (self.random.next() % 25) as i32 - 12;
+                (self.random.next() as f64 / u64::MAX as f64 - 0.5) * 10f64.powi(exponent)
+            }
+        };
+        json!(value)
+    }
+
+    fn generate_string(&mut self, case_index: usize) -> String {
+        match EDGE_STRINGS.get(case_index) {
+            Some(edge_string) => edge_string.to_string(),
+            None => (0..self.random.next() % 12)
+                .map(|_| self.random_char())
+                .collect(),
+        }
+    }
+
+    fn random_char(&mut self) -> char {
+        STRING_CHARACTERS[(self.random.next() % STRING_CHARACTERS.len() as u64) as usize]
+    }
+
+    fn generate_length(&mut self, case_index: usize, depth: usize) -> u64 {
+        match case_index {
+            _ if depth >= MAX_DEPTH => 0,
+            0 => 0,
+            1 => 1,
+            _ => 1 + self.random.next() % MAX_COLLECTION_LENGTH,
+        }
+    }
+
+    fn generate_sequence(
+        &mut self,
+        inner: &Type,
+        case_index: usize,
+        depth: usize,
+    ) -> Option<Value> {
+        (0..self.generate_length(case_index, depth))
+            .map(|_| self.generate(inner, case_index, depth + 1))
+            .collect::<Option<Vec<Value>>>()
+            .map(Value::Array)
+    }
+
+    // Maps are encoded as MessagePack maps with the keys as strings, so only keys deserialized from strings are supported
+    fn generate_map(
+        &mut self,
+        key: &Type,
+        value: &Type,
+        case_index: usize,
+        depth: usize,
+    ) -> Option<Value> {
+        if !matches!(
+            self.generate(key, usize::MAX, depth + 1),
+            Some(Value::String(_))
+        ) {
+            return None;
+        }
+        let mut map = Map::new();
+        for index in 0..self.generate_length(case_index, depth) {
+            let key = format!("{}{}", self.generate_string(usize::MAX), index);
+            map.insert(key, self.generate(value, case_index, depth + 1)?);
+        }
+        Some(Value::Object(map))
+    }
+
+    /// Structs and enums of the project are generated as serde represents them, following their `#[serde]` attributes.
+    fn generate_named_type(
+        &mut self,
+        name: &str,
+        case_index: usize,
+        depth: usize,
+    ) -> Option<Value> {
+        if depth >= MAX_DEPTH {
+            return None;
+        }
+        let type_mapper = self.type_mapper;
+        if let Some(rust_struct) = type_mapper.get_struct(name) {
+            // Tuple structs are not recorded with their fields, so they cannot be generated
+            if rust_struct.fields.is_empty() {
+                return None;
+            }
+            let mut object = Map::new();
+            for field in &rust_struct.fields {
+                let ty = syn::parse_str::<Type>(&field.rust_type).ok()?;
+                object.insert(
+                    field.serialized_name.clone(),
+                    self.generate(&ty, case_index, depth + 1)?,
+                );
+            }
+            return Some(Value::Object(object));
+        }
+        if let Some(rust_enum) = type_mapper.get_enum(name) {
+            if rust_enum.variants.is_empty() {
+                return None;
+            }
+            let variant_index = match case_index < rust_enum.variants.len() {
+                true => case_index,
+                false => (self.random.next() % rust_enum.variants.len() as u64) as usize,
+            };
+            let variant = &rust_enum.variants[variant_index];
+            let name = Value::String(variant.serialized_name.clone());
+            let content = match &variant.fields {
+                RustEnumVariantFields::Unit => {
+                    return match &rust_enum.representation {
+                        EnumRepresentation::External => Some(name),
+                        EnumRepresentation::Internal { tag }
+                        | EnumRepresentation::Adjacent { tag, .. } => Some(json!({ tag: name })),
+                        EnumRepresentation::Untagged => Some(Value::Null),
+                    };
+                }
+                RustEnumVariantFields::Unnamed(types) => {
+                    let mut values = types
+                        .iter()
+                        .map(|rust_type| {
+                            let ty = syn::parse_str::<Type>(rust_type).ok()?;
+                            self.generate(&ty, case_index, depth + 1)
+                        })
+                        .collect::<Option<Vec<Value>>>()?;
+                    match values.len() {
+                        1 => values.remove(0),
+                        _ => Value::Array(values),
+                    }
+                }
+                RustEnumVariantFields::Named(fields) => {
+                    let mut object = Map::new();
+                    for field in fields {
+                        let ty = syn::parse_str::<Type>(&field.rust_type).ok()?;
+                        object.insert(
+                            field.serialized_name.clone(),
+                            self.generate(&ty, case_index, depth + 1)?,
+                        );
+                    }
+                    Value::Object(object)
+                }
+            };
+            return match &rust_enum.representation {
+                EnumRepresentation::External => {
+                    Some(json!({ variant.serialized_name.clone(): content }))
+                }
+                // The tag is added to the fields of the content, which therefore has to be an object
+                EnumRepresentation::Internal { tag } => match content {
+                    Value::Object(mut object) => {
+                        object.insert(tag.clone(), name);
+                        Some(Value::Object(object))
+                    }
+                    _ => None,
+                },
+                EnumRepresentation::Adjacent { tag, content: key } => {
+                    Some(json!({ tag: name, key: content }))
+                }
+                EnumRepresentation::Untagged => Some(content),
+            };
+        }
+        let aliased_type = type_mapper.get_aliased_type(name)?;
+        self.generate(aliased_type, case_index, depth + 1)
+    }
+}
+
+/// Returns the range of an integer type. The values of `usize` and `isize` cover 64 bits as on the native targets,
+/// so that fragments relying on their width are reported, since they are 32 bits wide on wasm32.
+/// 128 bit integers are limited to 64 bits, which is the range of MessagePack integers.
+fn get_integer_range(type_name: &str) -> Option<(i128, i128)> {
+    Some(match type_name {
+        "u8" => (0, u8::MAX as i128),
+        "u16" => (0, u16::MAX as i128),
+        "u32" => (0, u32::MAX as i128),
+        "u64" | "u128" | "usize" => (0, u64::MAX as i128),
+        "i8" => (i8::MIN as i128, i8::MAX as i128),
+        "i16" => (i16::MIN as i128, i16::MAX as i128),
+        "i32" => (i32::MIN as i128, i32::MAX as i128),
+        "i64" | "i128" | "isize" => (i64::MIN as i128, i64::MAX as i128),
+        _ => return None,
+    })
+}
+
+/// The SplitMix64 generator, which is sufficient for generating inputs and needs no further dependency.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::application::MobileFragments;
+    use crate::modules::source_code_analyzer::ast_visitor::AstVisitor;
+    use crate::modules::source_code_analyzer::FilePath;
+    use syn::visit::Visit;
+
+    fn get_parameters(rust_types: &[&str]) -> Vec<ParameterInfo> {
+        rust_types
+            .iter()
+            .enumerate()
+            .map(|(index, rust_type)| ParameterInfo {
+                name: format!("param{}", index),
+                rust_type: rust_type.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_generate_cases() {
+        let source_code = "
+            pub struct Item { pub name: String, pub price: u32 }
+            pub enum Discount { None, Percent(u8), Fixed { amount: i64 } }
+        ";
+        let mut mobile_fragments = MobileFragments::default();
+        let mut ast_visitor = AstVisitor::new(
+            FilePath::from("src/lib.rs".to_string()),
+            source_code.to_string(),
+            &mut mobile_fragments,
+        );
+        ast_visitor.visit_file(&syn::parse_file(source_code).unwrap());
+        let type_mapper = TypeMapper::new(&[ast_visitor.rust_file], false);
+
+        let parameters = get_parameters(&["&[Item]", "Discount", "usize", "Option<f32>"]);
+        let cases = InputGenerator::new(&type_mapper, 7)
+            .generate_cases(&parameters, 16)
+            .unwrap();
+        assert_eq!(cases.len(), 16);
+        assert_eq!(
+            cases[0],
+            vec![json!([]), json!("None"), json!(0), json!(null)]
+        );
+        assert_eq!(
+            cases[1],
+            vec![
+                json!([{ "name": "a", "price": 1 }]),
+                json!({ "Percent": 1 }),
+                json!(1),
+                json!(0.1f64)
+            ]
+        );
+        assert_eq!(cases[2][1], json!({ "Fixed": { "amount": i64::MAX } }));
+        assert_eq!(cases[2][2], json!(u64::MAX));
+        assert_eq!(
+            InputGenerator::new(&type_mapper, 7).generate_cases(&parameters, 16),
+            Ok(cases)
+        );
+
+        assert_eq!(
+            InputGenerator::new(&type_mapper, 7)
+                .generate_cases(&get_parameters(&["u8", "HashMap<u32, String>"]), 1),
+            Err("HashMap<u32, String>".to_string())
+        );
+    }
+
+    #[test]
+    fn test_generate_serde_representations() {
+        let source_code = r#"
+            #[serde(rename_all = "camelCase")]
+            pub struct Item { pub unit_price: u32 }
+            #[serde(tag = "type", rename_all = "lowercase")]
+            pub enum Event { Opened, Paid { amount: u8 }, Added(Item) }
+            #[serde(tag = "t", content = "c")]
+            pub enum Change { Reset, Set(u8) }
+            #[serde(untagged)]
+            pub enum Amount { Empty, Exact(u8) }
+        "#;
+        let mut mobile_fragments = MobileFragments::default();
+        let mut ast_visitor = AstVisitor::new(
+            FilePath::from("src/lib.rs".to_string()),
+            source_code.to_string(),
+            &mut mobile_fragments,
+        );
+        ast_visitor.visit_file(&syn::parse_file(source_code).unwrap());
+        let type_mapper = TypeMapper::new(&[ast_visitor.rust_file], false);
+
+        let parameters = get_parameters(&["Event", "Change", "Amount"]);
+        let cases = InputGenerator::new(&type_mapper, 7)
+            .generate_cases(&parameters, 3)
+            .unwrap();
+        assert_eq!(
+            cases[0],
+            vec![
+                json!({ "type": "opened" }),
+                json!({ "t": "Reset" }),
+                json!(null)
+            ]
+        );
+        assert_eq!(
+            cases[1],
+            vec![
+                json!({ "type": "paid", "amount": 1 }),
+                json!({ "t": "Set", "c": 1 }),
+                json!(1)
+            ]
+        );
+        assert_eq!(
+            cases[2][0],
+            json!({ "type": "added", "unitPrice": u32::MAX })
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use crate::modules::application::{
+    ExecutableFragmentDataForCodeDistributor, FinalFragmentContext, MobileFragments,
+};
+use crate::modules::configuration::Configuration;
+use crate::modules::error::ApplicationError;
+use crate::modules::fragment_compiler::get_wasm_target_directory;
+use crate::modules::source_code_analyzer::rust_file::RustFile;
+use crate::modules::util::type_mapper::TypeMapper;
+use crate::modules::verifier::input_generator::InputGenerator;
+use code_distributor::fragment_executor::wasmtime;
+use log::{error, info, warn};
+use serde_json::Value;
+use std::fmt;
+
+pub mod input_generator;
+pub mod native_harness;
+
+/// How a call of a fragment ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// The payload of the response, holding the result and the changed instance and parameters
+    Returned(Value),
+    /// The error code and message of the response, `Trap` for wasm traps that are not caused by a panic
+    Failed { code: String, message: String },
+}
+
+impl Outcome {
+    /// Failed calls are compared by their error code, since panic messages contain paths that differ between the builds.
+    fn matches(&self, other: &Outcome) -> bool {
+        match (self, other) {
+            (Outcome::Returned(value), Outcome::Returned(other_value)) => value == other_value,
+            (
+                Outcome::Failed { code, .. },
+                Outcome::Failed {
+                    code: other_code, ..
+                },
+            ) => code == other_code,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Returned(value) => write!(f, "{}", value),
+            Outcome::Failed { code, message } => write!(f, "{}: {}", code, message),
+        }
+    }
+}
+
+/// A compiled module and its function fragments that are verified.
+pub struct VerifiedModule<'a> {
+    pub fragment: &'a FinalFragmentContext,
+    pub functions: Vec<VerifiedFunction>,
+}
+
+pub struct VerifiedFunction {
+    pub fragment_id: String,
+    /// The export of the function, including the export prefix of bundled fragments
+    pub export_name: String,
+    pub cases: Vec<Vec<Value>>,
+}
+
+/// Calls every function fragment with generated parameters, once built for the host and once as the compiled wasm module
+/// executed by the code-distributor, and reports the calls whose results differ. See "Verifying Fragments" in the readme.
+/// Returns the number of mismatches.
+pub fn run(
+    mobile_fragments: &MobileFragments,
+    generated_fragments: &[FinalFragmentContext],
+    rust_files: &[RustFile],
+    config: &Configuration,
+    case_count: usize,
+    seed: u64,
+) -> Result<usize, ApplicationError> {
+    let type_mapper = TypeMapper::new(rust_files, false);
+    let mut input_generator = InputGenerator::new(&type_mapper, seed);
+    let mut modules: Vec<VerifiedModule> = Vec::new();
+    for fragment in &mobile_fragments.functions {
+        let entry = ExecutableFragmentDataForCodeDistributor::from(fragment.clone());
+        let cases =
+            match input_generator.generate_cases(&fragment.rust_function.parameters, case_count) {
+                Ok(cases) => cases,
+                Err(rust_type) => {
+                    warn!(
+                        "Skipping fragment {}, no parameters of type {} can be generated",
+                        entry.id, rust_type
+                    );
+                    continue;
+                }
+            };
+        let Some(generated_fragment) = generated_fragments
+            .iter()
+            .find(|generated| generated.fragment_identifier == entry.module.file)
+        else {
+            warn!("Skipping fragment {}, its module was not built", entry.id);
+            continue;
+        };
+        let function = VerifiedFunction {
+            export_name: format!(
+                "{}{}",
+                entry.module.export_prefix, entry.functions[0].export_name
+            ),
+            fragment_id: entry.id,
+            cases,
+        };
+        match modules
+            .iter_mut()
+            .find(|module| module.fragment.fragment_identifier == entry.module.file)
+        {
+            Some(module) => module.functions.push(function),
+            None => modules.push(VerifiedModule {
+                fragment: generated_fragment,
+                functions: vec![function],
+            }),
+        }
+    }
+    if modules.is_empty() {
+        warn!("No fragments to verify");
+        return Ok(0);
+    }
+
+    let binaries = native_harness::build(&modules, config)?;
+
+    let mut mismatch_count = 0;
+    for (module, binary) in modules.iter().zip(binaries) {
+        let module_info = wasmtime::load_module(
+            &get_wasm_target_directory(config).join(&module.fragment.fragment_identifier),
+        )
+        .map_err(|e| ApplicationError::StrError(e.to_string()))?;
+        for function in &module.functions {
+            let mut function_mismatch_count = 0;
+            for parameters in &function.cases {
+                let native = native_harness::execute(&binary, &function.export_name, parameters)?;
+                let wasm = execute_wasm(&module_info, &function.export_name, parameters);
+                if !native.matches(&wasm) {
+                    error!(
+                        "Fragment {} behaves differently as wasm for the parameters {}\n  native: {}\n  wasm:   {}",
+                        function.fragment_id,
+                        Value::Array(parameters.clone()),
+                        native,
+                        wasm
+                    );
+                    function_mismatch_count += 1;
+                }
+            }
+            info!(
+                "Verified fragment {} with {} cases, {} mismatches",
+                function.fragment_id,
+                function.cases.len(),
+                function_mismatch_count
+            );
+            mismatch_count += function_mismatch_count;
+        }
+    }
+    Ok(mismatch_count)
+}
+
+fn execute_wasm(
+    module_info: &wasmtime::ModuleInfo,
+    export_name: &str,
+    parameters: &[Value],
+) -> Outcome {
+    match wasmtime::execute(module_info, export_name.to_string(), parameters) {
+        Ok(output) => {
+            Outcome::Returned(serde_json::from_str(&output).unwrap_or(Value::String(output)))
+        }
+        Err(e) => get_failure(e),
+    }
+}
+
+fn get_failure(error: code_distributor::ApplicationError) -> Outcome {
+    match error {
+        code_distributor::ApplicationError::FragmentError { code, message } => {
+            Outcome::Failed { code, message }
+        }
+        code_distributor::ApplicationError::FragmentTrap { error, .. } => get_failure(*error),
+        e => Outcome::Failed {
+            code: "Trap".to_string(),
+            message: e.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_outcome_matches() {
+        let returned = Outcome::Returned(json!({ "result": 0.30000000000000004 }));
+        assert!(returned.matches(&Outcome::Returned(json!({ "result": 0.30000000000000004 }))));
+        assert!(!returned.matches(&Outcome::Returned(json!({ "result": 0.3 }))));
+
+        let panic = Outcome::Failed {
+            code: "Panic".to_string(),
+            message: "attempt to add with overflow".to_string(),
+        };
+        assert!(panic.matches(&Outcome::Failed {
+            code: "Panic".to_string(),
+            message: String::new(),
+        }));
+        assert!(!panic.matches(&Outcome::Failed {
+            code: "InvalidParameter".to_string(),
+            message: String::new(),
+        }));
+        assert!(!panic.matches(&returned));
+    }
+}
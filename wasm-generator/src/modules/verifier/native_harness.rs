@@ -0,0 +1,240 @@
+use crate::modules::configuration::Configuration;
+use crate::modules::constants::FRAGMENTS_LOCATION;
+use crate::modules::dependency_resolver::crates_resolver::DEFAULT_CRATES;
+use crate::modules::error::ApplicationError;
+use crate::modules::fragment_compiler;
+use crate::modules::verifier::{Outcome, VerifiedModule};
+use log::info;
+use serde_json::Value;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// The workspace of the native harnesses, it is kept apart from the fragments so that their wasm builds are unaffected.
+const HARNESS_LOCATION: &str = "temp/verify";
+
+/// Generates a binary per module, which links the crate of the module built for the host and calls its exports through
+/// the fragment ABI, as the hosts call the wasm module. The binaries are built with the profile of the wasm modules,
+/// so that both builds agree on overflow checks, and share the lockfile of the fragments workspace.
+/// Returns the paths of the binaries in the order of the modules.
+pub fn build(
+    modules: &[VerifiedModule],
+    config: &Configuration,
+) -> Result<Vec<PathBuf>, ApplicationError> {
+    let workspace_path = PathBuf::from(&config.project).join(HARNESS_LOCATION);
+    std::fs::create_dir_all(&workspace_path)?;
+
+    let mut members = Vec::new();
+    for module in modules {
+        let crate_path = &module.fragment.directory.base_path;
+        let package_name = crate_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let harness_name = format!("{}_native", package_name);
+        let harness_path = workspace_path.join(&harness_name);
+        std::fs::create_dir_all(harness_path.join("src"))?;
+        std::fs::write(
+            harness_path.join("Cargo.toml"),
+            generate_cargo_toml(&harness_name, &package_name, crate_path),
+        )?;
+        std::fs::write(
+            harness_path.join("src").join("main.rs"),
+            generate_main(&package_name.replace('-', "_"), module),
+        )?;
+        members.push(harness_name);
+    }
+
+    let mut workspace = toml::Table::new();
+    workspace.insert("members".to_string(), members.clone().into());
+    workspace.insert("resolver".to_string(), "2".into());
+    let mut workspace_cargo_toml = toml::Table::new();
+    workspace_cargo_toml.insert("workspace".to_string(), toml::Value::Table(workspace));
+    std::fs::write(
+        workspace_path.join("Cargo.toml"),
+        workspace_cargo_toml.to_string(),
+    )?;
+    let fragments_lockfile = PathBuf::from(&config.project)
+        .join(FRAGMENTS_LOCATION)
+        .join("Cargo.lock");
+    if fragments_lockfile.exists() {
+        std::fs::copy(&fragments_lockfile, workspace_path.join("Cargo.lock"))?;
+    }
+
+    let mut args = vec!["build", "--workspace"];
+    let profile = match config.release_mode.unwrap_or(false) {
+        true => {
+            args.push("--release");
+            "release"
+        }
+        false => "debug",
+    };
+    if config.offline.unwrap_or(false) {
+        args.push("--offline");
+    }
+    let max_thread_pool = config.max_thread_pool.map(|jobs| jobs.to_string());
+    if let Some(max_thread_pool) = &max_thread_pool {
+        args.extend(["--jobs", max_thread_pool]);
+    }
+    info!("Building native harnesses: {:?}", &workspace_path);
+    fragment_compiler::run_command("cargo", args, &workspace_path, Vec::new())?;
+    Ok(members
+        .iter()
+        .map(|member| workspace_path.join("target").join(profile).join(member))
+        .collect())
+}
+
+/// Calls an export of a harness with the given parameters. Every call runs in a process of its own,
+/// since a panic unwinding out of an export aborts the process.
+pub fn execute(
+    binary: &Path,
+    export_name: &str,
+    parameters: &[Value],
+) -> Result<Outcome, ApplicationError> {
+    let mut child = Command::new(binary)
+        .arg(export_name)
+        .env("RUST_BACKTRACE", "0")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(serde_json::to_string(parameters)?.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Ok(Outcome::Failed {
+            code: "Panic".to_string(),
+            message: format!(
+                "{} ({})",
+                String::from_utf8_lossy(&output.stderr).trim(),
+                output.status
+            ),
+        });
+    }
+    let response: Value = serde_json::from_slice(&output.stdout)?;
+    Ok(match response["status"].as_u64() {
+        Some(0) => Outcome::Returned(response["payload"].clone()),
+        _ => Outcome::Failed {
+            code: response["payload"]["code"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            message: response["payload"]["message"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        },
+    })
+}
+
+fn generate_cargo_toml(harness_name: &str, package_name: &str, crate_path: &Path) -> String {
+    let mut package = toml::Table::new();
+    package.insert("name".to_string(), harness_name.into());
+    package.insert("version".to_string(), "0.1.0".into());
+    package.insert("edition".to_string(), "2021".into());
+    package.insert("publish".to_string(), false.into());
+
+    let mut fragment_crate = toml::Table::new();
+    fragment_crate.insert(
+        "path".to_string(),
+        crate_path.to_string_lossy().to_string().into(),
+    );
+    let mut dependencies = toml::Table::new();
+    dependencies.insert(package_name.to_string(), toml::Value::Table(fragment_crate));
+    for (name, version) in DEFAULT_CRATES
+        .iter()
+        .filter(|(name, _)| ["rmp-serde", "serde_json"].contains(name))
+    {
+        dependencies.insert(name.to_string(), version.to_string().into());
+    }
+
+    let mut cargo_toml = toml::Table::new();
+    cargo_toml.insert("package".to_string(), toml::Value::Table(package));
+    cargo_toml.insert("dependencies".to_string(), toml::Value::Table(dependencies));
+    cargo_toml.to_string()
+}
+
+fn generate_main(crate_name: &str, module: &VerifiedModule) -> String {
+    let exports: Vec<&str> = module
+        .functions
+        .iter()
+        .map(|function| function.export_name.as_str())
+        .collect();
+    let declarations: String = exports
+        .iter()
+        .enumerate()
+        .map(|(index, export)| {
+            format!(
+                "    #[link_name = \"{}\"]\n    fn dcm_export_{}(params_ptr: *const u8, parameter_count: usize) -> *const u8;\n",
+                export, index
+            )
+        })
+        .collect();
+    let match_arms: String = exports
+        .iter()
+        .enumerate()
+        .map(|(index, export)| format!("        \"{}\" => dcm_export_{},\n", export, index))
+        .collect();
+    HARNESS_MAIN
+        .replace("{crate_name}", crate_name)
+        .replace("{declarations}", &declarations)
+        .replace("{match_arms}", &match_arms)
+}
+
+// The exports are declared by their symbol names, which also covers the prefixed exports of bundles
+const HARNESS_MAIN: &str = r#"extern crate {crate_name};
+
+use std::io::Read;
+
+extern "C" {
+    fn alloc(size: usize) -> *mut u8;
+    fn dealloc(ptr: *mut u8, size: usize);
+    fn dcm_free_result(ptr: *mut u8);
+{declarations}}
+
+fn main() {
+    let export_name = std::env::args().nth(1).expect("Missing export name");
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .expect("Failed to read parameters");
+    let parameters: Vec<serde_json::Value> =
+        serde_json::from_str(&input).expect("Invalid parameters");
+    let export: unsafe extern "C" fn(*const u8, usize) -> *const u8 = match export_name.as_str() {
+{match_arms}        _ => panic!("Unknown export {}", export_name),
+    };
+
+    let mut args: Vec<u8> = Vec::new();
+    for parameter in &parameters {
+        let bytes = rmp_serde::to_vec_named(parameter).expect("Failed to encode parameter");
+        while args.len() % 4 != 0 {
+            args.push(0);
+        }
+        args.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        args.extend(bytes);
+    }
+    unsafe {
+        let params_ptr = match args.len() {
+            0 => std::ptr::null_mut(),
+            length => {
+                let ptr = alloc(length);
+                std::ptr::copy_nonoverlapping(args.as_ptr(), ptr, length);
+                ptr
+            }
+        };
+        let response = export(params_ptr, parameters.len());
+        if !args.is_empty() {
+            dealloc(params_ptr, args.len());
+        }
+        let header = std::slice::from_raw_parts(response, 8);
+        let payload_length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let payload = std::slice::from_raw_parts(response.add(8), payload_length);
+        let payload: serde_json::Value =
+            rmp_serde::from_slice(payload).expect("Invalid response payload");
+        println!("{}", serde_json::json!({ "status": header[1], "payload": payload }));
+        dcm_free_result(response as *mut u8);
+    }
+}
+"#;
@@ -40,10 +40,10 @@ impl Client {
         &mut self,
         update_fragments_data: Vec<UpdateFragmentData>,
     ) -> Result<(), ApplicationError> {
-        self.fragment_registry
-            .update_fragments(&update_fragments_data)
-            .ok();
-        self.send_message(update_fragments_data, Events::UpdateFragments)
+        let applied_updates = self
+            .fragment_registry
+            .update_fragments(&update_fragments_data);
+        self.send_message(applied_updates, Events::UpdateFragments)
             .await?;
         Ok(())
     }
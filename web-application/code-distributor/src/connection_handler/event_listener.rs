@@ -11,6 +11,7 @@ use warp::ws::{Message as WsMessage, WebSocket};
 use crate::connection_handler::message::{Events, Message};
 use crate::fragment_executor::FragmentExecutor;
 use crate::fragment_registry::fragment::ExecutionLocation;
+use crate::util::error::ApplicationError;
 
 pub(crate) async fn handle_events(
     mut rx: SplitStream<WebSocket>,
@@ -57,17 +58,33 @@ async fn handle_execute_function_event(
                     &execute_function_data.parameters,
                 )
                 .await;
-            if let Ok(result) = result {
-                let message = Message::new(message.message_id, Events::FunctionResult, result);
-                let json_string = serde_json::to_string(&message)
-                    .unwrap_or("Unable to serialize the result".to_string());
-                {
-                    tx.lock()
-                        .await
-                        .send(WsMessage::text(json_string))
-                        .await
-                        .ok();
+            let json_string = match result {
+                Ok(result) => {
+                    let message = Message::new(message.message_id, Events::FunctionResult, result);
+                    serde_json::to_string(&message)
                 }
+                Err(e) => {
+                    log::error!(
+                        "Failed to execute {} of fragment {}: {}",
+                        execute_function_data.function_name,
+                        execute_function_data.fragment_id,
+                        e
+                    );
+                    let message = Message::new(
+                        message.message_id,
+                        Events::FunctionError,
+                        FunctionErrorData::from(e),
+                    );
+                    serde_json::to_string(&message)
+                }
+            }
+            .unwrap_or("Unable to serialize the result".to_string());
+            {
+                tx.lock()
+                    .await
+                    .send(WsMessage::text(json_string))
+                    .await
+                    .ok();
             }
         }
         Err(e) => {
@@ -83,6 +100,42 @@ pub(crate) struct ExecuteFunctionData {
     parameters: Vec<Value>,
 }
 
+/// Reported to the client instead of a result when a function could not be executed.
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct FunctionErrorData {
+    code: String,
+    message: String,
+    /// The backtrace of a trapped fragment, pointing at the project sources if the fragment was built with debug information
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    backtrace: Option<String>,
+}
+
+impl From<ApplicationError> for FunctionErrorData {
+    fn from(error: ApplicationError) -> Self {
+        match error {
+            ApplicationError::FragmentError { code, message } => Self {
+                code,
+                message,
+                backtrace: None,
+            },
+            ApplicationError::WasmtimeError(trap) => Self {
+                code: "Trap".to_string(),
+                message: format!("{:#}", trap),
+                backtrace: None,
+            },
+            ApplicationError::FragmentTrap { error, backtrace } => Self {
+                backtrace: Some(backtrace),
+                ..Self::from(*error)
+            },
+            error => Self {
+                code: "ExecutionFailed".to_string(),
+                message: error.to_string(),
+                backtrace: None,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub(crate) struct UpdateFragmentData {
     pub id: String,
@@ -22,6 +22,7 @@ impl<T> Message<T> {
 pub(crate) enum Events {
     ExecuteFunction,
     FunctionResult,
+    FunctionError,
     UpdateFragments,
 }
 
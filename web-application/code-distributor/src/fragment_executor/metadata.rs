@@ -0,0 +1,169 @@
+use crate::util::error::ApplicationError;
+use serde_derive::Deserialize;
+use sha2::{Digest, Sha256};
+use wasm_encoder::{RawSection, Section};
+use wasmparser::{Parser, Payload};
+
+/// The name of the custom section in which the wasm-generator embeds the build metadata of a module.
+const METADATA_SECTION_NAME: &str = "dcm.meta";
+/// The newest version of the metadata format, see "Build Metadata" in the wasm-generator readme.
+const SUPPORTED_METADATA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ModuleMetadata {
+    pub version: u32,
+    pub fragments: Vec<FragmentSource>,
+    pub content_hash: String,
+    pub git_revision: Option<String>,
+    pub generator_version: String,
+    pub target: String,
+    pub release_mode: bool,
+    pub optimize_wasm: bool,
+    pub debug_info: bool,
+    pub abi_version: u32,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct FragmentSource {
+    pub id: String,
+    pub file: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl ModuleMetadata {
+    /// Reads the metadata of a module and checks that it describes the module, returning `None` for modules without it.
+    pub fn read(module: &[u8]) -> Result<Option<Self>, ApplicationError> {
+        let (stripped, Some(section)) = strip(module)? else {
+            return Ok(None);
+        };
+        let metadata: Self = serde_json::from_slice(section).map_err(|e| {
+            invalid_module(format!("Invalid {} section: {}", METADATA_SECTION_NAME, e))
+        })?;
+        if metadata.version > SUPPORTED_METADATA_VERSION {
+            return Err(invalid_module(format!(
+                "Metadata version {} is not supported, the newest supported version is {}",
+                metadata.version, SUPPORTED_METADATA_VERSION
+            )));
+        }
+
+        // The hash covers the module as it was before the metadata was appended
+        let content_hash: String = Sha256::digest(stripped)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        if content_hash != metadata.content_hash {
+            return Err(invalid_module(format!(
+                "Module content hash {} does not match the hash {} recorded at build time",
+                content_hash, metadata.content_hash
+            )));
+        }
+        Ok(Some(metadata))
+    }
+
+    /// Checks that the module can execute the given fragment.
+    pub fn validate(
+        &self,
+        fragment_id: &str,
+        supported_abi_version: u32,
+    ) -> Result<(), ApplicationError> {
+        if self.abi_version > supported_abi_version {
+            return Err(invalid_module(format!(
+                "Fragment {} was built for ABI version {}, the newest supported version is {}",
+                fragment_id, self.abi_version, supported_abi_version
+            )));
+        }
+        if !self
+            .fragments
+            .iter()
+            .any(|fragment| fragment.id == fragment_id)
+        {
+            return Err(invalid_module(format!(
+                "Module does not contain fragment {}, it contains {}",
+                fragment_id,
+                self.fragments
+                    .iter()
+                    .map(|fragment| fragment.id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn invalid_module(message: String) -> ApplicationError {
+    ApplicationError::WasmError { message }
+}
+
+/// Returns the module without its `dcm.meta` section, and the payload of the section.
+/// The other sections are copied with their content, the same way the wasm-generator strips modules before hashing them.
+fn strip(module: &[u8]) -> Result<(Vec<u8>, Option<&[u8]>), ApplicationError> {
+    let mut stripped = Vec::new();
+    let mut metadata = None;
+    for payload in Parser::new(0).parse_all(module) {
+        match payload.map_err(|e| invalid_module(format!("Invalid wasm module: {}", e)))? {
+            Payload::Version { range, .. } => stripped.extend_from_slice(&module[range]),
+            Payload::CustomSection(reader) if reader.name() == METADATA_SECTION_NAME => {
+                metadata = Some(reader.data());
+            }
+            payload => {
+                if let Some((id, range)) = payload.as_section() {
+                    RawSection {
+                        id,
+                        data: &module[range],
+                    }
+                    .append_to(&mut stripped);
+                }
+            }
+        }
+    }
+    Ok((stripped, metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+    use wasm_encoder::CustomSection;
+
+    fn append_metadata(module: &[u8], metadata: &str) -> Vec<u8> {
+        let mut module = module.to_vec();
+        CustomSection {
+            name: Cow::Borrowed(METADATA_SECTION_NAME),
+            data: Cow::Borrowed(metadata.as_bytes()),
+        }
+        .append_to(&mut module);
+        module
+    }
+
+    #[test]
+    fn test_read_metadata() {
+        let module = wat::parse_str("(module)").unwrap();
+        assert_eq!(ModuleMetadata::read(&module).unwrap(), None);
+
+        let content_hash: String = Sha256::digest(&module)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        let metadata_json = |content_hash: &str| {
+            format!(
+                r#"{{"version":1,"fragments":[{{"id":"checkout","file":"src/shop.rs","start_line":12,"end_line":20}}],
+                "content_hash":"{}","git_revision":null,"generator_version":"0.1.0","target":"wasm32-unknown-unknown",
+                "release_mode":true,"optimize_wasm":false,"debug_info":false,"abi_version":3}}"#,
+                content_hash
+            )
+        };
+        let metadata =
+            ModuleMetadata::read(&append_metadata(&module, &metadata_json(&content_hash)))
+                .unwrap()
+                .unwrap();
+        assert_eq!(metadata.fragments[0].file, "src/shop.rs");
+        assert!(metadata.validate("checkout", 3).is_ok());
+        assert!(metadata.validate("refund", 3).is_err());
+        assert!(metadata.validate("checkout", 2).is_err());
+
+        let tampered = append_metadata(&module, &metadata_json(&"0".repeat(64)));
+        assert!(ModuleMetadata::read(&tampered).is_err());
+    }
+}
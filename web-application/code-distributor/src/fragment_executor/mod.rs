@@ -1,6 +1,8 @@
 use crate::util::error::ApplicationError;
 use async_trait::async_trait;
 
+pub mod metadata;
+pub mod source_map;
 pub mod wasmtime;
 
 #[async_trait]
@@ -0,0 +1,171 @@
+use serde_derive::Deserialize;
+use std::fmt::Write;
+use std::path::Path;
+use wasmtime::WasmBacktrace;
+
+/// The newest version of the source maps written by the wasm-generator, see `debug_info` in its readme.
+const SUPPORTED_SOURCE_MAP_VERSION: u32 = 1;
+
+/// Maps the lines of the generated `lib.rs` of a module to the project files its items were copied from.
+#[derive(Debug, Deserialize)]
+pub struct SourceMap {
+    version: u32,
+    /// The generated file, relative to the fragments workspace the module was built in
+    generated_file: String,
+    mappings: Vec<SourceMapping>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourceMapping {
+    generated_start_line: usize,
+    generated_end_line: usize,
+    file: String,
+    start_line: usize,
+    item: String,
+}
+
+/// A frame of a trapped fragment, as described by the debug information of the module.
+struct Frame<'a> {
+    function: Option<&'a str>,
+    file: Option<&'a str>,
+    line: Option<u32>,
+}
+
+impl SourceMap {
+    /// Loads the source map deployed next to a module as `<module>.map.json`, if there is one.
+    pub fn load(module_path: &Path) -> Option<Self> {
+        let source_map_path = module_path.with_extension("map.json");
+        let content = std::fs::read_to_string(source_map_path).ok()?;
+        let source_map: Self = match serde_json::from_str(&content) {
+            Ok(source_map) => source_map,
+            Err(e) => {
+                log::warn!("Ignoring invalid source map of {:?}: {}", module_path, e);
+                return None;
+            }
+        };
+        if source_map.version > SUPPORTED_SOURCE_MAP_VERSION {
+            log::warn!(
+                "Ignoring source map of {:?} with unsupported version {}",
+                module_path,
+                source_map.version
+            );
+            return None;
+        }
+        Some(source_map)
+    }
+
+    /// Returns the item, project file and line a line of the generated file belongs to.
+    /// Lines within an item are assumed to keep their distance to its start, since its body is copied as it is.
+    fn resolve(&self, file: &str, line: usize) -> Option<(&str, &str, usize)> {
+        if !file.replace('\\', "/").ends_with(&self.generated_file) {
+            return None;
+        }
+        self.mappings
+            .iter()
+            .find(|mapping| {
+                (mapping.generated_start_line..=mapping.generated_end_line).contains(&line)
+            })
+            .map(|mapping| {
+                (
+                    mapping.item.as_str(),
+                    mapping.file.as_str(),
+                    mapping.start_line + line - mapping.generated_start_line,
+                )
+            })
+    }
+
+    /// Formats the frames of a trap like a Rust backtrace, pointing at the project sources wherever they are known.
+    pub fn symbolicate(&self, backtrace: &WasmBacktrace) -> String {
+        let frames = backtrace.frames().iter().flat_map(|frame| {
+            let symbols: Vec<Frame> = match frame.symbols().is_empty() {
+                true => vec![Frame {
+                    function: frame.func_name(),
+                    file: None,
+                    line: None,
+                }],
+                // Inlined functions are reported as additional symbols of the same frame
+                false => frame
+                    .symbols()
+                    .iter()
+                    .map(|symbol| Frame {
+                        function: symbol.name().or(frame.func_name()),
+                        file: symbol.file(),
+                        line: symbol.line(),
+                    })
+                    .collect(),
+            };
+            symbols
+        });
+        self.format_frames(frames)
+    }
+
+    fn format_frames<'a>(&self, frames: impl Iterator<Item = Frame<'a>>) -> String {
+        let mut backtrace = String::new();
+        for (index, frame) in frames.enumerate() {
+            let resolved = frame
+                .file
+                .zip(frame.line)
+                .and_then(|(file, line)| self.resolve(file, line as usize));
+            let (function, location) = match resolved {
+                Some((item, file, line)) => (item, Some(format!("{}:{}", file, line))),
+                None => (
+                    frame.function.unwrap_or("<unknown>"),
+                    frame
+                        .file
+                        .zip(frame.line)
+                        .map(|(file, line)| format!("{}:{}", file, line)),
+                ),
+            };
+            let _ = writeln!(backtrace, "{:>4}: {}", index, function);
+            if let Some(location) = location {
+                let _ = writeln!(backtrace, "             at {}", location);
+            }
+        }
+        backtrace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_frames() {
+        let source_map: SourceMap = serde_json::from_str(
+            r#"{
+                "version": 1,
+                "generated_file": "fragment_1/src/lib.rs",
+                "mappings": [
+                    {"generated_start_line": 40, "generated_end_line": 45, "file": "src/shop.rs", "start_line": 12, "item": "shop::checkout"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let frames = vec![
+            Frame {
+                function: Some("core::panicking::panic"),
+                file: Some("/rustc/library/core/src/panicking.rs"),
+                line: Some(144),
+            },
+            Frame {
+                function: Some("fragment_1::shop::checkout"),
+                file: Some("/project/temp/fragments/fragment_1/src/lib.rs"),
+                line: Some(43),
+            },
+            Frame {
+                function: None,
+                file: None,
+                line: None,
+            },
+        ];
+        assert_eq!(
+            source_map.format_frames(frames.into_iter()),
+            "   0: core::panicking::panic
+             at /rustc/library/core/src/panicking.rs:144
+   1: shop::checkout
+             at src/shop.rs:15
+   2: <unknown>
+"
+        );
+    }
+}
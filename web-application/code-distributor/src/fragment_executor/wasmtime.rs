@@ -1,19 +1,48 @@
+use crate::fragment_executor::metadata::ModuleMetadata;
+use crate::fragment_executor::source_map::SourceMap;
 use crate::fragment_executor::FragmentExecutor;
 use crate::fragment_registry::FragmentRegistry;
 use crate::util::error::ApplicationError;
 use async_trait::async_trait;
+use serde_derive::Deserialize;
 use std::collections::HashMap;
-use std::path::PathBuf;
-use wasmtime::{Engine, Instance, Module, Store};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use wasmtime::{
+    Config, Engine, Instance, Memory, Module, Store, WasmBacktrace, WasmBacktraceDetails,
+};
 
+#[derive(Clone)]
 pub struct ModuleInfo {
     pub module: Module,
     pub engine: Engine,
+    /// The prefix of the exports of the fragment, fragments built into a bundle export their functions as `<id>/execute__<name>`
+    pub export_prefix: String,
+    /// The source map deployed with the module, used to resolve traps to the project sources
+    pub source_map: Option<Arc<SourceMap>>,
+    /// The build metadata embedded in the module, missing in modules built before it was introduced
+    pub metadata: Option<Arc<ModuleMetadata>>,
 }
 
 impl ModuleInfo {
     pub fn new(module: Module, engine: Engine) -> Self {
-        Self { module, engine }
+        Self {
+            module,
+            engine,
+            export_prefix: String::new(),
+            source_map: None,
+            metadata: None,
+        }
+    }
+
+    pub fn with_export_prefix(mut self, export_prefix: String) -> Self {
+        self.export_prefix = export_prefix;
+        self
+    }
+
+    pub fn with_source_map(mut self, source_map: SourceMap) -> Self {
+        self.source_map = Some(Arc::new(source_map));
+        self
     }
 }
 
@@ -22,19 +51,97 @@ pub(crate) struct Wasmtime {
 }
 
 impl Wasmtime {
+    /// Compiles the modules of all fragments, the module of a bundle is compiled once and shared by all its fragments.
+    /// Fragments whose module cannot be loaded or fails validation are logged and left out, so that calls to them fail
+    /// without affecting the other fragments.
     pub(crate) fn new(fragment_registry: &FragmentRegistry, fragments_dir: String) -> Self {
         let mut fragments: HashMap<String, ModuleInfo> = HashMap::new();
+        // The errors are kept as messages, since they are reported once per fragment of the bundle
+        let mut bundles: HashMap<String, Result<ModuleInfo, String>> = HashMap::new();
         for fragment in fragment_registry.fragments.iter() {
-            let fragment_path = PathBuf::from(&fragments_dir).join(format!("{}.wasm", fragment.id));
-            let engine = Engine::default();
-            let module = Module::from_file(&engine, fragment_path).unwrap();
-            fragments.insert(fragment.id.clone(), ModuleInfo::new(module, engine));
+            let module_info = match &fragment.bundle {
+                Some(bundle) => bundles
+                    .entry(bundle.clone())
+                    .or_insert_with(|| {
+                        load_module(&PathBuf::from(&fragments_dir).join(bundle))
+                            .map_err(|e| e.to_string())
+                    })
+                    .clone()
+                    .map(|module_info| module_info.with_export_prefix(format!("{}/", fragment.id))),
+                None => load_module(
+                    &PathBuf::from(&fragments_dir).join(format!("{}.wasm", fragment.id)),
+                )
+                .map_err(|e| e.to_string()),
+            };
+            let module_info = module_info.and_then(|module_info| match &module_info.metadata {
+                Some(metadata) => metadata
+                    .validate(&fragment.id, SUPPORTED_ABI_VERSION)
+                    .map(|_| module_info)
+                    .map_err(|e| e.to_string()),
+                None => Ok(module_info),
+            });
+            match module_info {
+                Ok(module_info) => {
+                    fragments.insert(fragment.id.clone(), module_info);
+                }
+                Err(e) => log::error!("Refusing to load fragment {}: {}", fragment.id, e),
+            }
         }
         Self { fragments }
     }
 }
 
-const WASM_PAGE_SIZE: usize = 64 * 1024;
+/// Modules deployed with a source map were built with debug information, which the engine parses to resolve trapping frames.
+pub fn load_module(module_path: &Path) -> Result<ModuleInfo, ApplicationError> {
+    let bytes = std::fs::read(module_path).map_err(|e| ApplicationError::WasmError {
+        message: format!("Unable to read module {:?}: {}", module_path, e),
+    })?;
+    let metadata = ModuleMetadata::read(&bytes).map_err(|e| ApplicationError::WasmError {
+        message: format!("Invalid build metadata in module {:?}: {}", module_path, e),
+    })?;
+    match &metadata {
+        Some(metadata) => log::info!(
+            "Loading module {:?} built from revision {} by wasm-generator {} in {} mode",
+            module_path,
+            metadata.git_revision.as_deref().unwrap_or("unknown"),
+            metadata.generator_version,
+            if metadata.release_mode {
+                "release"
+            } else {
+                "debug"
+            }
+        ),
+        None => log::info!("Loading module {:?} without build metadata", module_path),
+    }
+    let source_map = SourceMap::load(module_path);
+    let engine = match source_map {
+        Some(_) => {
+            let mut config = Config::new();
+            config.wasm_backtrace_details(WasmBacktraceDetails::Enable);
+            Engine::new(&config)?
+        }
+        None => Engine::default(),
+    };
+    let module = Module::new(&engine, bytes).map_err(|e| ApplicationError::WasmError {
+        message: format!("Unable to compile module {:?}: {:#}", module_path, e),
+    })?;
+    let mut module_info = ModuleInfo::new(module, engine);
+    module_info.source_map = source_map.map(Arc::new);
+    module_info.metadata = metadata.map(Arc::new);
+    Ok(module_info)
+}
+
+/// The newest version of the fragment ABI supported by this executor, see "Fragment ABI" in the wasm-generator readme.
+const SUPPORTED_ABI_VERSION: u32 = 3;
+/// The first ABI version in which fragments export `dcm_free_result` to release their result buffers.
+const FREE_RESULT_ABI_VERSION: u32 = 2;
+/// The first ABI version in which fragments return error envelopes and export `dcm_take_panic`.
+const ERROR_ENVELOPE_ABI_VERSION: u32 = 3;
+/// Fragments generated before the ABI was versioned do not export `dcm_abi_version`,
+/// and return a length prefixed MessagePack string containing the JSON encoded result.
+const LEGACY_ABI_VERSION: u32 = 0;
+const RESPONSE_HEADER_SIZE: usize = 8;
+const STATUS_OK: u8 = 0;
 
 #[async_trait]
 impl FragmentExecutor for Wasmtime {
@@ -44,8 +151,14 @@ impl FragmentExecutor for Wasmtime {
         function_name: &str,
         params: &[serde_json::Value],
     ) -> Result<String, ApplicationError> {
-        let module_info = self.fragments.get(fragment_id).unwrap();
-        let function_name = format!("execute__{}", function_name);
+        let module_info =
+            self.fragments
+                .get(fragment_id)
+                .ok_or_else(|| ApplicationError::FragmentError {
+                    code: "UnknownFragment".to_string(),
+                    message: format!("Fragment {} is not registered", fragment_id),
+                })?;
+        let function_name = format!("{}execute__{}", module_info.export_prefix, function_name);
         execute(module_info, function_name, params)
     }
 }
@@ -57,6 +170,7 @@ pub fn execute(
 ) -> Result<String, ApplicationError> {
     let mut store = Store::new(&module_info.engine, ());
     let instance = Instance::new(&mut store, &module_info.module, &[]).unwrap();
+    let abi_version = get_abi_version(&instance, &mut store)?;
     let func = instance.get_typed_func::<(i32, i32), i32>(&mut store, &function_name)?;
     let memory = instance.get_memory(&mut store, "memory").unwrap();
 
@@ -65,7 +179,8 @@ pub fn execute(
         let param_as_bytes = rmp_serde::to_vec_named(param)?; // Serialize the parameter to MessagePack format
         let bytes_len = (param_as_bytes.len() as u32).to_le_bytes();
 
-        // Pad args to the next 4-byte boundary
+        // Pad args to the next 4-byte boundary, `is_multiple_of` is not available on the toolchain of the Dockerfile
+        #[allow(clippy::manual_is_multiple_of)]
         while args.len() % 4 != 0 {
             args.push(0);
         }
@@ -74,33 +189,165 @@ pub fn execute(
         args.extend(&param_as_bytes); // Extend args with the parameter bytes
     }
 
-    // grow memory if needed
+    // Allocate memory in WebAssembly and get the pointer, the module's allocator grows the memory if needed.
+    // Nothing is allocated for functions without parameters, since zero sized allocations are not allowed.
     let total_length = args.len();
-    let required_pages = (total_length + WASM_PAGE_SIZE - 1) / WASM_PAGE_SIZE;
-    memory.grow(&mut store, required_pages as u64)?;
-
-    // Allocate memory in WebAssembly and get the pointer
     let alloc_func = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
-    let ptr = alloc_func.call(&mut store, total_length as i32)?;
+    let dealloc_func = instance.get_typed_func::<(i32, i32), ()>(&mut store, "dealloc")?;
+    let ptr = match total_length {
+        0 => 0,
+        _ => {
+            let ptr = alloc_func.call(&mut store, total_length as i32)?;
+            if ptr == 0 {
+                return Err(ApplicationError::WasmError {
+                    message: format!("Fragment failed to allocate {} bytes", total_length),
+                });
+            }
+            ptr
+        }
+    };
 
-    // Get the memory view, write bytes to memory, and call the function
-    let view = memory;
-    view.write(&mut store, ptr as usize, &args)?;
-    let pointer = func.call(&mut store, (ptr, params.len() as i32))?;
+    // Write bytes to memory, call the function and release the parameters
+    memory.write(&mut store, ptr as u32 as usize, &args)?;
+    let pointer = match func.call(&mut store, (ptr, params.len() as i32)) {
+        Ok(pointer) => pointer,
+        Err(trap) => {
+            let backtrace = module_info.source_map.as_ref().and_then(|source_map| {
+                trap.downcast_ref::<WasmBacktrace>()
+                    .map(|backtrace| source_map.symbolicate(backtrace))
+            });
+            let error = take_panic(&instance, &mut store, &memory, abi_version)
+                .unwrap_or(ApplicationError::WasmtimeError(trap));
+            return Err(match backtrace {
+                Some(backtrace) => ApplicationError::FragmentTrap {
+                    error: Box::new(error),
+                    backtrace,
+                },
+                None => error,
+            });
+        }
+    };
+    if total_length > 0 {
+        dealloc_func.call(&mut store, (ptr, total_length as i32))?;
+    }
 
     let data = memory.data(&store);
-    let output_len = {
-        let bytes = read(data, pointer as usize, 4);
-        u32::from_le_bytes(bytes.as_slice().try_into().unwrap())
+    if abi_version == LEGACY_ABI_VERSION {
+        let output_len = {
+            let bytes = read(data, pointer as u32 as usize, 4)?;
+            u32::from_le_bytes(bytes.try_into().unwrap())
+        };
+        let output_bytes = read(data, pointer as u32 as usize + 4, output_len as usize)?;
+        // Deserialize the output bytes to a string
+        let output_str: String = rmp_serde::from_slice(output_bytes)?;
+        return Ok(output_str);
+    }
+    let output = decode_response(data, pointer as u32 as usize, abi_version);
+    if abi_version >= FREE_RESULT_ABI_VERSION {
+        free_result(&instance, &mut store, pointer)?;
+    }
+    output
+}
+
+fn free_result(
+    instance: &Instance,
+    store: &mut Store<()>,
+    pointer: i32,
+) -> Result<(), ApplicationError> {
+    let free_result_func = instance.get_typed_func::<i32, ()>(&mut *store, "dcm_free_result")?;
+    free_result_func.call(&mut *store, pointer)?;
+    Ok(())
+}
+
+/// Returns the error describing the panic that made the fragment trap, if the fragment captured one.
+/// The instance remains usable after a trap, so the captured message can still be retrieved.
+fn take_panic(
+    instance: &Instance,
+    store: &mut Store<()>,
+    memory: &Memory,
+    abi_version: u32,
+) -> Option<ApplicationError> {
+    if abi_version < ERROR_ENVELOPE_ABI_VERSION {
+        return None;
+    }
+    let take_panic_func = instance
+        .get_typed_func::<(), i32>(&mut *store, "dcm_take_panic")
+        .ok()?;
+    let pointer = take_panic_func.call(&mut *store, ()).ok()?;
+    if pointer == 0 {
+        return None;
+    }
+    let output = decode_response(memory.data(&*store), pointer as u32 as usize, abi_version);
+    free_result(instance, store, pointer).ok()?;
+    output.err()
+}
+
+/// Returns the ABI version the fragment was generated with, failing if it is newer than the supported one.
+fn get_abi_version(instance: &Instance, store: &mut Store<()>) -> Result<u32, ApplicationError> {
+    let abi_version = match instance.get_typed_func::<(), i32>(&mut *store, "dcm_abi_version") {
+        Ok(version_func) => version_func.call(&mut *store, ())? as u32,
+        Err(_) => LEGACY_ABI_VERSION,
     };
-    let output_bytes = read(data, (pointer as usize) + 4, output_len as usize);
-    // Deserialize the output bytes to a string
-    let output_str: String = rmp_serde::from_slice(output_bytes.as_slice())?;
-    Ok(output_str)
+    if abi_version > SUPPORTED_ABI_VERSION {
+        return Err(ApplicationError::WasmError {
+            message: format!(
+                "Fragment ABI version {} is not supported, the newest supported version is {}",
+                abi_version, SUPPORTED_ABI_VERSION
+            ),
+        });
+    }
+    Ok(abi_version)
+}
+
+/// The payload of a response with an error status.
+#[derive(Deserialize)]
+struct FragmentErrorPayload {
+    code: String,
+    message: String,
+}
+
+/// Decodes a response consisting of an 8 byte header, holding the ABI version, the status and the payload length,
+/// followed by the MessagePack payload. The payload is returned as JSON for the clients.
+fn decode_response(
+    data: &[u8],
+    pointer: usize,
+    abi_version: u32,
+) -> Result<String, ApplicationError> {
+    let header = read(data, pointer, RESPONSE_HEADER_SIZE)?;
+    if header[0] as u32 != abi_version {
+        return Err(ApplicationError::WasmError {
+            message: format!(
+                "Fragment response has ABI version {}, expected {}",
+                header[0], abi_version
+            ),
+        });
+    }
+    let payload_len = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    let payload = read(data, pointer + RESPONSE_HEADER_SIZE, payload_len as usize)?;
+    if header[1] != STATUS_OK {
+        let error: FragmentErrorPayload = rmp_serde::from_slice(payload)?;
+        return Err(ApplicationError::FragmentError {
+            code: error.code,
+            message: error.message,
+        });
+    }
+    let value: serde_json::Value = rmp_serde::from_slice(payload)?;
+    Ok(value.to_string())
 }
 
-fn read(data: &[u8], offset: usize, length: usize) -> Vec<u8> {
-    data[offset..(offset + length)].to_vec()
+/// Returns the given range of the module's memory, failing if a pointer or length returned by the module is out of bounds.
+fn read(data: &[u8], offset: usize, length: usize) -> Result<&[u8], ApplicationError> {
+    offset
+        .checked_add(length)
+        .and_then(|end| data.get(offset..end))
+        .ok_or_else(|| ApplicationError::WasmError {
+            message: format!(
+                "Fragment returned a buffer of {} bytes at {} outside of its memory of {} bytes",
+                length,
+                offset,
+                data.len()
+            ),
+        })
 }
 
 #[cfg(test)]
@@ -120,6 +367,140 @@ mod tests {
         println!("Result: {}", result.unwrap());
     }
 
+    #[test]
+    fn test_new_skips_invalid_modules() {
+        let fragments_dir =
+            std::env::temp_dir().join(format!("dcm-fragments-{}", std::process::id()));
+        std::fs::create_dir_all(&fragments_dir).unwrap();
+        std::fs::copy(
+            PathBuf::from(FRAGMENT_PATH).join("fibonacci.wasm"),
+            fragments_dir.join("fibonacci.wasm"),
+        )
+        .unwrap();
+        std::fs::write(fragments_dir.join("broken.wasm"), b"not a module").unwrap();
+        let fragments = crate::fragment_registry::fragment::parse_manifest(
+            r#"[
+                {"id": "fibonacci", "execution_location": "Server"},
+                {"id": "broken", "execution_location": "Server"},
+                {"id": "missing", "execution_location": "Server"}
+            ]"#,
+        )
+        .unwrap();
+
+        let wasmtime = Wasmtime::new(
+            &FragmentRegistry::new(fragments),
+            fragments_dir.to_string_lossy().to_string(),
+        );
+        std::fs::remove_dir_all(&fragments_dir).unwrap();
+
+        let mut loaded_fragments = wasmtime.fragments.keys().collect::<Vec<&String>>();
+        loaded_fragments.sort();
+        assert_eq!(loaded_fragments, vec!["fibonacci"]);
+    }
+
+    #[test]
+    fn test_decode_response() {
+        #[derive(serde_derive::Serialize)]
+        struct Response {
+            result: u32,
+        }
+        let payload = rmp_serde::to_vec_named(&Response { result: 55 }).unwrap();
+        let mut data = vec![0u8; 4];
+        data.extend_from_slice(&[1, STATUS_OK, 0, 0]);
+        data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        data.extend(&payload);
+        assert_eq!(decode_response(&data, 4, 1).unwrap(), r#"{"result":55}"#);
+
+        data[5] = 1;
+        assert!(decode_response(&data, 4, 1).is_err());
+    }
+
+    #[test]
+    fn test_decode_error_response() {
+        #[derive(serde_derive::Serialize)]
+        struct Error {
+            code: String,
+            message: String,
+        }
+        let payload = rmp_serde::to_vec_named(&Error {
+            code: "InvalidParameterCount".to_string(),
+            message: "Expected 1 parameters, got 2".to_string(),
+        })
+        .unwrap();
+        let mut data = vec![3, 1, 0, 0];
+        data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        data.extend(&payload);
+        match decode_response(&data, 0, 3) {
+            Err(ApplicationError::FragmentError { code, message }) => {
+                assert_eq!(code, "InvalidParameterCount");
+                assert_eq!(message, "Expected 1 parameters, got 2");
+            }
+            other => panic!("Expected a fragment error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_response_out_of_bounds() {
+        let mut data = vec![1, STATUS_OK, 0, 0];
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decode_response(&data, 0, 1).is_err());
+        assert!(decode_response(&data, usize::MAX, 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_bundled_fragment() {
+        // A bundle whose fragment `math` returns a response holding the MessagePack map {"result": 1}
+        let bundle = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 16) "\03\00\00\00\09\00\00\00\81\a6result\01")
+                (func (export "dcm_abi_version") (result i32) i32.const 3)
+                (func (export "alloc") (param i32) (result i32) i32.const 64)
+                (func (export "dealloc") (param i32 i32))
+                (func (export "dcm_free_result") (param i32))
+                (func (export "math/execute__one") (param i32 i32) (result i32) i32.const 16))"#,
+        )
+        .unwrap();
+        let engine = Engine::default();
+        let module = Module::new(&engine, bundle).unwrap();
+        let module_info = ModuleInfo::new(module, engine).with_export_prefix("math/".to_string());
+        let wasmtime = Wasmtime {
+            fragments: HashMap::from([("math".to_string(), module_info)]),
+        };
+        let result = wasmtime.execute("math", "one", &[]).await.unwrap();
+        assert_eq!(result, r#"{"result":1}"#);
+        assert!(wasmtime.execute("other", "one", &[]).await.is_err());
+    }
+
+    #[test]
+    fn test_execute_trapping_fragment_with_source_map() {
+        let module = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "dcm_abi_version") (result i32) i32.const 3)
+                (func (export "alloc") (param i32) (result i32) i32.const 64)
+                (func (export "dealloc") (param i32 i32))
+                (func (export "dcm_take_panic") (result i32) i32.const 0)
+                (func $checkout (result i32) unreachable)
+                (func (export "execute__checkout") (param i32 i32) (result i32) call $checkout))"#,
+        )
+        .unwrap();
+        let source_map = serde_json::from_str(
+            r#"{"version": 1, "generated_file": "fragment_1/src/lib.rs", "mappings": []}"#,
+        )
+        .unwrap();
+        let engine = Engine::default();
+        let module = Module::new(&engine, module).unwrap();
+        let module_info = ModuleInfo::new(module, engine).with_source_map(source_map);
+        match execute(&module_info, "execute__checkout".to_string(), &[]) {
+            Err(ApplicationError::FragmentTrap { error, backtrace }) => {
+                assert!(matches!(*error, ApplicationError::WasmtimeError(_)));
+                assert!(backtrace.starts_with("   0: checkout\n"));
+            }
+            other => panic!("Expected a trap with a backtrace, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_execute_wasm_factorial() {
         let mut params: Vec<serde_json::Value> = Vec::new();
@@ -4,10 +4,97 @@ use serde_derive::{Deserialize, Serialize};
 pub(crate) struct Fragment {
     pub(crate) id: String,
     pub(crate) execution_location: ExecutionLocation,
+    /// Fragments keeping mutable global state behave differently on the client and the server, so they are never moved.
+    #[serde(default)]
+    pub(crate) has_mutable_global_state: bool,
+    /// The wasm module of the bundle the fragment is built into, fragments without a bundle are built into `<id>.wasm`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) bundle: Option<String>,
+    /// The locations the fragment may be moved to, listed by manifests since version 2
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) allowed_locations: Option<Vec<ExecutionLocation>>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+impl Fragment {
+    // `Option::is_none_or` is not available on the toolchain of the Dockerfile
+    #[allow(clippy::unnecessary_map_or)]
+    pub(crate) fn is_allowed_on(&self, execution_location: &ExecutionLocation) -> bool {
+        self.allowed_locations
+            .as_ref()
+            .map_or(true, |locations| locations.contains(execution_location))
+    }
+}
+
+/// The `executable_fragments.json` written by the wasm-generator, see "Fragment Manifest" in its readme.
+/// Versions before 2 consist of the list of fragments only.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum FragmentManifest {
+    Versioned {
+        version: u32,
+        fragments: Vec<Fragment>,
+    },
+    Legacy(Vec<Fragment>),
+}
+
+/// The newest manifest version, newer manifests are read as far as their fields are known.
+const SUPPORTED_MANIFEST_VERSION: u32 = 2;
+
+/// Parses the fragments listed in `executable_fragments.json`.
+pub(crate) fn parse_manifest(content: &str) -> Result<Vec<Fragment>, serde_json::Error> {
+    Ok(match serde_json::from_str(content)? {
+        FragmentManifest::Versioned { version, fragments } => {
+            if version > SUPPORTED_MANIFEST_VERSION {
+                log::warn!(
+                    "Fragment manifest version {} is newer than the supported version {}",
+                    version,
+                    SUPPORTED_MANIFEST_VERSION
+                );
+            }
+            fragments
+        }
+        FragmentManifest::Legacy(fragments) => fragments,
+    })
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub(crate) enum ExecutionLocation {
     Client,
     Server,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let legacy = parse_manifest(
+            r#"[{"id": "checkout", "execution_location": "Client", "has_mutable_global_state": false}]"#,
+        )
+        .unwrap();
+        assert_eq!(legacy[0].id, "checkout");
+        assert!(legacy[0].is_allowed_on(&ExecutionLocation::Server));
+
+        let manifest = parse_manifest(
+            r#"{
+                "version": 2,
+                "generator_version": "0.1.0",
+                "abi_version": 3,
+                "fragments": [{
+                    "id": "checkout",
+                    "execution_location": "Client",
+                    "allowed_locations": ["Client"],
+                    "has_mutable_global_state": false,
+                    "module": {"file": "checkout.wasm", "hash": "", "size": 0, "target": "wasm32-unknown-unknown", "export_prefix": ""},
+                    "functions": [{"name": "checkout", "export_name": "execute__checkout", "parameters": [], "return_type": "u32"}],
+                    "crates": [],
+                    "source": {"id": "checkout", "file": "src/shop.rs", "start_line": 12, "end_line": 20}
+                }]
+            }"#,
+        )
+        .unwrap();
+        assert!(manifest[0].is_allowed_on(&ExecutionLocation::Client));
+        assert!(!manifest[0].is_allowed_on(&ExecutionLocation::Server));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::connection_handler::event_listener::UpdateFragmentData;
 use crate::fragment_registry::fragment::Fragment;
-use crate::util::error::ApplicationError;
+use log::warn;
 use serde_derive::Serialize;
 
 pub mod fragment;
@@ -15,20 +15,40 @@ impl FragmentRegistry {
         Self { fragments }
     }
 
+    /// Updates the execution location of the fragments and returns the updates that were applied.
+    /// Moving fragments that keep mutable global state is refused, since their behaviour depends on where they are executed.
+    /// So is moving fragments to locations their manifest entry does not allow.
     pub fn update_fragments(
         &mut self,
         update_fragments_data: &Vec<UpdateFragmentData>,
-    ) -> Result<(), ApplicationError> {
+    ) -> Vec<UpdateFragmentData> {
+        let mut applied_updates = Vec::new();
         for update_fragment_data in update_fragments_data {
-            if let Some(index) = self
+            if let Some(fragment) = self
                 .fragments
-                .iter()
-                .position(|f| f.id == update_fragment_data.id)
+                .iter_mut()
+                .find(|f| f.id == update_fragment_data.id)
             {
-                self.fragments[index].execution_location =
-                    update_fragment_data.execution_location.clone();
+                if fragment.has_mutable_global_state
+                    && fragment.execution_location != update_fragment_data.execution_location
+                {
+                    warn!(
+                        "Refusing to move fragment {} since it keeps mutable global state",
+                        fragment.id
+                    );
+                    continue;
+                }
+                if !fragment.is_allowed_on(&update_fragment_data.execution_location) {
+                    warn!(
+                        "Refusing to move fragment {} to {:?} since its code is not portable there",
+                        fragment.id, update_fragment_data.execution_location
+                    );
+                    continue;
+                }
+                fragment.execution_location = update_fragment_data.execution_location.clone();
+                applied_updates.push(update_fragment_data.clone());
             }
         }
-        Ok(())
+        applied_updates
     }
 }
@@ -1,5 +1,5 @@
 use crate::configuration::Configuration;
-use crate::fragment_registry::fragment::Fragment;
+use crate::fragment_registry::fragment;
 use crate::fragment_registry::FragmentRegistry;
 use client_registry::ClientRegistry;
 use std::path::PathBuf;
@@ -16,6 +16,8 @@ pub mod fragment_executor;
 mod fragment_registry;
 mod util;
 
+pub use util::error::ApplicationError;
+
 static INIT_CALLED: AtomicBool = AtomicBool::new(false);
 
 pub async fn init(config: Configuration) {
@@ -30,7 +32,7 @@ pub async fn init(config: Configuration) {
         &PathBuf::from(&config.fragments_dir).join("executable_fragments.json"),
     )
     .expect("Unable to read executable_fragments.json file");
-    let fragments = serde_json::from_str::<Vec<Fragment>>(final_fragments_json.as_str())
+    let fragments = fragment::parse_manifest(final_fragments_json.as_str())
         .expect("Unable to parse executable_fragments.json file");
     let fragment_registry = FragmentRegistry::new(fragments);
     let fragment_executor = Arc::new(fragment_executor::wasmtime::Wasmtime::new(
@@ -27,6 +27,13 @@ pub enum ApplicationError {
     WasmerMemoryAccessError(#[from] wasmtime::MemoryAccessError),
     #[error("message: {message}")]
     WasmError { message: String },
+    #[error("{code}: {message}")]
+    FragmentError { code: String, message: String },
+    #[error("{error}\nstack backtrace:\n{backtrace}")]
+    FragmentTrap {
+        error: Box<ApplicationError>,
+        backtrace: String,
+    },
     #[error("WebSocketError")]
     WebSocketError(#[from] warp::Error),
     #[error("DecondingError")]
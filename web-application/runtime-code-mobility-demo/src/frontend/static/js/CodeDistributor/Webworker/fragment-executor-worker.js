@@ -1,15 +1,30 @@
 self.importScripts('../vendor/message-pack/msgpack.js');
 
 self.addEventListener('message', async (e) => {
-    const { wasmBuffer, functionName, parameters } = e.data;
+    const { wasmBuffer, exportPrefix, functionName, parameters, useBigInt } = e.data;
     try {
-        const result = await execute(wasmBuffer, functionName, parameters);
+        const result = await execute(wasmBuffer, exportPrefix || '', functionName, parameters, useBigInt);
         self.postMessage({ result });
     } catch (error) {
-        self.postMessage({ error: error.message });
+        self.postMessage({ error: error.message, code: error.code || 'ExecutionFailed' });
     }
 });
 
+// The newest version of the fragment ABI supported by this executor, see "Fragment ABI" in the wasm-generator readme.
+const SUPPORTED_ABI_VERSION = 3;
+// The first ABI version in which fragments export dcm_free_result to release their result buffers.
+const FREE_RESULT_ABI_VERSION = 2;
+// The first ABI version in which fragments return error envelopes and export dcm_take_panic.
+const ERROR_ENVELOPE_ABI_VERSION = 3;
+// Fragments generated before the ABI was versioned do not export dcm_abi_version.
+const LEGACY_ABI_VERSION = 0;
+const RESPONSE_HEADER_SIZE = 8;
+const STATUS_OK = 0;
+// Decodes 64 bit integers as Int64BE and Uint64BE instances, which are converted to BigInt
+const INT64_CODEC = self.msgpack.createCodec({ int64: true });
+const Int64BE = toInt64(-1n).constructor;
+const Uint64BE = toInt64(0n).constructor;
+
 function toLittleEndianBytes(value) {
     const buffer = new ArrayBuffer(4); // using 4 bytes for 32-bit integer
     const view = new DataView(buffer);
@@ -17,12 +32,57 @@ function toLittleEndianBytes(value) {
     return new Uint8Array(buffer);
 }
 
-async function execute(wasmModule, functionName, params) {
-    let wrapperFunction = "execute__" + functionName;
+// The vendored MessagePack library cannot encode BigInt, only its own 64 bit integer classes, which are obtained by
+// decoding the MessagePack encoding of the value.
+function toInt64(value) {
+    const bytes = new Uint8Array(9);
+    const view = new DataView(bytes.buffer);
+    if (value < 0n) {
+        bytes[0] = 0xd3;
+        view.setBigInt64(1, value);
+    } else {
+        bytes[0] = 0xcf;
+        view.setBigUint64(1, value);
+    }
+    return self.msgpack.decode(bytes, { codec: INT64_CODEC });
+}
+
+// Replaces BigInt values with 64 bit integers the MessagePack library can encode.
+function encodeBigInts(value) {
+    if (typeof value === 'bigint') {
+        return toInt64(value);
+    }
+    if (Array.isArray(value)) {
+        return value.map(encodeBigInts);
+    }
+    if (value !== null && typeof value === 'object' && Object.getPrototypeOf(value) === Object.prototype) {
+        return Object.fromEntries(Object.entries(value).map(([key, item]) => [key, encodeBigInts(item)]));
+    }
+    return value;
+}
+
+// Replaces the 64 bit integers decoded with INT64_CODEC by BigInt values.
+function decodeBigInts(value) {
+    if (value instanceof Int64BE || value instanceof Uint64BE) {
+        return BigInt(value.toString());
+    }
+    if (Array.isArray(value)) {
+        return value.map(decodeBigInts);
+    }
+    if (value !== null && typeof value === 'object' && Object.getPrototypeOf(value) === Object.prototype) {
+        return Object.fromEntries(Object.entries(value).map(([key, item]) => [key, decodeBigInts(item)]));
+    }
+    return value;
+}
+
+async function execute(wasmModule, exportPrefix, functionName, params, useBigInt) {
+    // Fragments built into a bundle export their functions prefixed with their id
+    let wrapperFunction = exportPrefix + "execute__" + functionName;
     const instance = await WebAssembly.instantiate(wasmModule);
+    const abiVersion = getAbiVersion(instance);
     let args = [];
     for (let param of params) {
-        const param_as_bytes = self.msgpack.encode(param);
+        const param_as_bytes = self.msgpack.encode(encodeBigInts(param));
         const length = this.toLittleEndianBytes(param_as_bytes.length);
         while (args.length % 4 != 0) {
             args.push(0);
@@ -31,20 +91,102 @@ async function execute(wasmModule, functionName, params) {
         args.push(...param_as_bytes);
     }
 
-    // Allocate memory in the WebAssembly instance for the byte array.
-    const ptr = instance.exports.alloc(args.length);
+    // Allocate memory in the WebAssembly instance for the byte array, zero sized allocations are not allowed.
+    const ptr = args.length > 0 ? instance.exports.alloc(args.length) : 0;
+    if (args.length > 0 && ptr === 0) {
+        throw new Error(`Fragment failed to allocate ${args.length} bytes`);
+    }
 
     // Get the memory of the WebAssembly instance as a Uint8Array.
     const memory = new Uint8Array(instance.exports.memory.buffer);
     memory.set(args, ptr);
-    const resultPtr = instance.exports[wrapperFunction](ptr, params.length);
-
-    // this.instance.exports.dealloc(ptr, bytes.length);
+    let resultPtr;
+    try {
+        resultPtr = instance.exports[wrapperFunction](ptr, params.length) >>> 0;
+    } catch (trap) {
+        throw takePanic(instance, abiVersion) || fragmentError('Trap', trap.message);
+    }
+    if (args.length > 0) {
+        instance.exports.dealloc(ptr, args.length);
+    }
 
     const memory2 = new Uint8Array(instance.exports.memory.buffer);
 
-    const length = new DataView(memory2.buffer, resultPtr, 4).getUint32(0, true);
-    const resultData = new Uint8Array(memory2.buffer, resultPtr + 4, length);
-    const result = await self.msgpack.decode(resultData);
-    return result;
+    if (abiVersion === LEGACY_ABI_VERSION) {
+        const length = new DataView(memory2.buffer, checkBounds(memory2, resultPtr, 4), 4).getUint32(0, true);
+        const resultData = new Uint8Array(memory2.buffer, checkBounds(memory2, resultPtr + 4, length), length);
+        return decodePayload(resultData, useBigInt);
+    }
+    try {
+        return decodeResponse(memory2, resultPtr, abiVersion, useBigInt);
+    } finally {
+        if (abiVersion >= FREE_RESULT_ABI_VERSION) {
+            instance.exports.dcm_free_result(resultPtr);
+        }
+    }
+}
+
+function fragmentError(code, message) {
+    const error = new Error(message);
+    error.code = code;
+    return error;
+}
+
+// Returns the error describing the panic that made the fragment trap, if the fragment captured one.
+// The instance remains usable after a trap, so the captured message can still be retrieved.
+function takePanic(instance, abiVersion) {
+    if (abiVersion < ERROR_ENVELOPE_ABI_VERSION) {
+        return null;
+    }
+    const panicPtr = instance.exports.dcm_take_panic() >>> 0;
+    if (panicPtr === 0) {
+        return null;
+    }
+    try {
+        decodeResponse(new Uint8Array(instance.exports.memory.buffer), panicPtr, abiVersion);
+        return null;
+    } catch (error) {
+        return error;
+    } finally {
+        instance.exports.dcm_free_result(panicPtr);
+    }
+}
+
+// Fails if a buffer returned by the fragment lies outside of its memory, returns the offset otherwise.
+function checkBounds(memory, offset, length) {
+    if (offset + length > memory.length) {
+        throw new Error(`Fragment returned a buffer of ${length} bytes at ${offset} outside of its memory of ${memory.length} bytes`);
+    }
+    return offset;
+}
+
+// Returns the ABI version the fragment was generated with, failing if it is newer than the supported one.
+function getAbiVersion(instance) {
+    const abiVersion = instance.exports.dcm_abi_version ? instance.exports.dcm_abi_version() : LEGACY_ABI_VERSION;
+    if (abiVersion > SUPPORTED_ABI_VERSION) {
+        throw new Error(`Fragment ABI version ${abiVersion} is not supported, the newest supported version is ${SUPPORTED_ABI_VERSION}`);
+    }
+    return abiVersion;
+}
+
+// Decodes a MessagePack payload, with `useBigInt` 64 bit integers are decoded as BigInt.
+// Without it they are numbers, which lose precision above 2^53.
+function decodePayload(payload, useBigInt) {
+    return useBigInt ? decodeBigInts(self.msgpack.decode(payload, { codec: INT64_CODEC })) : self.msgpack.decode(payload);
+}
+
+// Decodes the 8 byte header and the MessagePack payload following it.
+function decodeResponse(memory, resultPtr, abiVersion, useBigInt = false) {
+    const header = new DataView(memory.buffer, checkBounds(memory, resultPtr, RESPONSE_HEADER_SIZE), RESPONSE_HEADER_SIZE);
+    if (header.getUint8(0) !== abiVersion) {
+        throw new Error(`Fragment response has ABI version ${header.getUint8(0)}, expected ${abiVersion}`);
+    }
+    const length = header.getUint32(4, true);
+    const payloadPtr = checkBounds(memory, resultPtr + RESPONSE_HEADER_SIZE, length);
+    const payload = new Uint8Array(memory.buffer, payloadPtr, length);
+    if (header.getUint8(1) !== STATUS_OK) {
+        const {code, message} = self.msgpack.decode(payload);
+        throw fragmentError(code, message);
+    }
+    return decodePayload(payload, useBigInt);
 }
@@ -3,6 +3,8 @@ import WebworkerPool from "./Webworker/WebworkerPool.js";
 
 export default class FragmentExecutor {
     fragmentMap = new Map();
+    // Compiled modules by file name, so that the module of a bundle is fetched and compiled once for all its fragments
+    moduleMap = new Map();
 
     constructor(fragmentRegistry, configuration) {
         this.configuration = configuration;
@@ -29,25 +31,32 @@ export default class FragmentExecutor {
     }
 
     async loadFragment(id) {
-        let identifier = `${id}.wasm`;
-        const response = await fetch(`${this.configuration.codeDistributorDir}fragments/${identifier}`);
-        const moduleBytes = await response.arrayBuffer();
-        const compiledModule = await WebAssembly.compile(moduleBytes);
-        this.fragmentMap.set(id, compiledModule);
+        const bundle = this.fragmentRegistry.bundleMap.get(id);
+        const identifier = bundle || `${id}.wasm`;
+        if (!this.moduleMap.has(identifier)) {
+            const response = await fetch(`${this.configuration.codeDistributorDir}fragments/${identifier}`);
+            const moduleBytes = await response.arrayBuffer();
+            this.moduleMap.set(identifier, await WebAssembly.compile(moduleBytes));
+        }
+        // The worker calls the functions of a bundled fragment through the exports prefixed with its id
+        const exportPrefix = bundle ? `${id}/` : '';
+        this.fragmentMap.set(id, { compiledModule: this.moduleMap.get(identifier), exportPrefix });
     }
 
-    async execute(fragmentId, functionName, parameters) {
+    async execute(fragmentId, functionName, parameters, useBigInt = false) {
         return new Promise((resolve, reject) => {
             const worker = this.webworkerPool.getWorker();
-            const wasmBuffer = this.fragmentMap.get(fragmentId);
+            const fragment = this.fragmentMap.get(fragmentId);
 
-            if (!wasmBuffer) {
+            if (!fragment) {
                 return reject(new Error(`WASM module for fragment ${fragmentId} not found`));
             }
 
             worker.onmessage = (e) => {
                 if (e.data.error) {
-                    reject(new Error(e.data.error));
+                    const error = new Error(e.data.error);
+                    error.code = e.data.code;
+                    reject(error);
                 } else {
                     const result = e.data.result;
                     resolve(result);
@@ -61,9 +70,11 @@ export default class FragmentExecutor {
             };
 
             worker.postMessage({
-                wasmBuffer: wasmBuffer,
+                wasmBuffer: fragment.compiledModule,
+                exportPrefix: fragment.exportPrefix,
                 functionName: functionName,
                 parameters: parameters,
+                useBigInt: useBigInt,
             });
         });
     }
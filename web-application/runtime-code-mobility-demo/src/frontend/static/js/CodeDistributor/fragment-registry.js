@@ -2,6 +2,8 @@ import internal_event, {INTERNAL_EVENT_TYPES} from "./internal-events.js";
 
 export default class FragmentRegistry {
     fragmentMap = new Map();
+    // Maps the ids of bundled fragments to the wasm module of their bundle
+    bundleMap = new Map();
 
     constructor(configuration) {
         this.configuration = configuration;
@@ -21,8 +23,11 @@ export default class FragmentRegistry {
 
     async fetchFragments() {
         const response = await fetch(`${this.configuration.codeDistributorDir}fragments/executable_fragments.json`);
-        const fragments = await response.json();
+        const manifest = await response.json();
+        // Manifests before version 2 consist of the list of fragments only
+        const fragments = Array.isArray(manifest) ? manifest : manifest.fragments;
         this.fragmentMap = new Map(fragments.map(obj => [obj.id, obj.execution_location]));
+        this.bundleMap = new Map(fragments.filter(obj => obj.bundle).map(obj => [obj.id, obj.bundle]));
     }
 
     update(fragmentId, executionLocation) {
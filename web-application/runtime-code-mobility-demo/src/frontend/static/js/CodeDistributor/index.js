@@ -3,6 +3,7 @@ import FragmentRegistry from "./fragment-registry.js";
 import FragmentExecutor from "./fragment-executor.js";
 import DecisionSystemSimulator from "./decision-system-simulator.js";
 import {EXECUTION_LOCATION} from "./constants.js";
+import {parseJson} from "./utils.js";
 
 export default class CodeDistributionManager {
 
@@ -44,7 +45,11 @@ export default class CodeDistributionManager {
         new DecisionSystemSimulator(this.configuration, auth, this.fragmentRegistry);
     }
 
-    async execute(fragmentId, functionName, parameters) {
+    /**
+     * Executes a function of a fragment on the server or the client.
+     * With `useBigInt`, 64 bit integers are decoded as BigInt where a number could not hold them exactly.
+     */
+    async execute(fragmentId, functionName, parameters, useBigInt = false) {
         const clientIcon = document.querySelector('#client-icon');
         const serverIcon = document.querySelector('#server-icon');
 
@@ -60,15 +65,14 @@ export default class CodeDistributionManager {
                 serverIcon.classList.remove('trigger');
             }, 200);
             let result = await this.webSocketClient.sendMessage(MESSAGE_TYPES.EXECUTE_FUNCTION, data);
-            return JSON.parse(result);
+            return useBigInt ? parseJson(result) : JSON.parse(result);
         } else {
             clientIcon.classList.add('trigger');
             setTimeout(() => {
                 clientIcon.classList.remove('trigger');
             }, 200);
 
-            let result = await this.fragmentExecutor.execute(fragmentId, functionName, parameters);
-            return JSON.parse(result);
+            return await this.fragmentExecutor.execute(fragmentId, functionName, parameters, useBigInt);
         }
     }
 }
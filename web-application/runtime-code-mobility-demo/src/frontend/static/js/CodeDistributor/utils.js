@@ -125,6 +125,36 @@ const uuidv4 = () => {
     );
 }
 
+/**
+ * JSON.parse keeping integers outside of the safe integer range exact, they are returned as BigInt.
+ * The integer literals are quoted with a random marker before parsing, strings are matched first to leave their
+ * content untouched.
+ * @param {string} text The JSON text
+ * @returns {*} the parsed value
+ */
+const parseJson = (text) => {
+    const marker = uuidv4();
+    const quoted = text.replace(/"(?:[^"\\]|\\.)*"|-?\d+(?:\.\d+)?(?:[eE][+-]?\d+)?/g, (token) =>
+        /^-?\d+$/.test(token) && !Number.isSafeInteger(Number(token)) ? `"${marker}${token}"` : token
+    );
+    return JSON.parse(quoted, (key, value) =>
+        typeof value === 'string' && value.startsWith(marker) ? BigInt(value.slice(marker.length)) : value
+    );
+};
+
+/**
+ * JSON.stringify writing BigInt values as integer literals instead of failing.
+ * @param {*} value The value to serialize
+ * @returns {string} the JSON text
+ */
+const stringifyJson = (value) => {
+    const marker = uuidv4();
+    const text = JSON.stringify(value, (key, value) =>
+        typeof value === 'bigint' ? `${marker}${value}${marker}` : value
+    );
+    return text.replace(new RegExp(`"${marker}(-?\\d+)${marker}"`, 'g'), '$1');
+};
+
 export {
     selectElement,
     selectElements,
@@ -136,5 +166,7 @@ export {
     getLang,
     addEventListenerMulti,
     setScrollTop,
-    uuidv4
+    uuidv4,
+    parseJson,
+    stringifyJson
 };
@@ -1,9 +1,10 @@
 import internal_event, {INTERNAL_EVENT_TYPES} from "./internal-events.js";
-import {isUndefined, selectElement, uuidv4} from "./utils.js";
+import {isUndefined, selectElement, stringifyJson, uuidv4} from "./utils.js";
 
 export class MESSAGE_TYPES {
     static EXECUTE_FUNCTION = 'ExecuteFunction';
     static FUNCTION_RESULT = 'FunctionResult';
+    static FUNCTION_ERROR = 'FunctionError';
     static UPDATE_FRAGMENTS = 'UpdateFragments';
 }
 
@@ -56,6 +57,14 @@ export default class WebSocketClient {
                     delete this.pendingRequests[message.message_id];
                 }
                 break;
+            case MESSAGE_TYPES.FUNCTION_ERROR:
+                if (this.pendingRequests[message.message_id]) {
+                    const error = new Error(message.data.message);
+                    error.code = message.data.code;
+                    this.pendingRequests[message.message_id].reject(error);
+                    delete this.pendingRequests[message.message_id];
+                }
+                break;
             case MESSAGE_TYPES.UPDATE_FRAGMENTS:
                 internal_event.emit(INTERNAL_EVENT_TYPES.UPDATE_FRAGMENTS, message);
         }
@@ -95,7 +104,7 @@ export default class WebSocketClient {
                     message_type: eventType,
                     data: data,
                 }
-                this.socket.send(JSON.stringify(message));
+                this.socket.send(stringifyJson(message));
             });
         } else {
             console.error('WebSocket is not open:', this.socket.readyState);